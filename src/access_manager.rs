@@ -1 +1,232 @@
+use std::{borrow::Cow, collections::HashMap};
 
+use crate::{
+    ast::{DataType, Expression, TableConstraint, TableConstraintKind},
+    types::DBError,
+    value::Value,
+};
+
+pub struct Table {
+    pub name: String,
+    pub columns: Vec<String>,
+    /// Declared type of each column, values of untyped columns are kept as given.
+    pub types: Vec<Option<DataType>>,
+    /// DEFAULT expression of each column, NULL being the default without one.
+    pub defaults: Vec<Option<Expression>>,
+    /// Whether each column is NOT NULL, as the columns of a primary key are.
+    pub not_null: Vec<bool>,
+    /// Value of each column for the rows stored before it was added, which
+    /// are kept short instead of being rewritten.
+    pub missing: Vec<Value>,
+    /// Constraints of the table, including those declared with a column,
+    /// each with a name. The executor checks them on every write.
+    pub constraints: Vec<TableConstraint>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new(name: String, columns: Vec<String>) -> Self {
+        Table {
+            name,
+            types: vec![None; columns.len()],
+            defaults: vec![None; columns.len()],
+            not_null: vec![false; columns.len()],
+            missing: vec![Value::Null; columns.len()],
+            constraints: Vec::new(),
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+
+    /// Like `column_index`, but failing if there is no such column.
+    pub fn resolve_column<ERR: DBError>(&self, name: &str) -> Result<usize, ERR> {
+        self.column_index(name).ok_or_else(|| {
+            ERR::cause(&format!(
+                "column {} of relation {} does not exist",
+                name, self.name
+            ))
+        })
+    }
+
+    /// The stored rows, completing those stored before columns were added
+    /// with the missing values of these columns.
+    pub fn scan(&self) -> impl Iterator<Item = Cow<'_, [Value]>> {
+        self.rows.iter().map(|row| {
+            if row.len() == self.columns.len() {
+                return Cow::Borrowed(&row[..]);
+            }
+            let mut row = row.clone();
+            row.extend_from_slice(&self.missing[row.len()..]);
+            Cow::Owned(row)
+        })
+    }
+
+    /// Adds a column at the end, which reads as `missing` in the rows
+    /// already stored.
+    pub fn add_column(
+        &mut self,
+        name: String,
+        data_type: Option<DataType>,
+        default: Option<Expression>,
+        missing: Value,
+    ) {
+        self.columns.push(name);
+        self.types.push(data_type);
+        self.defaults.push(default);
+        self.not_null.push(false);
+        self.missing.push(missing);
+    }
+
+    /// Removes the column at `index` along with its values.
+    pub fn drop_column(&mut self, index: usize) {
+        self.columns.remove(index);
+        self.types.remove(index);
+        self.defaults.remove(index);
+        self.not_null.remove(index);
+        self.missing.remove(index);
+        for row in &mut self.rows {
+            if index < row.len() {
+                row.remove(index);
+            }
+        }
+    }
+
+    /// Fails if `row` has NULL in a NOT NULL column.
+    pub fn check_not_null<ERR: DBError>(&self, row: &[Value]) -> Result<(), ERR> {
+        for (index, value) in row.iter().enumerate() {
+            if self.not_null[index] && *value == Value::Null {
+                return Err(ERR::cause(&format!(
+                    "null value in column {} of relation {} violates not-null constraint",
+                    self.columns[index], self.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `constraint`, naming it after the table and its columns like
+    /// `users_pkey` or `orders_user_id_fkey` when it has no name.
+    pub fn add_constraint<ERR: DBError>(
+        &mut self,
+        mut constraint: TableConstraint,
+    ) -> Result<(), ERR> {
+        let name = match constraint.name.take() {
+            Some(name) => {
+                if self.constraint(&name).is_some() {
+                    return Err(ERR::cause(&format!(
+                        "constraint {} for relation {} already exists",
+                        name, self.name
+                    )));
+                }
+                name
+            }
+            None => {
+                let (columns, suffix) = match &constraint.kind {
+                    TableConstraintKind::PrimaryKey(_) => (&[][..], "pkey"),
+                    TableConstraintKind::Unique(columns) => (&columns[..], "key"),
+                    TableConstraintKind::ForeignKey { columns, .. } => (&columns[..], "fkey"),
+                    TableConstraintKind::Check(_) => (&[][..], "check"),
+                };
+                let mut base = self.name.clone();
+                for column in columns {
+                    base = format!("{}_{}", base, column);
+                }
+                let base = format!("{}_{}", base, suffix);
+                let mut name = base.clone();
+                let mut count = 0;
+                while self.constraint(&name).is_some() {
+                    count += 1;
+                    name = format!("{}{}", base, count);
+                }
+                name
+            }
+        };
+        constraint.name = Some(name);
+        self.constraints.push(constraint);
+        Ok(())
+    }
+
+    pub fn constraint(&self, name: &str) -> Option<&TableConstraint> {
+        self.constraints
+            .iter()
+            .find(|constraint| constraint.name.as_deref() == Some(name))
+    }
+
+    /// Columns of the primary key, if the table has one.
+    pub fn primary_key(&self) -> Option<&[String]> {
+        self.constraints
+            .iter()
+            .find_map(|constraint| match &constraint.kind {
+                TableConstraintKind::PrimaryKey(columns) => Some(&columns[..]),
+                _ => None,
+            })
+    }
+
+    /// Converts `value` for the column at `index`.
+    pub fn cast<ERR: DBError>(&self, index: usize, value: Value) -> Result<Value, ERR> {
+        match &self.types[index] {
+            Some(data_type) => value.cast(data_type),
+            None => Ok(value),
+        }
+    }
+}
+
+/// Keeps every table of the database in memory, keyed by table name.
+#[derive(Default)]
+pub struct AccessManager {
+    tables: HashMap<String, Table>,
+}
+
+impl AccessManager {
+    pub fn new() -> Self {
+        AccessManager::default()
+    }
+
+    pub fn create_table<ERR: DBError>(&mut self, table: Table) -> Result<(), ERR> {
+        if self.tables.contains_key(&table.name) {
+            return Err(ERR::cause(&format!("table {} already exists", table.name)));
+        }
+        self.tables.insert(table.name.clone(), table);
+        Ok(())
+    }
+
+    pub fn drop_table<ERR: DBError>(&mut self, name: &str) -> Result<Table, ERR> {
+        self.tables
+            .remove(name)
+            .ok_or_else(|| ERR::cause(&format!("table {} does not exist", name)))
+    }
+
+    pub fn rename_table<ERR: DBError>(&mut self, name: &str, new_name: &str) -> Result<(), ERR> {
+        if self.tables.contains_key(new_name) {
+            return Err(ERR::cause(&format!("table {} already exists", new_name)));
+        }
+        let mut table = self.drop_table(name)?;
+        table.name = new_name.to_string();
+        self.tables.insert(table.name.clone(), table);
+        Ok(())
+    }
+
+    pub fn tables(&self) -> impl Iterator<Item = &Table> {
+        self.tables.values()
+    }
+
+    pub fn tables_mut(&mut self) -> impl Iterator<Item = &mut Table> {
+        self.tables.values_mut()
+    }
+
+    pub fn table<ERR: DBError>(&self, name: &str) -> Result<&Table, ERR> {
+        self.tables
+            .get(name)
+            .ok_or_else(|| ERR::cause(&format!("table {} does not exist", name)))
+    }
+
+    pub fn table_mut<ERR: DBError>(&mut self, name: &str) -> Result<&mut Table, ERR> {
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| ERR::cause(&format!("table {} does not exist", name)))
+    }
+}
@@ -1,94 +1,490 @@
+use std::fmt;
+
+use crate::value::Value;
+
 #[derive(Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum SQLStatement {
     CreateTable(CreateStatement),
     DropTable(DropStatement),
-    Select(SelectStatement),
+    AlterTable(AlterStatement),
+    Select(Query),
     Insert(InsertStatement),
     Update(UpdateStatement),
     Delete(DeleteStatement),
 }
 #[derive(Debug, PartialEq)]
 pub struct CreateStatement {
-    table_name: String,
-    columns: Vec<ColumnDefinition>,
+    /// `IF NOT EXISTS`, which makes creating an existing table do nothing.
+    pub if_not_exists: bool,
+    pub table_name: String,
+    pub columns: Vec<ColumnDefinition>,
+    pub constraints: Vec<TableConstraint>,
 }
+/// `DROP TABLE [IF EXISTS] name, ... [CASCADE | RESTRICT]`
 #[derive(Debug, PartialEq)]
 pub struct DropStatement {
-    table_name: String,
+    pub table_names: Vec<String>,
+    pub if_exists: bool,
+    pub behavior: DropBehavior,
+}
+/// What DROP does with the objects depending on what it drops.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DropBehavior {
+    /// Refuse to drop, the default.
+    #[default]
+    Restrict,
+    /// Drop the dependent objects too.
+    Cascade,
+}
+/// `ALTER TABLE name operation`
+#[derive(Debug, PartialEq)]
+pub struct AlterStatement {
+    pub table_name: String,
+    pub operation: AlterOperation,
 }
 #[derive(Debug, PartialEq)]
+pub enum AlterOperation {
+    /// `ADD [COLUMN] definition`
+    AddColumn(ColumnDefinition),
+    /// `DROP [COLUMN] name [CASCADE | RESTRICT]`
+    DropColumn {
+        name: String,
+        behavior: DropBehavior,
+    },
+    /// `RENAME [COLUMN] old TO new`
+    RenameColumn { old: String, new: String },
+    /// `RENAME TO name`
+    RenameTable(String),
+    /// `ALTER [COLUMN] name action`
+    AlterColumn {
+        name: String,
+        action: AlterColumnAction,
+    },
+    /// `ADD table_constraint`
+    AddConstraint(TableConstraint),
+    /// `DROP CONSTRAINT name`
+    DropConstraint(String),
+}
+#[derive(Debug, PartialEq)]
+pub enum AlterColumnAction {
+    SetNotNull,
+    DropNotNull,
+    SetDefault(Expression),
+    DropDefault,
+}
+/// A SELECT, or set operations combining the rows of SELECTs.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Query {
+    Select(Box<SelectStatement>),
+    SetOperation(Box<SetOperation>),
+}
+/// `left {UNION | INTERSECT | EXCEPT} [ALL] right`, where the ORDER BY and
+/// limits following the last operand apply to the combined rows.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SetOperation {
+    pub with: Option<With>,
+    pub left: Query,
+    pub operator: SetOperator,
+    /// ALL keeps duplicate rows.
+    pub all: bool,
+    pub right: Query,
+    pub order_by: Vec<OrderByExpression>,
+    pub limit: Option<Expression>,
+    pub offset: Option<Expression>,
+}
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+#[derive(Debug, PartialEq, Clone)]
 pub struct SelectStatement {
-    table_name: String,
-    columns: Vec<String>,
+    pub with: Option<With>,
+    pub distinct: Option<Distinct>,
+    pub from: Vec<TableReference>,
+    pub columns: Vec<SelectItem>,
+    pub where_clause: Option<WhereClause>,
+    pub group_by: Vec<Expression>,
+    pub having: Option<Expression>,
+    pub order_by: Vec<OrderByExpression>,
+    pub limit: Option<Expression>,
+    pub offset: Option<Expression>,
+}
+/// `WITH [RECURSIVE] query, ...`, binding names to queries for the statement
+/// it precedes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct With {
+    /// Under RECURSIVE a query can also reference itself.
+    pub recursive: bool,
+    pub queries: Vec<CommonTableExpression>,
+}
+/// `name [(column, ...)] AS (query)`, where the query of a recursive one is
+/// `non-recursive-term UNION [ALL] recursive-term`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CommonTableExpression {
+    pub name: String,
+    /// Names for the columns of the query, its own column names if empty.
+    pub columns: Vec<String>,
+    pub query: Query,
+}
+#[derive(Debug, PartialEq, Clone)]
+pub struct OrderByExpression {
+    pub expression: Expression,
+    pub ascending: bool,
+    /// `Some(true)` for `NULLS FIRST`, `Some(false)` for `NULLS LAST`.
+    pub nulls_first: Option<bool>,
+}
+/// `DISTINCT` or `DISTINCT ON (expression, ...)` following SELECT.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Distinct {
+    /// Keeps one of each set of equal output rows.
+    Distinct,
+    /// Keeps the first row of each set of rows equal on the expressions.
+    On(Vec<Expression>),
+}
+#[derive(Debug, PartialEq, Clone)]
+pub enum SelectItem {
+    /// `expression [[AS] alias]`
+    Expression {
+        expression: Expression,
+        alias: Option<String>,
+    },
+    /// `*`
+    Wildcard,
+    /// `table.*`
+    QualifiedWildcard(String),
+}
+/// An entry of the FROM clause, where `a, b` lists two entries and
+/// `a JOIN b` forms a single one.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TableReference {
+    /// `name [[AS] alias]`
+    Table { name: String, alias: Option<String> },
+    /// `(SELECT ...) [AS] alias`
+    Derived { subquery: Box<Query>, alias: String },
+    Join {
+        left: Box<TableReference>,
+        operator: JoinOperator,
+        right: Box<TableReference>,
+        constraint: JoinConstraint,
+    },
+}
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JoinOperator {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+#[derive(Debug, PartialEq, Clone)]
+pub enum JoinConstraint {
+    On(Expression),
+    Using(Vec<String>),
+    /// `CROSS JOIN`
+    None,
 }
 #[derive(Debug, PartialEq)]
 pub struct InsertStatement {
-    table_name: String,
-    columns: Vec<String>,
-    values: Vec<Value>,
+    pub with: Option<With>,
+    pub table_name: String,
+    /// Empty when the statement names no columns, which fills the columns
+    /// of the table in order.
+    pub columns: Vec<String>,
+    pub source: InsertSource,
+    /// Empty without a RETURNING clause.
+    pub returning: Vec<SelectItem>,
+}
+/// The rows an INSERT adds.
+#[derive(Debug, PartialEq)]
+pub enum InsertSource {
+    /// One tuple per row of `VALUES (...), (...)`.
+    Values(Vec<Vec<InsertValue>>),
+    Query(Box<Query>),
+}
+#[derive(Debug, PartialEq)]
+pub enum InsertValue {
+    Expression(Expression),
+    /// `DEFAULT`, the default value of the column.
+    Default,
 }
 #[derive(Debug, PartialEq)]
 pub struct UpdateStatement {
-    table_name: String,
-    set_clauses: Vec<SetClause>,
-    where_clause: Option<WhereClause>,
+    pub with: Option<With>,
+    pub table_name: String,
+    pub alias: Option<String>,
+    pub set_clauses: Vec<SetClause>,
+    /// Tables of `UPDATE ... FROM`, joined with the updated table by the
+    /// WHERE clause.
+    pub from: Vec<TableReference>,
+    pub where_clause: Option<WhereClause>,
+    pub returning: Vec<SelectItem>,
 }
 #[derive(Debug, PartialEq)]
 pub struct DeleteStatement {
-    table_name: String,
-    where_clause: Option<WhereClause>,
+    pub with: Option<With>,
+    pub table_name: String,
+    /// Tables of `DELETE ... USING`, joined with the table by the WHERE clause.
+    pub using: Vec<TableReference>,
+    pub where_clause: Option<WhereClause>,
+    pub returning: Vec<SelectItem>,
 }
-#[derive(Debug, PartialEq)]
+/// `name [type] [constraint ...]`
+#[derive(Debug, PartialEq, Clone)]
 pub struct ColumnDefinition {
-    name: String,
+    pub name: String,
+    pub data_type: Option<DataType>,
+    pub constraints: Vec<ColumnConstraint>,
 }
-#[derive(Debug, PartialEq)]
-pub enum Value {
-    StrValue(String),
-    NumValue(f64),
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DataType {
+    /// `INTEGER` or `INT`
+    Integer,
+    BigInt,
+    Real,
+    Text,
+    /// `VARCHAR[(length)]`
+    Varchar(Option<u32>),
+    /// `BOOLEAN` or `BOOL`
+    Boolean,
+    Blob,
+    Date,
+    Timestamp,
+    /// `DECIMAL[(precision[, scale])]` or `NUMERIC[...]`
+    Decimal {
+        precision: Option<u32>,
+        scale: Option<u32>,
+    },
+}
+/// `[CONSTRAINT name] option` following the type of a column.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColumnConstraint {
+    pub name: Option<String>,
+    pub option: ColumnOption,
+}
+#[derive(Debug, PartialEq, Clone)]
+pub enum ColumnOption {
+    NotNull,
+    Null,
+    Default(Expression),
+    Unique,
+    PrimaryKey,
+    Check(Expression),
+    References(ForeignKeyReference),
+}
+/// `REFERENCES table [(column, ...)]`, where no columns stand for the
+/// primary key of the table.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForeignKeyReference {
+    pub table: String,
+    pub columns: Vec<String>,
+}
+/// `[CONSTRAINT name] constraint` following the columns of a table.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TableConstraint {
+    pub name: Option<String>,
+    pub kind: TableConstraintKind,
+}
+#[derive(Debug, PartialEq, Clone)]
+pub enum TableConstraintKind {
+    PrimaryKey(Vec<String>),
+    Unique(Vec<String>),
+    /// `FOREIGN KEY (column, ...) REFERENCES ...`
+    ForeignKey {
+        columns: Vec<String>,
+        references: ForeignKeyReference,
+    },
+    Check(Expression),
 }
 #[derive(Debug, PartialEq)]
 pub struct SetClause {
-    field: String,
-    value: Value,
+    pub field: String,
+    pub value: Expression,
 }
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct WhereClause {
-    condition: Expression,
+    pub condition: Expression,
 }
-#[derive(Debug, PartialEq)]
-pub struct Expression {
-    column: String,
-    value: Value,
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expression {
+    Column(String),
+    /// `table.column`
+    QualifiedColumn {
+        table: String,
+        column: String,
+    },
+    Literal(Value),
+    /// A parameter placeholder, by the 0-based index of its bound value.
+    Parameter(usize),
+    UnaryOperation {
+        operator: UnaryOperator,
+        operand: Box<Expression>,
+    },
+    BinaryOperation {
+        left: Box<Expression>,
+        operator: BinaryOperator,
+        right: Box<Expression>,
+    },
+    /// `expression [NOT] IN (list)`
+    InList {
+        expression: Box<Expression>,
+        list: Vec<Expression>,
+        negated: bool,
+    },
+    /// `expression [NOT] BETWEEN low AND high`
+    Between {
+        expression: Box<Expression>,
+        low: Box<Expression>,
+        high: Box<Expression>,
+        negated: bool,
+    },
+    /// `expression [NOT] LIKE|ILIKE pattern [ESCAPE 'c']`, `%` matches any
+    /// sequence of characters and `_` matches a single character. `ESCAPE ''`
+    /// is the same as no ESCAPE.
+    Like {
+        expression: Box<Expression>,
+        pattern: Box<Expression>,
+        escape: Option<char>,
+        case_insensitive: bool,
+        negated: bool,
+    },
+    /// `expression IS [NOT] NULL`
+    IsNull {
+        expression: Box<Expression>,
+        negated: bool,
+    },
+    /// `(SELECT ...)` returning at most one row of one column
+    Subquery(Box<Query>),
+    /// `expression [NOT] IN (SELECT ...)`
+    InSubquery {
+        expression: Box<Expression>,
+        subquery: Box<Query>,
+        negated: bool,
+    },
+    /// `EXISTS (SELECT ...)`
+    Exists(Box<Query>),
+    /// `name([DISTINCT] arguments)`, `wildcard` is set for `name(*)`
+    Function {
+        name: String,
+        arguments: Vec<Expression>,
+        distinct: bool,
+        wildcard: bool,
+    },
+}
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnaryOperator {
+    Plus,
+    Minus,
+    Not,
+}
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BinaryOperator {
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+    And,
+    Or,
 }
 
 impl CreateStatement {
     pub fn new(table_name: String, columns: Vec<ColumnDefinition>) -> Self {
         CreateStatement {
+            if_not_exists: false,
             table_name,
             columns,
+            constraints: Vec::new(),
         }
     }
 }
 
+impl OrderByExpression {
+    pub fn new(expression: Expression, ascending: bool, nulls_first: Option<bool>) -> Self {
+        OrderByExpression {
+            expression,
+            ascending,
+            nulls_first,
+        }
+    }
+
+    /// Whether nulls come before other values, by default they sort as if
+    /// larger than any other value.
+    pub fn nulls_first(&self) -> bool {
+        self.nulls_first.unwrap_or(!self.ascending)
+    }
+}
+
 impl ColumnDefinition {
     pub fn new(name: String) -> Self {
-        ColumnDefinition { name }
+        ColumnDefinition {
+            name,
+            data_type: None,
+            constraints: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataType::Integer => write!(f, "INTEGER"),
+            DataType::BigInt => write!(f, "BIGINT"),
+            DataType::Real => write!(f, "REAL"),
+            DataType::Text => write!(f, "TEXT"),
+            DataType::Varchar(None) => write!(f, "VARCHAR"),
+            DataType::Varchar(Some(length)) => write!(f, "VARCHAR({})", length),
+            DataType::Boolean => write!(f, "BOOLEAN"),
+            DataType::Blob => write!(f, "BLOB"),
+            DataType::Date => write!(f, "DATE"),
+            DataType::Timestamp => write!(f, "TIMESTAMP"),
+            DataType::Decimal { precision, scale } => {
+                write!(f, "DECIMAL")?;
+                match (precision, scale) {
+                    (Some(precision), Some(scale)) => write!(f, "({}, {})", precision, scale),
+                    (Some(precision), None) => write!(f, "({})", precision),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl AlterStatement {
+    pub fn new(table_name: String, operation: AlterOperation) -> Self {
+        AlterStatement {
+            table_name,
+            operation,
+        }
     }
 }
 
 impl DropStatement {
-    pub fn new(table_name: String) -> Self {
-        DropStatement { table_name }
+    pub fn new(table_names: Vec<String>) -> Self {
+        DropStatement {
+            table_names,
+            if_exists: false,
+            behavior: DropBehavior::Restrict,
+        }
     }
 }
 
 impl InsertStatement {
-    pub fn new(table_name: String, columns: Vec<String>, values: Vec<Value>) -> Self {
+    pub fn new(table_name: String, columns: Vec<String>, source: InsertSource) -> Self {
         InsertStatement {
+            with: None,
             table_name,
             columns,
-            values,
+            source,
+            returning: Vec::new(),
         }
     }
 }
@@ -96,8 +492,11 @@ impl InsertStatement {
 impl DeleteStatement {
     pub fn new(table_name: String, where_clause: Option<WhereClause>) -> Self {
         DeleteStatement {
+            with: None,
             table_name,
+            using: Vec::new(),
             where_clause,
+            returning: Vec::new(),
         }
     }
 }
@@ -109,8 +508,132 @@ impl WhereClause {
 }
 
 impl Expression {
-    pub fn new(column: String, value: Value) -> Self {
-        Expression { column, value }
+    pub fn unary(operator: UnaryOperator, operand: Expression) -> Self {
+        Expression::UnaryOperation {
+            operator,
+            operand: Box::new(operand),
+        }
+    }
+
+    pub fn binary(left: Expression, operator: BinaryOperator, right: Expression) -> Self {
+        Expression::BinaryOperation {
+            left: Box::new(left),
+            operator,
+            right: Box::new(right),
+        }
+    }
+
+    pub fn function(name: &str, arguments: Vec<Expression>) -> Self {
+        Expression::Function {
+            name: name.to_string(),
+            arguments,
+            distinct: false,
+            wildcard: false,
+        }
+    }
+
+    /// Whether this is a call of one of the aggregate functions COUNT, SUM,
+    /// AVG, MIN or MAX.
+    pub fn is_aggregate(&self) -> bool {
+        match self {
+            Expression::Function { name, .. } => matches!(
+                name.to_uppercase().as_str(),
+                "COUNT" | "SUM" | "AVG" | "MIN" | "MAX"
+            ),
+            _ => false,
+        }
+    }
+
+    pub fn contains_aggregate(&self) -> bool {
+        self.is_aggregate()
+            || self
+                .children()
+                .into_iter()
+                .any(Expression::contains_aggregate)
+    }
+
+    /// The direct subexpressions of this expression, which do not include
+    /// the expressions of subqueries.
+    pub fn children(&self) -> Vec<&Expression> {
+        match self {
+            Expression::Column(_)
+            | Expression::QualifiedColumn { .. }
+            | Expression::Literal(_)
+            | Expression::Parameter(_) => Vec::new(),
+            Expression::UnaryOperation { operand, .. } => vec![operand],
+            Expression::BinaryOperation { left, right, .. } => vec![left, right],
+            Expression::InList {
+                expression, list, ..
+            } => std::iter::once(expression.as_ref()).chain(list).collect(),
+            Expression::Between {
+                expression,
+                low,
+                high,
+                ..
+            } => vec![expression, low, high],
+            Expression::Like {
+                expression,
+                pattern,
+                ..
+            } => vec![expression, pattern],
+            Expression::IsNull { expression, .. } | Expression::InSubquery { expression, .. } => {
+                vec![expression]
+            }
+            Expression::Subquery(_) | Expression::Exists(_) => Vec::new(),
+            Expression::Function { arguments, .. } => arguments.iter().collect(),
+        }
+    }
+
+    /// Mutable counterpart of `children`.
+    pub fn children_mut(&mut self) -> Vec<&mut Expression> {
+        match self {
+            Expression::Column(_)
+            | Expression::QualifiedColumn { .. }
+            | Expression::Literal(_)
+            | Expression::Parameter(_) => Vec::new(),
+            Expression::UnaryOperation { operand, .. } => vec![operand],
+            Expression::BinaryOperation { left, right, .. } => vec![left, right],
+            Expression::InList {
+                expression, list, ..
+            } => std::iter::once(expression.as_mut()).chain(list).collect(),
+            Expression::Between {
+                expression,
+                low,
+                high,
+                ..
+            } => vec![expression, low, high],
+            Expression::Like {
+                expression,
+                pattern,
+                ..
+            } => vec![expression, pattern],
+            Expression::IsNull { expression, .. } | Expression::InSubquery { expression, .. } => {
+                vec![expression]
+            }
+            Expression::Subquery(_) | Expression::Exists(_) => Vec::new(),
+            Expression::Function { arguments, .. } => arguments.iter_mut().collect(),
+        }
+    }
+
+    /// The subqueries of this expression, not including those nested in them.
+    pub fn subqueries(&self) -> Vec<&Query> {
+        match self {
+            Expression::Subquery(subquery) | Expression::Exists(subquery) => vec![subquery],
+            Expression::InSubquery {
+                expression,
+                subquery,
+                ..
+            } => {
+                let mut subqueries = expression.subqueries();
+                subqueries.push(subquery);
+                subqueries
+            }
+            _ => self
+                .children()
+                .into_iter()
+                .flat_map(Expression::subqueries)
+                .collect(),
+        }
     }
 }
 
@@ -121,22 +644,260 @@ impl UpdateStatement {
         where_clause: Option<WhereClause>,
     ) -> Self {
         UpdateStatement {
+            with: None,
             table_name,
+            alias: None,
             set_clauses,
+            from: Vec::new(),
             where_clause,
+            returning: Vec::new(),
         }
     }
+
+    /// The name columns of the updated table are qualified with, the alias if given.
+    pub fn qualifier(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.table_name)
+    }
 }
 
 impl SetClause {
-    pub fn new(field: String, value: Value) -> Self {
+    pub fn new(field: String, value: Expression) -> Self {
         SetClause { field, value }
     }
 }
 
-impl SelectStatement {
-    pub fn new(table_name: String, columns: Vec<String>) -> Self {
-        SelectStatement { table_name, columns }
+impl SelectItem {
+    pub fn expression(expression: Expression) -> Self {
+        SelectItem::Expression {
+            expression,
+            alias: None,
+        }
+    }
+}
+
+impl TableReference {
+    pub fn table(name: &str) -> Self {
+        TableReference::Table {
+            name: name.to_string(),
+            alias: None,
+        }
+    }
+
+    /// The name columns of this table are qualified with, the alias if given.
+    pub fn qualifier(&self) -> Option<&str> {
+        match self {
+            TableReference::Table { name, alias } => Some(alias.as_deref().unwrap_or(name)),
+            TableReference::Derived { alias, .. } => Some(alias),
+            TableReference::Join { .. } => None,
+        }
+    }
+
+    fn references_table(&self, table: &str) -> bool {
+        match self {
+            TableReference::Table { name, .. } => name == table,
+            TableReference::Derived { subquery, .. } => subquery.references_table(table),
+            TableReference::Join {
+                left,
+                right,
+                constraint,
+                ..
+            } => {
+                left.references_table(table)
+                    || right.references_table(table)
+                    || match constraint {
+                        JoinConstraint::On(condition) => condition
+                            .subqueries()
+                            .into_iter()
+                            .any(|subquery| subquery.references_table(table)),
+                        _ => false,
+                    }
+            }
+        }
     }
 }
 
+impl CommonTableExpression {
+    /// Whether this is a query of a WITH RECURSIVE that reads its own rows.
+    pub fn is_recursive(&self, with: &With) -> bool {
+        with.recursive && self.query.references_table(&self.name)
+    }
+}
+
+impl Query {
+    pub fn with(&self) -> Option<&With> {
+        match self {
+            Query::Select(statement) => statement.with.as_ref(),
+            Query::SetOperation(operation) => operation.with.as_ref(),
+        }
+    }
+
+    /// Whether this query or one of its subqueries reads `table`, which a
+    /// WITH query of the same name hides from the query.
+    pub fn references_table(&self, table: &str) -> bool {
+        if let Some(with) = self.with() {
+            for query in &with.queries {
+                let hidden = query.name == table;
+                if !(hidden && with.recursive) && query.query.references_table(table) {
+                    return true;
+                }
+                if hidden {
+                    return false;
+                }
+            }
+        }
+        match self {
+            Query::Select(statement) => {
+                statement
+                    .from
+                    .iter()
+                    .any(|reference| reference.references_table(table))
+                    || statement
+                        .expressions()
+                        .into_iter()
+                        .flat_map(Expression::subqueries)
+                        .any(|subquery| subquery.references_table(table))
+            }
+            Query::SetOperation(operation) => {
+                operation.left.references_table(table)
+                    || operation.right.references_table(table)
+                    || operation
+                        .expressions()
+                        .into_iter()
+                        .flat_map(Expression::subqueries)
+                        .any(|subquery| subquery.references_table(table))
+            }
+        }
+    }
+}
+
+impl From<SelectStatement> for Query {
+    fn from(statement: SelectStatement) -> Self {
+        Query::Select(Box::new(statement))
+    }
+}
+
+impl SetOperation {
+    pub fn new(left: Query, operator: SetOperator, all: bool, right: Query) -> Self {
+        SetOperation {
+            with: None,
+            left,
+            operator,
+            all,
+            right,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// The expressions of ORDER BY, LIMIT and OFFSET.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        let mut expressions: Vec<&Expression> = self
+            .order_by
+            .iter()
+            .map(|order_by| &order_by.expression)
+            .collect();
+        expressions.extend(&self.limit);
+        expressions.extend(&self.offset);
+        expressions
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOperator::Plus => write!(f, "+"),
+            UnaryOperator::Minus => write!(f, "-"),
+            UnaryOperator::Not => write!(f, "NOT"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryOperator::Plus => write!(f, "+"),
+            BinaryOperator::Minus => write!(f, "-"),
+            BinaryOperator::Multiply => write!(f, "*"),
+            BinaryOperator::Divide => write!(f, "/"),
+            BinaryOperator::Modulo => write!(f, "%"),
+            BinaryOperator::Equal => write!(f, "="),
+            BinaryOperator::NotEqual => write!(f, "<>"),
+            BinaryOperator::LessThan => write!(f, "<"),
+            BinaryOperator::LessThanOrEqual => write!(f, "<="),
+            BinaryOperator::GreaterThan => write!(f, ">"),
+            BinaryOperator::GreaterThanOrEqual => write!(f, ">="),
+            BinaryOperator::And => write!(f, "AND"),
+            BinaryOperator::Or => write!(f, "OR"),
+        }
+    }
+}
+
+impl fmt::Display for SetOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetOperator::Union => write!(f, "UNION"),
+            SetOperator::Intersect => write!(f, "INTERSECT"),
+            SetOperator::Except => write!(f, "EXCEPT"),
+        }
+    }
+}
+
+impl SelectStatement {
+    /// The expressions of every clause but FROM.
+    pub fn expressions(&self) -> Vec<&Expression> {
+        let mut expressions = self.source_expressions();
+        expressions.extend(self.sort_expressions());
+        expressions
+    }
+
+    /// The expressions of every clause but FROM, DISTINCT ON and ORDER BY.
+    pub fn source_expressions(&self) -> Vec<&Expression> {
+        let mut expressions = Vec::new();
+        for item in &self.columns {
+            if let SelectItem::Expression { expression, .. } = item {
+                expressions.push(expression);
+            }
+        }
+        expressions.extend(
+            self.where_clause
+                .iter()
+                .map(|where_clause| &where_clause.condition),
+        );
+        expressions.extend(&self.group_by);
+        expressions.extend(&self.having);
+        expressions.extend(&self.limit);
+        expressions.extend(&self.offset);
+        expressions
+    }
+
+    /// The DISTINCT ON and ORDER BY expressions, which may also name output
+    /// columns.
+    pub fn sort_expressions(&self) -> Vec<&Expression> {
+        let mut expressions = Vec::new();
+        if let Some(Distinct::On(distinct_on)) = &self.distinct {
+            expressions.extend(distinct_on);
+        }
+        expressions.extend(self.order_by.iter().map(|order_by| &order_by.expression));
+        expressions
+    }
+
+    pub fn new(
+        from: Vec<TableReference>,
+        columns: Vec<SelectItem>,
+        where_clause: Option<WhereClause>,
+    ) -> Self {
+        SelectStatement {
+            with: None,
+            distinct: None,
+            from,
+            columns,
+            where_clause,
+            group_by: Vec::new(),
+            having: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+}
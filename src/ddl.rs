@@ -1,6 +1,6 @@
 use crate::types::{TableName, TableValue};
 
-enum DDL<T> {
+pub enum DDL<T> {
     CreateTable {
         table_name: TableName,
         columns: Vec<TableValue<T>>,
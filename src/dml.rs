@@ -1,6 +1,6 @@
 use crate::types::{TableName, TableValue};
 
-enum DML<T> {
+pub enum DML<T> {
     Insert {
         table_name: TableName,
         values: Vec<TableValue<T>>,
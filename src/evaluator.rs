@@ -0,0 +1,697 @@
+use std::{
+    cmp::Ordering,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    ast::{BinaryOperator, Expression, Query, UnaryOperator},
+    types::DBError,
+    value::{Decimal, Value},
+};
+
+/// Resolves the column references of an expression.
+pub trait RowContext {
+    /// Value of column `name`, of table `table` when the reference is qualified.
+    fn column<ERR: DBError>(&self, table: Option<&str>, name: &str) -> Result<Value, ERR>;
+
+    /// Value of an aggregate function call, only known to contexts spanning a
+    /// group of rows.
+    fn aggregate<ERR: DBError>(&self, function: &Expression) -> Result<Value, ERR> {
+        let _ = function;
+        Err(ERR::cause("aggregate functions are not allowed here"))
+    }
+
+    /// Value bound to the parameter at `index` for the statement being executed.
+    fn parameter<ERR: DBError>(&self, index: usize) -> Result<Value, ERR> {
+        Err(ERR::cause(&format!(
+            "no value is bound to parameter ${}",
+            index + 1
+        )))
+    }
+
+    /// Result rows of a subquery, which may reference the columns of this row.
+    fn subquery<ERR: DBError>(&self, subquery: &Query) -> Result<Rc<Vec<Vec<Value>>>, ERR> {
+        let _ = subquery;
+        Err(ERR::cause("subqueries are not allowed here"))
+    }
+
+    /// Time the statement being executed started at, which `now()` returns.
+    fn statement_time(&self) -> i64 {
+        current_time()
+    }
+}
+
+/// Microseconds since 1970-01-01 00:00:00 UTC.
+pub fn current_time() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_micros() as i64)
+}
+
+/// Context of expressions that may not reference any column, like `LIMIT 10`,
+/// holding the values bound to the parameters of the statement.
+pub struct EmptyRow<'a>(pub &'a [Value]);
+impl RowContext for EmptyRow<'_> {
+    fn column<ERR: DBError>(&self, _table: Option<&str>, name: &str) -> Result<Value, ERR> {
+        Err(ERR::cause(&format!("column {} is not allowed here", name)))
+    }
+
+    fn parameter<ERR: DBError>(&self, index: usize) -> Result<Value, ERR> {
+        self.0
+            .get(index)
+            .cloned()
+            .ok_or_else(|| ERR::cause(&format!("no value is bound to parameter ${}", index + 1)))
+    }
+}
+
+pub struct ExpressionEvaluator;
+impl ExpressionEvaluator {
+    pub fn evaluate<C, ERR>(expression: &Expression, row: &C) -> Result<Value, ERR>
+    where
+        C: RowContext,
+        ERR: DBError,
+    {
+        match expression {
+            Expression::Column(name) => row.column(None, name),
+            Expression::QualifiedColumn { table, column } => row.column(Some(table), column),
+            Expression::Literal(value) => Ok(value.clone()),
+            Expression::Parameter(index) => row.parameter(*index),
+            Expression::UnaryOperation { operator, operand } => {
+                let value = Self::evaluate(operand, row)?;
+                Self::unary_operation(*operator, value)
+            }
+            Expression::BinaryOperation {
+                left,
+                operator,
+                right,
+            } => {
+                let left = Self::evaluate(left, row)?;
+                let right = Self::evaluate(right, row)?;
+                Self::binary_operation(left, *operator, right)
+            }
+            Expression::InList {
+                expression,
+                list,
+                negated,
+            } => {
+                let value = Self::evaluate(expression, row)?;
+                let mut result = Value::Boolean(false);
+                for item in list {
+                    let item = Self::evaluate(item, row)?;
+                    match Value::compare(&value, &item)? {
+                        Some(Ordering::Equal) => {
+                            result = Value::Boolean(true);
+                            break;
+                        }
+                        None => result = Value::Null,
+                        _ => {}
+                    }
+                }
+                Self::negate_if(result, *negated)
+            }
+            Expression::Between {
+                expression,
+                low,
+                high,
+                negated,
+            } => {
+                let value = Self::evaluate(expression, row)?;
+                let low = Self::evaluate(low, row)?;
+                let high = Self::evaluate(high, row)?;
+                let above_low =
+                    Self::binary_operation(value.clone(), BinaryOperator::GreaterThanOrEqual, low)?;
+                let below_high =
+                    Self::binary_operation(value, BinaryOperator::LessThanOrEqual, high)?;
+                let result = Self::binary_operation(above_low, BinaryOperator::And, below_high)?;
+                Self::negate_if(result, *negated)
+            }
+            Expression::Like {
+                expression,
+                pattern,
+                escape,
+                case_insensitive,
+                negated,
+            } => {
+                let value = Self::evaluate(expression, row)?;
+                let pattern = Self::evaluate(pattern, row)?;
+                let result = match (value, pattern) {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::Text(value), Value::Text(pattern)) => {
+                        Value::Boolean(like(&value, &pattern, *escape, *case_insensitive))
+                    }
+                    _ => return Err(ERR::cause("LIKE requires text operands")),
+                };
+                Self::negate_if(result, *negated)
+            }
+            Expression::IsNull {
+                expression,
+                negated,
+            } => {
+                let is_null = Self::evaluate(expression, row)? == Value::Null;
+                Ok(Value::Boolean(is_null != *negated))
+            }
+            Expression::Subquery(subquery) => {
+                let rows = Self::single_column(row.subquery(subquery)?)?;
+                match rows.as_slice() {
+                    [] => Ok(Value::Null),
+                    [row] => Ok(row[0].clone()),
+                    _ => Err(ERR::cause(
+                        "more than one row returned by a subquery used as an expression",
+                    )),
+                }
+            }
+            Expression::InSubquery {
+                expression,
+                subquery,
+                negated,
+            } => {
+                let value = Self::evaluate(expression, row)?;
+                let rows = Self::single_column(row.subquery(subquery)?)?;
+                let mut result = Value::Boolean(false);
+                for item in rows.iter() {
+                    match Value::compare(&value, &item[0])? {
+                        Some(Ordering::Equal) => {
+                            result = Value::Boolean(true);
+                            break;
+                        }
+                        None => result = Value::Null,
+                        _ => {}
+                    }
+                }
+                Self::negate_if(result, *negated)
+            }
+            Expression::Exists(subquery) => Ok(Value::Boolean(!row.subquery(subquery)?.is_empty())),
+            Expression::Function { .. } if expression.is_aggregate() => row.aggregate(expression),
+            Expression::Function {
+                name, arguments, ..
+            } => match name.to_lowercase().as_str() {
+                "now" | "current_timestamp" if arguments.is_empty() => {
+                    Ok(Value::Timestamp(row.statement_time()))
+                }
+                _ => Err(ERR::cause(&format!("function {} does not exist", name))),
+            },
+        }
+    }
+
+    /// Folds the argument values of an aggregate function over a group,
+    /// ignoring NULLs. Only COUNT of no values is not NULL.
+    pub fn aggregate<ERR: DBError>(
+        name: &str,
+        distinct: bool,
+        values: Vec<Value>,
+    ) -> Result<Value, ERR> {
+        let mut values: Vec<Value> = values
+            .into_iter()
+            .filter(|value| *value != Value::Null)
+            .collect();
+        if distinct {
+            values.sort_by(Value::total_cmp);
+            values.dedup_by(|left, right| left.total_cmp(right) == Ordering::Equal);
+        }
+        let name = name.to_uppercase();
+        if name == "COUNT" {
+            return Ok(Value::Int64(values.len() as i64));
+        }
+        let Some(first) = values.first().cloned() else {
+            return Ok(Value::Null);
+        };
+        match name.as_str() {
+            "SUM" | "AVG" => {
+                let count = values.len();
+                let mut sum = Value::Int64(0);
+                for value in values {
+                    if !value.is_numeric() {
+                        return Err(ERR::cause(&format!("{} requires numbers", name)));
+                    }
+                    sum = Self::arithmetic(sum, BinaryOperator::Plus, value)?;
+                }
+                if name == "SUM" {
+                    return Ok(sum);
+                }
+                // the average of integers is exact
+                if let Value::Int64(sum) = sum {
+                    return Self::arithmetic(
+                        Value::Decimal(Decimal::from(sum)),
+                        BinaryOperator::Divide,
+                        Value::Int64(count as i64),
+                    );
+                }
+                Self::arithmetic(sum, BinaryOperator::Divide, Value::Int64(count as i64))
+            }
+            _ => {
+                let wanted = if name == "MIN" {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                let mut result = first;
+                for value in values {
+                    if Value::compare(&value, &result)? == Some(wanted) {
+                        result = value;
+                    }
+                }
+                Ok(result)
+            }
+        }
+    }
+
+    fn single_column<ERR: DBError>(rows: Rc<Vec<Vec<Value>>>) -> Result<Rc<Vec<Vec<Value>>>, ERR> {
+        match rows.first() {
+            Some(row) if row.len() != 1 => Err(ERR::cause("subquery must return only one column")),
+            _ => Ok(rows),
+        }
+    }
+
+    /// Evaluates a condition, where only TRUE passes and FALSE and NULL do not.
+    pub fn matches<C, ERR>(condition: &Expression, row: &C) -> Result<bool, ERR>
+    where
+        C: RowContext,
+        ERR: DBError,
+    {
+        match Self::evaluate(condition, row)? {
+            Value::Boolean(value) => Ok(value),
+            Value::Null => Ok(false),
+            _ => Err(ERR::cause("condition must be a boolean expression")),
+        }
+    }
+
+    fn negate_if<ERR: DBError>(value: Value, negated: bool) -> Result<Value, ERR> {
+        if negated {
+            Self::unary_operation(UnaryOperator::Not, value)
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn unary_operation<ERR: DBError>(operator: UnaryOperator, value: Value) -> Result<Value, ERR> {
+        match (operator, value) {
+            (_, Value::Null) => Ok(Value::Null),
+            (UnaryOperator::Not, Value::Boolean(value)) => Ok(Value::Boolean(!value)),
+            (UnaryOperator::Minus, Value::Int64(value)) => value
+                .checked_neg()
+                .map(Value::Int64)
+                .ok_or_else(|| ERR::cause("bigint out of range")),
+            (UnaryOperator::Minus, Value::Float64(value)) => Ok(Value::Float64(-value)),
+            (UnaryOperator::Minus, Value::Decimal(value)) => value
+                .checked_neg()
+                .map(Value::Decimal)
+                .ok_or_else(|| ERR::cause("numeric value out of range")),
+            (UnaryOperator::Plus, value) if value.is_numeric() => Ok(value),
+            (operator, value) => Err(ERR::cause(&format!(
+                "cannot apply {} to {}",
+                operator,
+                value.type_name().unwrap_or_default()
+            ))),
+        }
+    }
+
+    fn binary_operation<ERR: DBError>(
+        left: Value,
+        operator: BinaryOperator,
+        right: Value,
+    ) -> Result<Value, ERR> {
+        match operator {
+            BinaryOperator::And | BinaryOperator::Or => {
+                let truth = |value: &Value| match value {
+                    Value::Boolean(value) => Ok(Some(*value)),
+                    Value::Null => Ok(None),
+                    _ => Err(ERR::cause(&format!(
+                        "argument of {:?} must be boolean",
+                        operator
+                    ))),
+                };
+                // SQL three-valued logic, NULL meaning unknown
+                let result = match (operator, truth(&left)?, truth(&right)?) {
+                    (BinaryOperator::And, Some(false), _)
+                    | (BinaryOperator::And, _, Some(false)) => Some(false),
+                    (BinaryOperator::Or, Some(true), _) | (BinaryOperator::Or, _, Some(true)) => {
+                        Some(true)
+                    }
+                    (BinaryOperator::And, Some(left), Some(right)) => Some(left && right),
+                    (_, Some(left), Some(right)) => Some(left || right),
+                    _ => None,
+                };
+                Ok(result.map_or(Value::Null, Value::Boolean))
+            }
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::LessThan
+            | BinaryOperator::LessThanOrEqual
+            | BinaryOperator::GreaterThan
+            | BinaryOperator::GreaterThanOrEqual => {
+                let Some(ordering) = Value::compare(&left, &right)? else {
+                    return Ok(Value::Null);
+                };
+                let result = match operator {
+                    BinaryOperator::Equal => ordering == Ordering::Equal,
+                    BinaryOperator::NotEqual => ordering != Ordering::Equal,
+                    BinaryOperator::LessThan => ordering == Ordering::Less,
+                    BinaryOperator::LessThanOrEqual => ordering != Ordering::Greater,
+                    BinaryOperator::GreaterThan => ordering == Ordering::Greater,
+                    _ => ordering != Ordering::Less,
+                };
+                Ok(Value::Boolean(result))
+            }
+            _ => Self::arithmetic(left, operator, right),
+        }
+    }
+
+    /// Applies an arithmetic operator. Numbers take the type of the operand
+    /// that is the most general of bigint, numeric and double precision, and
+    /// a date plus or minus a bigint is a date some days apart.
+    fn arithmetic<ERR: DBError>(
+        left: Value,
+        operator: BinaryOperator,
+        right: Value,
+    ) -> Result<Value, ERR> {
+        let divides = matches!(operator, BinaryOperator::Divide | BinaryOperator::Modulo);
+        match (left, right) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Int64(left), Value::Int64(right)) => {
+                let result = match operator {
+                    BinaryOperator::Plus => left.checked_add(right),
+                    BinaryOperator::Minus => left.checked_sub(right),
+                    BinaryOperator::Multiply => left.checked_mul(right),
+                    _ if right == 0 => return Err(ERR::cause("division by zero")),
+                    BinaryOperator::Divide => left.checked_div(right),
+                    // the remainder is 0, although i64::MIN / -1 overflows
+                    _ if right == -1 => Some(0),
+                    _ => left.checked_rem(right),
+                };
+                result
+                    .map(Value::Int64)
+                    .ok_or_else(|| ERR::cause("bigint out of range"))
+            }
+            (Value::Date(date), Value::Int64(days))
+                if matches!(operator, BinaryOperator::Plus | BinaryOperator::Minus) =>
+            {
+                let date = match operator {
+                    BinaryOperator::Plus => (date as i64).checked_add(days),
+                    _ => (date as i64).checked_sub(days),
+                };
+                date.and_then(|date| i32::try_from(date).ok())
+                    .map(Value::Date)
+                    .ok_or_else(|| ERR::cause("date out of range"))
+            }
+            (Value::Int64(days), Value::Date(date)) if operator == BinaryOperator::Plus => {
+                Self::arithmetic(Value::Date(date), operator, Value::Int64(days))
+            }
+            (Value::Date(left), Value::Date(right)) if operator == BinaryOperator::Minus => {
+                Ok(Value::Int64(left as i64 - right as i64))
+            }
+            (left @ Value::Float64(_), right) | (left, right @ Value::Float64(_))
+                if left.is_numeric() && right.is_numeric() =>
+            {
+                let (left, right) = (left.to_f64(), right.to_f64());
+                let result = match operator {
+                    BinaryOperator::Plus => left + right,
+                    BinaryOperator::Minus => left - right,
+                    BinaryOperator::Multiply => left * right,
+                    _ if right == 0.0 => return Err(ERR::cause("division by zero")),
+                    BinaryOperator::Divide => left / right,
+                    _ => left % right,
+                };
+                Ok(Value::Float64(result))
+            }
+            (left @ (Value::Int64(_) | Value::Decimal(_)), right @ Value::Decimal(_))
+            | (left @ Value::Decimal(_), right @ Value::Int64(_)) => {
+                let exact = |value| match value {
+                    Value::Int64(value) => Decimal::from(value),
+                    Value::Decimal(value) => value,
+                    _ => unreachable!(),
+                };
+                let (left, right) = (exact(left), exact(right));
+                if divides && right.is_zero() {
+                    return Err(ERR::cause("division by zero"));
+                }
+                let result = match operator {
+                    BinaryOperator::Plus => left.checked_add(&right),
+                    BinaryOperator::Minus => left.checked_sub(&right),
+                    BinaryOperator::Multiply => left.checked_mul(&right),
+                    BinaryOperator::Divide => left.checked_div(&right),
+                    _ => left.checked_rem(&right),
+                };
+                result
+                    .map(Value::Decimal)
+                    .ok_or_else(|| ERR::cause("numeric value out of range"))
+            }
+            (left, right) => Err(ERR::cause(&format!(
+                "cannot apply {} to {} and {}",
+                operator,
+                left.type_name().unwrap_or("unknown"),
+                right.type_name().unwrap_or("unknown")
+            ))),
+        }
+    }
+}
+
+enum LikeToken {
+    AnySequence,
+    AnyChar,
+    Char(char),
+}
+
+/// SQL `LIKE` matching, `%` matches any sequence of characters, `_` any
+/// single character and `escape` makes the following character literal.
+pub fn like(value: &str, pattern: &str, escape: Option<char>, case_insensitive: bool) -> bool {
+    let fold = |c: char| {
+        if case_insensitive {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    };
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if Some(c) == escape => LikeToken::Char(fold(chars.next().unwrap_or(c))),
+            '%' => LikeToken::AnySequence,
+            '_' => LikeToken::AnyChar,
+            c => LikeToken::Char(fold(c)),
+        };
+        tokens.push(token);
+    }
+    let value: Vec<char> = value.chars().map(fold).collect();
+
+    // matched[i] is whether the tokens so far match the first i characters
+    let mut matched = vec![false; value.len() + 1];
+    matched[0] = true;
+    for token in tokens {
+        let mut next = vec![false; value.len() + 1];
+        match token {
+            LikeToken::AnySequence => {
+                let mut any = false;
+                for i in 0..=value.len() {
+                    any |= matched[i];
+                    next[i] = any;
+                }
+            }
+            LikeToken::AnyChar => {
+                next[1..].copy_from_slice(&matched[..value.len()]);
+            }
+            LikeToken::Char(c) => {
+                for i in 0..value.len() {
+                    next[i + 1] = matched[i] && value[i] == c;
+                }
+            }
+        }
+        matched = next;
+    }
+    matched[value.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::BinaryOperator,
+        parser::{parse_expression, ParserError},
+        types::DBError,
+        value::{Decimal, Value},
+    };
+
+    use super::{like, ExpressionEvaluator, RowContext};
+
+    struct TestRow;
+    impl RowContext for TestRow {
+        fn column<ERR: DBError>(&self, _table: Option<&str>, name: &str) -> Result<Value, ERR> {
+            match name {
+                "age" => Ok(Value::Int64(20)),
+                "city" => Ok(Value::Text("Seoul".to_string())),
+                "vip" => Ok(Value::Boolean(false)),
+                "deleted_at" => Ok(Value::Null),
+                _ => Err(ERR::cause("unknown column")),
+            }
+        }
+    }
+
+    fn evaluate(input: &str) -> Result<Value, ParserError> {
+        let expression = parse_expression(input)?;
+        ExpressionEvaluator::evaluate(&expression, &TestRow)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let test_cases = vec![
+            (
+                "age >= 18 AND (city = 'Seoul' OR vip)",
+                Value::Boolean(true),
+            ),
+            ("1 + 2 * 3 - 4 % 3", Value::Int64(6)),
+            ("-age / 4", Value::Int64(-5)),
+            ("7 / 2", Value::Int64(3)),
+            ("7.5 / 2", Value::Decimal("3.75".parse().unwrap())),
+            ("age * 0.5 + 1", Value::Decimal(Decimal::from(11))),
+            ("age = 20.0", Value::Boolean(true)),
+            ("city IN ('Busan', 'Seoul')", Value::Boolean(true)),
+            ("age NOT BETWEEN 10 AND 19", Value::Boolean(true)),
+            ("city LIKE 'Se_u%'", Value::Boolean(true)),
+            ("city ILIKE 'se%'", Value::Boolean(true)),
+            ("'a\\b' LIKE 'a\\_' ESCAPE ''", Value::Boolean(true)),
+            (
+                "deleted_at IS NULL AND city IS NOT NULL",
+                Value::Boolean(true),
+            ),
+            ("deleted_at = deleted_at", Value::Null),
+            ("deleted_at = 1 OR age = 20", Value::Boolean(true)),
+            ("deleted_at = 1 AND age = 20", Value::Null),
+            ("deleted_at = 1 AND age = 1", Value::Boolean(false)),
+            ("age IN (1, deleted_at)", Value::Null),
+            ("NOT deleted_at > 1", Value::Null),
+            ("-1e308", Value::Float64(-1e308)),
+            ("1e-2000000000", Value::Float64(0.0)),
+            ("1e300 > age", Value::Boolean(true)),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(evaluate(input), Ok(expected), "{}", input);
+        }
+        assert!(evaluate("age / 0").is_err());
+        assert!(evaluate("age / 0.0").is_err());
+        assert_eq!(
+            evaluate("1 - 0.5"),
+            Ok(Value::Decimal("0.5".parse().unwrap()))
+        );
+        assert_eq!(
+            evaluate("city - 1").map_err(|err| err.message().to_string()),
+            Err("cannot apply - to text and bigint".to_string())
+        );
+        assert_eq!(
+            evaluate("-city").map_err(|err| err.message().to_string()),
+            Err("cannot apply - to text".to_string())
+        );
+        assert!(evaluate("age = city").is_err());
+        assert!(evaluate("unknown = 1").is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_overflow() {
+        let arithmetic = |left, operator, right| {
+            ExpressionEvaluator::arithmetic::<ParserError>(left, operator, right)
+        };
+        assert_eq!(
+            arithmetic(
+                Value::Int64(i64::MIN),
+                BinaryOperator::Modulo,
+                Value::Int64(-1)
+            ),
+            Ok(Value::Int64(0))
+        );
+        assert!(arithmetic(
+            Value::Int64(i64::MIN),
+            BinaryOperator::Divide,
+            Value::Int64(-1)
+        )
+        .is_err());
+        assert_eq!(
+            arithmetic(Value::Date(0), BinaryOperator::Minus, Value::Int64(-1)),
+            Ok(Value::Date(1))
+        );
+        for (left, operator, right) in [
+            (Value::Date(0), BinaryOperator::Plus, Value::Int64(i64::MAX)),
+            (Value::Int64(i64::MAX), BinaryOperator::Plus, Value::Date(0)),
+            (
+                Value::Date(0),
+                BinaryOperator::Minus,
+                Value::Int64(i64::MIN),
+            ),
+            (
+                Value::Date(-1),
+                BinaryOperator::Minus,
+                Value::Int64(i64::MAX),
+            ),
+            (
+                Value::Date(0),
+                BinaryOperator::Plus,
+                Value::Int64(i32::MAX as i64 + 1),
+            ),
+        ] {
+            assert!(
+                arithmetic(left.clone(), operator, right.clone()).is_err(),
+                "{:?} {:?} {:?}",
+                left,
+                operator,
+                right
+            );
+        }
+    }
+
+    #[test]
+    fn test_like() {
+        assert!(like("kimchi", "kim%", None, false));
+        assert!(!like("Kimchi", "kim%", None, false));
+        assert!(like("Kimchi", "kim%", None, true));
+        assert!(like("abc", "a_c", None, false));
+        assert!(!like("abbc", "a_c", None, false));
+        assert!(like("", "%", None, false));
+        assert!(like("10%", "10!%", Some('!'), false));
+        assert!(!like("100", "10!%", Some('!'), false));
+        assert!(like("a_b", "a\\_b", Some('\\'), false));
+        assert!(like("a%b%c", "%!%%", Some('!'), false));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let values = || {
+            vec![
+                Value::Int64(3),
+                Value::Null,
+                Value::Int64(1),
+                Value::Int64(3),
+            ]
+        };
+        let aggregate = |name: &str, distinct: bool, values: Vec<Value>| {
+            ExpressionEvaluator::aggregate::<ParserError>(name, distinct, values)
+        };
+        assert_eq!(aggregate("COUNT", false, values()), Ok(Value::Int64(3)));
+        assert_eq!(aggregate("count", true, values()), Ok(Value::Int64(2)));
+        assert_eq!(aggregate("SUM", false, values()), Ok(Value::Int64(7)));
+        assert_eq!(aggregate("SUM", true, values()), Ok(Value::Int64(4)));
+        assert_eq!(
+            aggregate("AVG", true, values()),
+            Ok(Value::Decimal(Decimal::from(2)))
+        );
+        assert_eq!(
+            aggregate("AVG", false, values()),
+            Ok(Value::Decimal("2.3333333333333333".parse().unwrap()))
+        );
+        assert_eq!(aggregate("MIN", false, values()), Ok(Value::Int64(1)));
+        assert_eq!(aggregate("MAX", false, values()), Ok(Value::Int64(3)));
+        assert_eq!(
+            aggregate("COUNT", false, vec![Value::Null]),
+            Ok(Value::Int64(0))
+        );
+        assert_eq!(aggregate("SUM", false, vec![]), Ok(Value::Null));
+        assert!(aggregate("SUM", false, vec![Value::Text("a".to_string())]).is_err());
+        assert_eq!(
+            aggregate(
+                "MAX",
+                false,
+                vec![Value::Text("a".to_string()), Value::Text("b".to_string())]
+            ),
+            Ok(Value::Text("b".to_string()))
+        );
+        assert!(evaluate("COUNT(age)").is_err());
+        assert!(evaluate("lower(city)").is_err());
+    }
+}
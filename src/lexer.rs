@@ -1,23 +1,46 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, ops::Range};
 
-use crate::{token::Token, types::DBError};
+use crate::{
+    token::{Span, Token},
+    types::DBError,
+    value::parse_hex,
+};
 
 pub trait Tokenize<ERR> {
     fn tokenize(input: String) -> Result<Vec<Token>, ERR>;
+    fn tokenize_spanned(input: String) -> Result<Vec<(Token, Span)>, ERR>;
+    /// Tokens including comments and whitespace, whose texts make up the input.
+    fn tokenize_with_trivia(input: String) -> Result<Vec<(Token, Span)>, ERR>;
+    fn tokenize_statements(input: String) -> Vec<TokenizedStatement<ERR>>;
+}
+
+/// Tokens of one `;`-terminated statement of a script, including the `;`.
+/// `range` is the byte range of the statement in the input.
+#[derive(Debug)]
+pub struct TokenizedStatement<ERR> {
+    pub tokens: Result<Vec<(Token, Span)>, ERR>,
+    pub range: Range<usize>,
 }
 trait Lexer<ERR> {
     fn new(input: String) -> Self;
     fn read_char(&mut self);
+    fn peek_char(&self) -> char;
     fn next_token(&mut self) -> Result<Token, ERR>;
+    fn next_spanned_token(&mut self) -> Result<(Token, Span), ERR>;
+    fn next_trivia_token(&mut self) -> Result<(Token, Span), ERR>;
+    fn read_trivia(&mut self) -> Result<Option<Token>, ERR>;
     fn read_identifier(&mut self) -> String;
-    fn read_value(&mut self) -> String;
-    fn read_number(&mut self) -> Result<f64, ERR>;
+    fn read_quoted_identifier(&mut self) -> Result<String, ERR>;
+    fn read_value(&mut self, escapes: bool) -> Result<String, ERR>;
+    fn read_blob(&mut self) -> Result<Vec<u8>, ERR>;
+    fn read_number(&mut self) -> Result<Token, ERR>;
     fn skip_whitespace(&mut self);
+    fn skip_statement(&mut self);
     fn lookup_identifier(&self, identifier: &str) -> Token;
 }
 
 #[derive(Debug)]
-struct LexerError(String);
+pub struct LexerError(String);
 impl DBError for LexerError {
     fn cause(msg: &str) -> Self {
         LexerError(msg.to_string())
@@ -26,12 +49,20 @@ impl DBError for LexerError {
         self.0.push_str(msg);
         self
     }
+    fn at(mut self, span: Span) -> Self {
+        self.0
+            .push_str(&format!(" at line {}, column {}", span.line, span.column));
+        self
+    }
 }
 
-struct SimpleLexer<ERR> {
+pub struct SimpleLexer<ERR> {
     chars: Vec<char>,
     index: usize,
     char: char,
+    position: usize,
+    line: usize,
+    column: usize,
     _err: PhantomData<ERR>,
 }
 impl<ERR> Tokenize<ERR> for SimpleLexer<ERR>
@@ -39,17 +70,89 @@ where
     ERR: DBError,
 {
     fn tokenize(input: String) -> Result<Vec<Token>, ERR> {
+        let tokens = Self::tokenize_spanned(input)?;
+        Ok(tokens.into_iter().map(|(token, _)| token).collect())
+    }
+
+    fn tokenize_spanned(input: String) -> Result<Vec<(Token, Span)>, ERR> {
         let mut lexer = SimpleLexer::new(input);
         let mut tokens = Vec::new();
         loop {
-            let token = lexer.next_token()?;
+            let (token, span) = lexer.next_spanned_token()?;
             if token.is_eof() {
                 return Ok(tokens);
             }
-            tokens.push(token);
+            tokens.push((token, span));
+        }
+    }
+
+    fn tokenize_with_trivia(input: String) -> Result<Vec<(Token, Span)>, ERR> {
+        let mut lexer = SimpleLexer::new(input);
+        let mut tokens = Vec::new();
+        loop {
+            let (token, span) = lexer.next_trivia_token()?;
+            if token.is_eof() {
+                return Ok(tokens);
+            }
+            tokens.push((token, span));
+        }
+    }
+
+    fn tokenize_statements(input: String) -> Vec<TokenizedStatement<ERR>> {
+        let mut lexer = SimpleLexer::new(input);
+        let mut statements = Vec::new();
+        let mut tokens = Vec::new();
+        let mut range = 0..0;
+        loop {
+            lexer.skip_whitespace();
+            if tokens.is_empty() {
+                range = lexer.position..lexer.position;
+            }
+            match lexer.next_trivia_token() {
+                // comments before a statement are not part of it
+                Ok((token, _)) if token.is_trivia() => {}
+                Ok((Token::EOF, _)) => {
+                    if !tokens.is_empty() {
+                        statements.push(TokenizedStatement {
+                            tokens: Ok(tokens),
+                            range,
+                        });
+                    }
+                    return statements;
+                }
+                Ok((Token::Semicolon, span)) => {
+                    range.end = span.end;
+                    if !tokens.is_empty() {
+                        tokens.push((Token::Semicolon, span));
+                        statements.push(TokenizedStatement {
+                            tokens: Ok(std::mem::take(&mut tokens)),
+                            range: range.clone(),
+                        });
+                    }
+                }
+                Ok((token, span)) => {
+                    range.end = span.end;
+                    tokens.push((token, span));
+                }
+                Err(err) => {
+                    lexer.skip_statement();
+                    range.end = lexer.position;
+                    tokens.clear();
+                    statements.push(TokenizedStatement {
+                        tokens: Err(err),
+                        range: range.clone(),
+                    });
+                }
+            }
         }
     }
 }
+impl<ERR> SimpleLexer<ERR> {
+    /// Whether the whole input has been read, as opposed to reading a NUL.
+    fn is_at_end(&self) -> bool {
+        self.index > self.chars.len()
+    }
+}
 impl<ERR> Lexer<ERR> for SimpleLexer<ERR>
 where
     ERR: DBError,
@@ -59,33 +162,103 @@ where
             chars: input.chars().collect(),
             index: 0,
             char: '\0',
+            position: 0,
+            line: 1,
+            column: 0,
             _err: PhantomData,
         };
         lexer.read_char();
         lexer
     }
     fn read_char(&mut self) {
+        if self.index > 0 && self.index <= self.chars.len() {
+            self.position += self.char.len_utf8();
+        }
+        if self.char == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         self.char = self.chars.get(self.index).map_or('\0', |v| *v);
         self.index += 1;
     }
 
+    fn peek_char(&self) -> char {
+        self.chars.get(self.index).map_or('\0', |v| *v)
+    }
+
     fn next_token(&mut self) -> Result<Token, ERR> {
-        self.skip_whitespace();
+        while self.read_trivia()?.is_some() {}
         let token = match self.char {
             '=' => Token::Equal,
+            '<' => match self.peek_char() {
+                '=' => {
+                    self.read_char();
+                    Token::LessThanOrEqual
+                }
+                '>' => {
+                    self.read_char();
+                    Token::NotEqual
+                }
+                _ => Token::LessThan,
+            },
+            '>' => match self.peek_char() {
+                '=' => {
+                    self.read_char();
+                    Token::GreaterThanOrEqual
+                }
+                _ => Token::GreaterThan,
+            },
+            '!' if self.peek_char() == '=' => {
+                self.read_char();
+                Token::NotEqual
+            }
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '/' => Token::Slash,
+            '%' => Token::Percent,
             ';' => Token::Semicolon,
             ',' => Token::Comma,
             '(' => Token::LeftParen,
             ')' => Token::RightParen,
             '*' => Token::Asterisk,
+            '.' if self.peek_char().is_ascii_digit() => return self.read_number(),
+            '?' => Token::QuestionMark,
+            '$' if self.peek_char().is_ascii_digit() => {
+                self.read_char();
+                let mut digits = String::new();
+                while self.char.is_ascii_digit() {
+                    digits.push(self.char);
+                    self.read_char();
+                }
+                return match digits.parse() {
+                    Ok(position) if position > 0 => Ok(Token::PositionalParameter(position)),
+                    _ => Err(ERR::cause(&format!("there is no parameter ${}", digits))),
+                };
+            }
+            ':' if self.peek_char().is_alphabetic() || self.peek_char() == '_' => {
+                self.read_char();
+                return Ok(Token::NamedParameter(self.read_identifier()));
+            }
+            '.' => Token::Dot,
             '\0' => Token::EOF,
-            '"' | '\'' => Token::StringLiteral(self.read_value()),
+            '\'' => return Ok(Token::StringLiteral(self.read_value(false)?)),
+            '"' | '`' => return Ok(Token::QuotedIdentifier(self.read_quoted_identifier()?)),
+            'e' | 'E' if self.peek_char() == '\'' => {
+                self.read_char();
+                return Ok(Token::StringLiteral(self.read_value(true)?));
+            }
+            'x' | 'X' if self.peek_char() == '\'' => {
+                self.read_char();
+                return Ok(Token::BlobLiteral(self.read_blob()?));
+            }
             _ => {
                 if self.char.is_alphabetic() {
                     let identifier = self.read_identifier();
                     return Ok(self.lookup_identifier(&identifier));
-                } else if self.char.is_numeric() {
-                    return Ok(Token::NumericLiteral(self.read_number()?));
+                } else if self.char.is_ascii_digit() {
+                    return self.read_number();
                 } else {
                     Token::Unknown(self.char)
                 }
@@ -95,6 +268,33 @@ where
         Ok(token)
     }
 
+    fn next_spanned_token(&mut self) -> Result<(Token, Span), ERR> {
+        loop {
+            let (token, span) = self.next_trivia_token()?;
+            if !token.is_trivia() {
+                return Ok((token, span));
+            }
+        }
+    }
+
+    fn next_trivia_token(&mut self) -> Result<(Token, Span), ERR> {
+        let (start, line, column) = (self.position, self.line, self.column);
+        let token = match self.read_trivia() {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => self.next_token(),
+            Err(err) => Err(err),
+        };
+        let span = Span {
+            start,
+            end: self.position,
+            line,
+            column,
+            end_line: self.line,
+            end_column: self.column,
+        };
+        token.map(|token| (token, span)).map_err(|err| err.at(span))
+    }
+
     fn read_identifier(&mut self) -> String {
         let mut identifier = String::new();
         while self.char.is_alphanumeric() || self.char == '_' {
@@ -103,24 +303,186 @@ where
         }
         identifier
     }
-    fn read_value(&mut self) -> String {
+    fn read_quoted_identifier(&mut self) -> Result<String, ERR> {
+        let identifier = self.read_value(false)?;
+        if identifier.is_empty() {
+            return Err(ERR::cause("zero-length delimited identifier"));
+        }
+        Ok(identifier)
+    }
+    /// Reads a string, or a delimited identifier, quoted by the current
+    /// character, in which a doubled quote stands for one quote. With `escapes`, as in `E'...'`, a
+    /// backslash starts a C-like escape sequence.
+    fn read_value(&mut self, escapes: bool) -> Result<String, ERR> {
+        let quote = self.char;
+        let unterminated = || match quote {
+            '\'' => ERR::cause("unterminated quoted string"),
+            _ => ERR::cause("unterminated quoted identifier"),
+        };
         let mut string_val = String::new();
+        // an invalid escape sequence fails once the whole string is read
+        let mut invalid = false;
         self.read_char();
-        while self.char != '"' && self.char != '\'' && self.char != '\0' {
-            string_val.push(self.char);
+        loop {
+            match self.char {
+                _ if self.is_at_end() => return Err(unterminated()),
+                char if char == quote => {
+                    self.read_char();
+                    if self.char != quote {
+                        if invalid {
+                            return Err(ERR::cause("invalid escape sequence"));
+                        }
+                        return Ok(string_val);
+                    }
+                    string_val.push(quote);
+                }
+                '\\' if escapes => {
+                    self.read_char();
+                    let escaped = match self.char {
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'x' | 'u' | 'U' => {
+                            let digits = match self.char {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let mut code = String::new();
+                            while code.len() < digits && self.peek_char().is_ascii_hexdigit() {
+                                self.read_char();
+                                code.push(self.char);
+                            }
+                            u32::from_str_radix(&code, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .unwrap_or_else(|| {
+                                    invalid = true;
+                                    char::REPLACEMENT_CHARACTER
+                                })
+                        }
+                        _ if self.is_at_end() => return Err(unterminated()),
+                        char => char,
+                    };
+                    string_val.push(escaped);
+                }
+                char => string_val.push(char),
+            }
             self.read_char();
         }
-        string_val
     }
-    fn read_number(&mut self) -> Result<f64, ERR> {
+
+    /// Reads the quoted hexadecimal digits of a blob literal.
+    fn read_blob(&mut self) -> Result<Vec<u8>, ERR> {
+        let digits = self.read_value(false)?;
+        parse_hex(&digits)
+            .ok_or_else(|| ERR::cause(&format!("invalid hexadecimal blob literal X'{}'", digits)))
+    }
+
+    /// Reads `digits[.digits][e[+-]digits]`, an integer literal when it has
+    /// neither a fraction nor an exponent and fits a bigint.
+    fn read_number(&mut self) -> Result<Token, ERR> {
         let mut number_str = String::new();
-        while self.char.is_digit(10) || self.char == '.' {
+        let mut integer = true;
+        while self.char.is_ascii_digit() {
+            number_str.push(self.char);
+            self.read_char();
+        }
+        if self.char == '.' {
+            integer = false;
             number_str.push(self.char);
             self.read_char();
+            while self.char.is_ascii_digit() {
+                number_str.push(self.char);
+                self.read_char();
+            }
+        }
+        if matches!(self.char, 'e' | 'E') {
+            integer = false;
+            number_str.push(self.char);
+            self.read_char();
+            if matches!(self.char, '+' | '-') {
+                number_str.push(self.char);
+                self.read_char();
+            }
+            if !self.char.is_ascii_digit() {
+                return Err(ERR::cause(&format!(
+                    "exponent of numeric literal {} has no digits",
+                    number_str
+                )));
+            }
+            while self.char.is_ascii_digit() {
+                number_str.push(self.char);
+                self.read_char();
+            }
+        }
+        if self.char == '.' || self.char.is_alphanumeric() || self.char == '_' {
+            return Err(ERR::cause(&format!(
+                "trailing junk after numeric literal {}",
+                number_str
+            )));
+        }
+        if integer {
+            if let Ok(value) = number_str.parse() {
+                return Ok(Token::IntegerLiteral(value));
+            }
+        }
+        if let Ok(value) = number_str.parse() {
+            return Ok(Token::DecimalLiteral(value));
+        }
+        match number_str.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Token::FloatLiteral(value)),
+            _ => Err(ERR::cause(&format!(
+                "numeric literal {} is out of range",
+                number_str
+            ))),
+        }
+    }
+
+    /// Reads whitespace, a `--` comment up to the end of the line or a
+    /// `/* */` comment, which may nest, if one starts at the current character.
+    fn read_trivia(&mut self) -> Result<Option<Token>, ERR> {
+        let mut text = String::new();
+        if self.char.is_whitespace() {
+            while self.char.is_whitespace() {
+                text.push(self.char);
+                self.read_char();
+            }
+            return Ok(Some(Token::Whitespace(text)));
+        }
+        if self.char == '-' && self.peek_char() == '-' {
+            while self.char != '\n' && !self.is_at_end() {
+                text.push(self.char);
+                self.read_char();
+            }
+            return Ok(Some(Token::Comment(text)));
+        }
+        if self.char != '/' || self.peek_char() != '*' {
+            return Ok(None);
+        }
+        let mut depth = 0;
+        loop {
+            if self.is_at_end() {
+                return Err(ERR::cause("unterminated /* comment"));
+            }
+            if self.char == '/' && self.peek_char() == '*' {
+                depth += 1;
+                text.push_str("/*");
+                self.read_char();
+            } else if self.char == '*' && self.peek_char() == '/' {
+                depth -= 1;
+                text.push_str("*/");
+                self.read_char();
+            } else {
+                text.push(self.char);
+            }
+            self.read_char();
+            if depth == 0 {
+                return Ok(Some(Token::Comment(text)));
+            }
         }
-        number_str
-            .parse::<f64>()
-            .map_err(|_| ERR::cause("error read_number"))
     }
 
     fn skip_whitespace(&mut self) {
@@ -129,11 +491,28 @@ where
         }
     }
 
+    /// Skips the rest of a statement that failed to tokenize, reading
+    /// strings, quoted identifiers and comments as a whole so that a `;` in
+    /// them does not end it.
+    fn skip_statement(&mut self) {
+        loop {
+            let position = self.position;
+            match self.next_trivia_token() {
+                Ok((Token::Semicolon | Token::EOF, _)) => return,
+                Ok(_) => {}
+                // a token that failed after its first character is skipped
+                Err(_) if self.position > position => {}
+                Err(_) => self.read_char(),
+            }
+        }
+    }
+
     fn lookup_identifier(&self, identifier: &str) -> Token {
         match identifier.to_uppercase().as_str() {
             "TABLE" => Token::Table,
             "CREATE" => Token::Create,
             "DROP" => Token::Drop,
+            "ALTER" => Token::Alter,
             "INSERT" => Token::Insert,
             "SELECT" => Token::Select,
             "UPDATE" => Token::Update,
@@ -143,17 +522,57 @@ where
             "WHERE" => Token::Where,
             "SET" => Token::Set,
             "VALUES" => Token::Values,
-            _ => Token::Identifier(identifier.to_string()),
+            "GROUP" => Token::Group,
+            "HAVING" => Token::Having,
+            "DISTINCT" => Token::Distinct,
+            "ORDER" => Token::Order,
+            "BY" => Token::By,
+            "ASC" => Token::Asc,
+            "DESC" => Token::Desc,
+            "LIMIT" => Token::Limit,
+            "OFFSET" => Token::Offset,
+            "FETCH" => Token::Fetch,
+            "AS" => Token::As,
+            "JOIN" => Token::Join,
+            "INNER" => Token::Inner,
+            "LEFT" => Token::Left,
+            "RIGHT" => Token::Right,
+            "FULL" => Token::Full,
+            "OUTER" => Token::Outer,
+            "CROSS" => Token::Cross,
+            "ON" => Token::On,
+            "USING" => Token::Using,
+            "WITH" => Token::With,
+            "UNION" => Token::Union,
+            "ALL" => Token::All,
+            "INTERSECT" => Token::Intersect,
+            "EXCEPT" => Token::Except,
+            "RETURNING" => Token::Returning,
+            "AND" => Token::And,
+            "OR" => Token::Or,
+            "NOT" => Token::Not,
+            "IN" => Token::In,
+            "BETWEEN" => Token::Between,
+            "LIKE" => Token::Like,
+            "ILIKE" => Token::ILike,
+            "ESCAPE" => Token::Escape,
+            "IS" => Token::Is,
+            "NULL" => Token::Null,
+            "TRUE" => Token::True,
+            "FALSE" => Token::False,
+            "EXISTS" => Token::Exists,
+            _ => Token::Identifier(identifier.to_lowercase()),
         }
     }
 }
 
+#[cfg(test)]
 mod tests {
-    use std::{hint::black_box, process::Termination, time::Instant};
+    use std::{hint::black_box, time::Instant};
 
     use crate::token::Token;
 
-    use super::{Lexer, LexerError, SimpleLexer};
+    use super::{Lexer, LexerError, SimpleLexer, Tokenize};
 
     #[test]
     fn ptest_read() {
@@ -164,7 +583,8 @@ mod tests {
             for _ in 0..100 {
                 lexer.read_char();
                 let c = lexer.char;
-                black_box(reulst_vec.push(c));
+                reulst_vec.push(c);
+                black_box(&reulst_vec);
             }
         }
         println!("FINSIH: {:?}", start.elapsed());
@@ -197,7 +617,7 @@ mod tests {
                 expected_tokens: vec![
                     Token::Identifier("var_name".to_string()),
                     Token::Equal,
-                    Token::NumericLiteral(123.0),
+                    Token::IntegerLiteral(123),
                     Token::Semicolon,
                     Token::EOF,
                 ],
@@ -240,7 +660,7 @@ mod tests {
                     Token::Where,
                     Token::Identifier("id".to_string()),
                     Token::Equal,
-                    Token::NumericLiteral(1.0),
+                    Token::IntegerLiteral(1),
                     Token::Semicolon,
                     Token::EOF,
                 ],
@@ -254,7 +674,7 @@ mod tests {
                     Token::Where,
                     Token::Identifier("id".to_string()),
                     Token::Equal,
-                    Token::NumericLiteral(1.0),
+                    Token::IntegerLiteral(1),
                     Token::Semicolon,
                     Token::EOF,
                 ],
@@ -275,7 +695,7 @@ mod tests {
                     Token::LeftParen,
                     Token::StringLiteral("value1".to_string()),
                     Token::Comma,
-                    Token::NumericLiteral(123.0),
+                    Token::IntegerLiteral(123),
                     Token::RightParen,
                     Token::Semicolon,
                     Token::EOF,
@@ -305,7 +725,7 @@ mod tests {
                     Token::Where,
                     Token::Identifier("column2".to_string()),
                     Token::Equal,
-                    Token::NumericLiteral(123.0),
+                    Token::IntegerLiteral(123),
                     Token::Semicolon,
                     Token::EOF,
                 ],
@@ -324,6 +744,80 @@ mod tests {
                     Token::EOF,
                 ],
             },
+            TestCase {
+                input: "a <> b != c <= d >= e < f > g + h - i * j / k % l AND NOT m OR n"
+                    .to_owned(),
+                expected_tokens: vec![
+                    Token::Identifier("a".to_string()),
+                    Token::NotEqual,
+                    Token::Identifier("b".to_string()),
+                    Token::NotEqual,
+                    Token::Identifier("c".to_string()),
+                    Token::LessThanOrEqual,
+                    Token::Identifier("d".to_string()),
+                    Token::GreaterThanOrEqual,
+                    Token::Identifier("e".to_string()),
+                    Token::LessThan,
+                    Token::Identifier("f".to_string()),
+                    Token::GreaterThan,
+                    Token::Identifier("g".to_string()),
+                    Token::Plus,
+                    Token::Identifier("h".to_string()),
+                    Token::Minus,
+                    Token::Identifier("i".to_string()),
+                    Token::Asterisk,
+                    Token::Identifier("j".to_string()),
+                    Token::Slash,
+                    Token::Identifier("k".to_string()),
+                    Token::Percent,
+                    Token::Identifier("l".to_string()),
+                    Token::And,
+                    Token::Not,
+                    Token::Identifier("m".to_string()),
+                    Token::Or,
+                    Token::Identifier("n".to_string()),
+                    Token::EOF,
+                ],
+            },
+            TestCase {
+                input: "a NOT IN (1) BETWEEN x LIKE y ILIKE z ESCAPE '!' IS NOT NULL".to_owned(),
+                expected_tokens: vec![
+                    Token::Identifier("a".to_string()),
+                    Token::Not,
+                    Token::In,
+                    Token::LeftParen,
+                    Token::IntegerLiteral(1),
+                    Token::RightParen,
+                    Token::Between,
+                    Token::Identifier("x".to_string()),
+                    Token::Like,
+                    Token::Identifier("y".to_string()),
+                    Token::ILike,
+                    Token::Identifier("z".to_string()),
+                    Token::Escape,
+                    Token::StringLiteral("!".to_string()),
+                    Token::Is,
+                    Token::Not,
+                    Token::Null,
+                    Token::EOF,
+                ],
+            },
+            TestCase {
+                input: "SELECT u.*, 1.5 FROM users u;".to_owned(),
+                expected_tokens: vec![
+                    Token::Select,
+                    Token::Identifier("u".to_string()),
+                    Token::Dot,
+                    Token::Asterisk,
+                    Token::Comma,
+                    Token::DecimalLiteral("1.5".parse().unwrap()),
+                    Token::From,
+                    Token::Identifier("users".to_string()),
+                    Token::Identifier("u".to_string()),
+                    Token::Semicolon,
+                    Token::EOF,
+                ],
+            },
         ];
         let mut case_num = 0;
         for case in test_cases {
@@ -336,4 +830,284 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_literals() {
+        let decimal = |value: &str| Token::DecimalLiteral(value.parse().unwrap());
+        let test_cases = vec![
+            ("42", Token::IntegerLiteral(42)),
+            ("9223372036854775808", decimal("9223372036854775808")),
+            ("1.50", decimal("1.5")),
+            (".5", decimal("0.5")),
+            ("3.", decimal("3")),
+            ("1e9", decimal("1000000000")),
+            ("2.5E-3", decimal("0.0025")),
+            ("1e308", Token::FloatLiteral(1e308)),
+            ("1e-38", decimal("1e-38")),
+            ("1e-39", Token::FloatLiteral(1e-39)),
+            ("1e-2000000000", Token::FloatLiteral(0.0)),
+            (
+                "123456789012345678901234567890123456789012",
+                Token::FloatLiteral(1.2345678901234568e41),
+            ),
+            ("'it''s'", Token::StringLiteral("it's".to_string())),
+            ("''", Token::StringLiteral("".to_string())),
+            (
+                "'say \"hi\"'",
+                Token::StringLiteral("say \"hi\"".to_string()),
+            ),
+            ("'a\\nb'", Token::StringLiteral("a\\nb".to_string())),
+            (
+                "E'tab\\there\\n\\'q\\' \\\\ \\x41\\u00e9'",
+                Token::StringLiteral("tab\there\n'q' \\ Aé".to_string()),
+            ),
+            ("e'it''s'", Token::StringLiteral("it's".to_string())),
+            ("X'00fF'", Token::BlobLiteral(vec![0x00, 0xff])),
+            ("x''", Token::BlobLiteral(vec![])),
+            ("TRUE", Token::True),
+            ("false", Token::False),
+            ("Null", Token::Null),
+        ];
+        for (input, expected) in test_cases {
+            let tokens = SimpleLexer::<LexerError>::tokenize(input.to_owned());
+            assert_eq!(tokens.unwrap(), vec![expected], "{}", input);
+        }
+        // a sign is an operator of its own
+        assert_eq!(
+            SimpleLexer::<LexerError>::tokenize("a-5".to_owned()).unwrap(),
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Minus,
+                Token::IntegerLiteral(5)
+            ]
+        );
+        // names merely starting like a prefixed literal are identifiers
+        assert_eq!(
+            SimpleLexer::<LexerError>::tokenize("x ex".to_owned()).unwrap(),
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Identifier("ex".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let tokens = SimpleLexer::<LexerError>::tokenize(
+            "Users \"User Id\" `order` \"a\"\"b\" \"SELECT\" SeLeCt Ünïcode".to_owned(),
+        );
+        assert_eq!(
+            tokens.unwrap(),
+            vec![
+                Token::Identifier("users".to_string()),
+                Token::QuotedIdentifier("User Id".to_string()),
+                Token::QuotedIdentifier("order".to_string()),
+                Token::QuotedIdentifier("a\"b".to_string()),
+                Token::QuotedIdentifier("SELECT".to_string()),
+                Token::Select,
+                Token::Identifier("ünïcode".to_string()),
+            ]
+        );
+        let test_cases = vec![
+            ("\"\"", "zero-length delimited identifier"),
+            (
+                "SELECT \"abc",
+                "unterminated quoted identifier at line 1, column 8",
+            ),
+            ("`abc\"", "unterminated quoted identifier"),
+        ];
+        for (input, expected) in test_cases {
+            let err = SimpleLexer::<LexerError>::tokenize(input.to_owned()).unwrap_err();
+            assert!(err.0.starts_with(expected), "{}: {}", input, err.0);
+        }
+    }
+
+    #[test]
+    fn test_literal_errors() {
+        let test_cases = vec![
+            ("1.2.3", "trailing junk after numeric literal 1.2"),
+            ("12abc", "trailing junk after numeric literal 12"),
+            ("1e", "exponent of numeric literal 1e has no digits"),
+            ("1e+x", "exponent of numeric literal 1e+ has no digits"),
+            ("1e309", "numeric literal 1e309 is out of range"),
+            (
+                "SELECT 'abc",
+                "unterminated quoted string at line 1, column 8",
+            ),
+            ("'it''", "unterminated quoted string"),
+            ("E'abc\\'", "unterminated quoted string"),
+            ("E'\\xZZ'", "invalid escape sequence"),
+            ("X'ABC'", "invalid hexadecimal blob literal X'ABC'"),
+            ("X'GG'", "invalid hexadecimal blob literal X'GG'"),
+        ];
+        for (input, expected) in test_cases {
+            let err = SimpleLexer::<LexerError>::tokenize(input.to_owned()).unwrap_err();
+            assert!(err.0.starts_with(expected), "{}: {}", input, err.0);
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "-- schema\nSELECT a--b\n/* outer /* inner; */ still */FROM t /**/ WHERE x = '--' - 1 / 2";
+        assert_eq!(
+            SimpleLexer::<LexerError>::tokenize(input.to_owned()).unwrap(),
+            vec![
+                Token::Select,
+                Token::Identifier("a".to_string()),
+                Token::From,
+                Token::Identifier("t".to_string()),
+                Token::Where,
+                Token::Identifier("x".to_string()),
+                Token::Equal,
+                Token::StringLiteral("--".to_string()),
+                Token::Minus,
+                Token::IntegerLiteral(1),
+                Token::Slash,
+                Token::IntegerLiteral(2),
+            ]
+        );
+
+        let tokens = SimpleLexer::<LexerError>::tokenize_with_trivia(input.to_owned()).unwrap();
+        let comments: Vec<&Token> = tokens
+            .iter()
+            .map(|(token, _)| token)
+            .filter(|token| matches!(token, Token::Comment(_)))
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                &Token::Comment("-- schema".to_string()),
+                &Token::Comment("--b".to_string()),
+                &Token::Comment("/* outer /* inner; */ still */".to_string()),
+                &Token::Comment("/**/".to_string()),
+            ]
+        );
+        // the trivia and the tokens make up the input
+        let text: String = tokens.iter().map(|(token, _)| token.to_string()).collect();
+        assert_eq!(text, input);
+        for (token, span) in &tokens {
+            assert_eq!(input[span.start..span.end], token.to_string());
+        }
+
+        let err = SimpleLexer::<LexerError>::tokenize("SELECT 1;\n  /* a /* b */ c".to_owned())
+            .unwrap_err();
+        assert_eq!(err.0, "unterminated /* comment at line 2, column 3");
+    }
+
+    #[test]
+    fn test_parameters() {
+        assert_eq!(
+            SimpleLexer::<LexerError>::tokenize("a = ? AND b IN ($12, :Name, :_x)".to_owned())
+                .unwrap(),
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Equal,
+                Token::QuestionMark,
+                Token::And,
+                Token::Identifier("b".to_string()),
+                Token::In,
+                Token::LeftParen,
+                Token::PositionalParameter(12),
+                Token::Comma,
+                Token::NamedParameter("Name".to_string()),
+                Token::Comma,
+                Token::NamedParameter("_x".to_string()),
+                Token::RightParen,
+            ]
+        );
+        assert!(SimpleLexer::<LexerError>::tokenize("SELECT $0".to_owned()).is_err());
+    }
+
+    #[test]
+    fn test_tokenize_statements_with_comments() {
+        let input = "-- first\nDROP TABLE a; /* b; */ DROP /* ; */ TABLE b -- c;\n; /* d";
+        let statements = SimpleLexer::<LexerError>::tokenize_statements(input.to_owned());
+        assert_eq!(statements.len(), 3);
+        assert_eq!(&input[statements[0].range.clone()], "DROP TABLE a;");
+        assert_eq!(
+            &input[statements[1].range.clone()],
+            "DROP /* ; */ TABLE b -- c;\n;"
+        );
+        assert_eq!(statements[1].tokens.as_ref().unwrap().len(), 4);
+        assert_eq!(&input[statements[2].range.clone()], "/* d");
+        assert!(statements[2].tokens.is_err());
+    }
+
+    #[test]
+    fn test_tokenize_statements() {
+        let input = "DROP TABLE a;\n ;  SELECT 1.2.3 FROM b; DROP TABLE c".to_owned();
+        let statements = SimpleLexer::<LexerError>::tokenize_statements(input.clone());
+        assert_eq!(statements.len(), 3);
+
+        assert_eq!(&input[statements[0].range.clone()], "DROP TABLE a;");
+        let tokens: Vec<&Token> = statements[0]
+            .tokens
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|(token, _)| token)
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                &Token::Drop,
+                &Token::Table,
+                &Token::Identifier("a".to_string()),
+                &Token::Semicolon,
+            ]
+        );
+        assert_eq!(&input[statements[1].range.clone()], "SELECT 1.2.3 FROM b;");
+        assert!(statements[1].tokens.is_err());
+        assert_eq!(&input[statements[2].range.clone()], "DROP TABLE c");
+        assert!(statements[2].tokens.is_ok());
+
+        // a `;` in a string or quoted identifier does not end a statement
+        // being skipped
+        let input = "SELECT 1.2.3, 'a;b', \"c;\" FROM d; SELECT E'\\x;'; DROP TABLE e;";
+        let statements = SimpleLexer::<LexerError>::tokenize_statements(input.to_owned());
+        assert_eq!(statements.len(), 3);
+        assert_eq!(
+            &input[statements[0].range.clone()],
+            "SELECT 1.2.3, 'a;b', \"c;\" FROM d;"
+        );
+        assert!(statements[0].tokens.is_err());
+        assert_eq!(&input[statements[1].range.clone()], "SELECT E'\\x;';");
+        assert!(statements[1].tokens.is_err());
+        assert_eq!(&input[statements[2].range.clone()], "DROP TABLE e;");
+        assert!(statements[2].tokens.is_ok());
+    }
+
+    #[test]
+    fn test_tokenize_spanned() {
+        let input = "SELECT name\n  FROM 사용자\nWHERE id = 12;".to_owned();
+        let tokens = SimpleLexer::<LexerError>::tokenize_spanned(input.clone()).unwrap();
+        let spans: Vec<(usize, usize)> = tokens
+            .iter()
+            .map(|(_, span)| (span.line, span.column))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (1, 1),
+                (1, 8),
+                (2, 3),
+                (2, 8),
+                (3, 1),
+                (3, 7),
+                (3, 10),
+                (3, 12),
+                (3, 14)
+            ]
+        );
+        for (token, span) in tokens {
+            assert_eq!(input[span.start..span.end].to_string(), token.to_string());
+        }
+    }
+
+    #[test]
+    fn test_lexer_error_location() {
+        let err =
+            SimpleLexer::<LexerError>::tokenize("SELECT a\nFROM 1.2.3".to_owned()).unwrap_err();
+        assert!(err.0.ends_with("at line 2, column 6"));
+    }
 }
@@ -0,0 +1,16 @@
+pub mod access_manager;
+pub mod ast;
+pub mod buffer_manager;
+pub mod ddl;
+pub mod disk_manager;
+pub mod dml;
+pub mod evaluator;
+pub mod lexer;
+pub mod parser;
+pub mod query_executor;
+pub mod query_planner;
+pub mod relation;
+pub mod syntax_analysis;
+pub mod token;
+pub mod types;
+pub mod value;
@@ -1,18 +1,38 @@
-mod access_manager;
-mod ast;
-mod buffer_manager;
-mod ddl;
-mod disk_manager;
-mod dml;
-mod lexer;
-mod parser;
-mod query_executor;
-mod query_planner;
-mod syntax_analysis;
-mod token;
-mod types;
+use std::io::{self, Read};
+
+use rust_db::{
+    parser,
+    query_executor::{QueryExecutor, QueryResult},
+};
 
 fn main() {
-    println!("Hello, world!");
-    //note
+    let mut script = String::new();
+    if io::stdin().read_to_string(&mut script).is_err() {
+        return;
+    }
+    match parser::parse_script(&script) {
+        Ok(statements) => {
+            let mut executor = QueryExecutor::new();
+            for parsed in statements {
+                match executor.execute(&parsed.statement) {
+                    Ok(QueryResult::Rows { columns, rows }) => {
+                        println!("{}", columns.join(" | "));
+                        for row in rows {
+                            let values: Vec<String> =
+                                row.iter().map(|value| value.to_string()).collect();
+                            println!("{}", values.join(" | "));
+                        }
+                    }
+                    Ok(QueryResult::RowsAffected(count)) => println!("{} rows affected", count),
+                    Ok(QueryResult::Done) => println!("OK"),
+                    Err(err) => println!("error: {}", err),
+                }
+            }
+        }
+        Err(errors) => {
+            for error in errors {
+                print!("{}", error.error.render(&script));
+            }
+        }
+    }
 }
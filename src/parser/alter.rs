@@ -0,0 +1,249 @@
+use crate::{
+    ast::{AlterColumnAction, AlterOperation, AlterStatement, DropBehavior, SQLStatement},
+    token::Token,
+    types::DBError,
+};
+
+use super::{
+    create::CreateStatementParser,
+    expression::ExpressionParser,
+    parser::{Parser, SQLParser},
+};
+
+pub struct AlterStatementParser;
+impl<P, ERR> SQLParser<P, ERR> for AlterStatementParser
+where
+    P: Parser,
+    ERR: DBError,
+{
+    fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
+        parser.expect_token(&Token::Alter)?;
+        parser.expect_token(&Token::Table)?;
+        let table_name = parser.expect_identifier("table name")?;
+        let operation = if parser.peek_token() == Some(&Token::Drop) {
+            parser.consume_token();
+            Self::parse_drop(parser)?
+        } else if parser.peek_token() == Some(&Token::Alter) {
+            parser.consume_token();
+            parser.consume_keyword("COLUMN");
+            let name = parser.expect_identifier("column name")?;
+            AlterOperation::AlterColumn {
+                name,
+                action: Self::parse_column_action(parser)?,
+            }
+        } else if parser.consume_keyword("ADD") {
+            if CreateStatementParser::starts_table_constraint(parser) {
+                AlterOperation::AddConstraint(CreateStatementParser::parse_table_constraint(
+                    parser,
+                )?)
+            } else {
+                parser.consume_keyword("COLUMN");
+                AlterOperation::AddColumn(CreateStatementParser::parse_column_definition(parser)?)
+            }
+        } else if parser.consume_keyword("RENAME") {
+            // `RENAME TO name`, unless TO is the column in `RENAME to TO name`
+            if parser.peek_nth_keyword(0, "TO") && !parser.peek_nth_keyword(1, "TO") {
+                parser.consume_token();
+                AlterOperation::RenameTable(parser.expect_identifier("table name")?)
+            } else {
+                parser.consume_keyword("COLUMN");
+                let old = parser.expect_identifier("column name")?;
+                parser.expect_keyword("TO")?;
+                let new = parser.expect_identifier("column name")?;
+                AlterOperation::RenameColumn { old, new }
+            }
+        } else {
+            return Err(parser.expected("`ADD`, `DROP`, `ALTER` or `RENAME`"));
+        };
+        Ok(SQLStatement::AlterTable(AlterStatement::new(
+            table_name, operation,
+        )))
+    }
+}
+
+impl AlterStatementParser {
+    /// Parses what follows `DROP`: `CONSTRAINT name` or
+    /// `[COLUMN] name [CASCADE | RESTRICT]`.
+    fn parse_drop<P: Parser, ERR: DBError>(parser: &mut P) -> Result<AlterOperation, ERR> {
+        if parser.consume_keyword("CONSTRAINT") {
+            return Ok(AlterOperation::DropConstraint(
+                parser.expect_identifier("constraint name")?,
+            ));
+        }
+        parser.consume_keyword("COLUMN");
+        let name = parser.expect_identifier("column name")?;
+        let behavior = if parser.consume_keyword("CASCADE") {
+            DropBehavior::Cascade
+        } else {
+            parser.consume_keyword("RESTRICT");
+            DropBehavior::Restrict
+        };
+        Ok(AlterOperation::DropColumn { name, behavior })
+    }
+
+    /// Parses `{SET | DROP} NOT NULL`, `SET DEFAULT expression` or `DROP DEFAULT`.
+    fn parse_column_action<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<AlterColumnAction, ERR> {
+        let set = match parser.peek_token() {
+            Some(Token::Set) => true,
+            Some(Token::Drop) => false,
+            _ => return Err(parser.expected("`SET` or `DROP`")),
+        };
+        parser.consume_token();
+        if parser.peek_token() == Some(&Token::Not) {
+            parser.consume_token();
+            parser.expect_token(&Token::Null)?;
+            return Ok(match set {
+                true => AlterColumnAction::SetNotNull,
+                false => AlterColumnAction::DropNotNull,
+            });
+        }
+        parser.expect_keyword("DEFAULT")?;
+        match set {
+            true => Ok(AlterColumnAction::SetDefault(ExpressionParser::parse(
+                parser,
+            )?)),
+            false => Ok(AlterColumnAction::DropDefault),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            AlterColumnAction, AlterOperation, AlterStatement, ColumnDefinition, DataType,
+            DropBehavior, Expression, SQLStatement, TableConstraint, TableConstraintKind,
+        },
+        parser::parse_sql,
+        value::Value,
+    };
+
+    fn alter(operation: AlterOperation) -> SQLStatement {
+        SQLStatement::AlterTable(AlterStatement::new("t".to_string(), operation))
+    }
+
+    #[test]
+    fn test_columns() {
+        let mut column = ColumnDefinition::new("a".to_string());
+        column.data_type = Some(DataType::BigInt);
+        for sql in [
+            "ALTER TABLE t ADD COLUMN a BIGINT",
+            "ALTER TABLE t ADD a BIGINT",
+        ] {
+            assert_eq!(
+                parse_sql(sql),
+                Ok(alter(AlterOperation::AddColumn(column.clone()))),
+                "{}",
+                sql
+            );
+        }
+        assert_eq!(
+            parse_sql("ALTER TABLE t DROP COLUMN a CASCADE"),
+            Ok(alter(AlterOperation::DropColumn {
+                name: "a".to_string(),
+                behavior: DropBehavior::Cascade,
+            }))
+        );
+        assert_eq!(
+            parse_sql("ALTER TABLE t DROP a"),
+            Ok(alter(AlterOperation::DropColumn {
+                name: "a".to_string(),
+                behavior: DropBehavior::Restrict,
+            }))
+        );
+        assert_eq!(
+            parse_sql("ALTER TABLE t RENAME COLUMN a TO b"),
+            Ok(alter(AlterOperation::RenameColumn {
+                old: "a".to_string(),
+                new: "b".to_string(),
+            }))
+        );
+        // a column may be named `to`
+        assert_eq!(
+            parse_sql("ALTER TABLE t RENAME to TO b"),
+            Ok(alter(AlterOperation::RenameColumn {
+                old: "to".to_string(),
+                new: "b".to_string(),
+            }))
+        );
+        assert_eq!(
+            parse_sql("ALTER TABLE t RENAME TO u"),
+            Ok(alter(AlterOperation::RenameTable("u".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_alter_column() {
+        for (sql, action) in [
+            (
+                "ALTER TABLE t ALTER COLUMN a SET NOT NULL",
+                AlterColumnAction::SetNotNull,
+            ),
+            (
+                "ALTER TABLE t ALTER a DROP NOT NULL",
+                AlterColumnAction::DropNotNull,
+            ),
+            (
+                "ALTER TABLE t ALTER a SET DEFAULT 0",
+                AlterColumnAction::SetDefault(Expression::Literal(Value::Int64(0))),
+            ),
+            (
+                "ALTER TABLE t ALTER a DROP DEFAULT",
+                AlterColumnAction::DropDefault,
+            ),
+        ] {
+            assert_eq!(
+                parse_sql(sql),
+                Ok(alter(AlterOperation::AlterColumn {
+                    name: "a".to_string(),
+                    action,
+                })),
+                "{}",
+                sql
+            );
+        }
+    }
+
+    #[test]
+    fn test_constraints() {
+        assert_eq!(
+            parse_sql("ALTER TABLE t ADD CONSTRAINT t_a UNIQUE (a)"),
+            Ok(alter(AlterOperation::AddConstraint(TableConstraint {
+                name: Some("t_a".to_string()),
+                kind: TableConstraintKind::Unique(vec!["a".to_string()]),
+            })))
+        );
+        assert_eq!(
+            parse_sql("ALTER TABLE t ADD PRIMARY KEY (a)"),
+            Ok(alter(AlterOperation::AddConstraint(TableConstraint {
+                name: None,
+                kind: TableConstraintKind::PrimaryKey(vec!["a".to_string()]),
+            })))
+        );
+        assert_eq!(
+            parse_sql("ALTER TABLE t DROP CONSTRAINT t_a"),
+            Ok(alter(AlterOperation::DropConstraint("t_a".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        for sql in [
+            "ALTER t ADD a",
+            "ALTER TABLE t",
+            "ALTER TABLE t MODIFY a",
+            "ALTER TABLE t ADD",
+            "ALTER TABLE t DROP CONSTRAINT",
+            "ALTER TABLE t RENAME a",
+            "ALTER TABLE t RENAME TO",
+            "ALTER TABLE t ALTER a SET NULL",
+            "ALTER TABLE t ALTER a DROP DEFAULT 0",
+            "ALTER TABLE t ALTER a SET DEFAULT a",
+            "ALTER TABLE t ADD a NULL NOT NULL",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+}
@@ -1,55 +1,266 @@
 use crate::{
-    ast::{ColumnDefinition, CreateStatement, SQLStatement},
+    ast::{
+        ColumnConstraint, ColumnDefinition, ColumnOption, CreateStatement, DataType, Expression,
+        ForeignKeyReference, SQLStatement, TableConstraint, TableConstraintKind,
+    },
     token::Token,
     types::DBError,
 };
 
-use super::parser::{Parser, SQLParser};
+use super::{
+    expression::ExpressionParser,
+    parser::{Parser, SQLParser},
+};
 
-struct CreateStatementParser;
+pub struct CreateStatementParser;
 impl<P, ERR> SQLParser<P, ERR> for CreateStatementParser
 where
     ERR: DBError,
     P: Parser,
 {
     fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
-        if parser.consume_token() && parser.peek_token() == Some(&Token::Table) {
+        parser.expect_token(&Token::Create)?;
+        parser.expect_token(&Token::Table)?;
+        let if_not_exists = parser.peek_nth_keyword(0, "IF")
+            && parser.peek_nth_token(1) == Some(&Token::Not)
+            && parser.consume_token()
+            && parser.consume_token();
+        if if_not_exists {
+            parser.expect_token(&Token::Exists)?;
+        }
+        let table_name = parser.expect_identifier("table name")?;
+        parser.expect_token(&Token::LeftParen)?;
+        let mut columns = Vec::new();
+        let mut constraints = Vec::new();
+        loop {
+            if Self::starts_table_constraint(parser) {
+                constraints.push(Self::parse_table_constraint(parser)?);
+            } else {
+                columns.push(Self::parse_column_definition(parser)?);
+            }
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
             parser.consume_token();
-            if let Some(Token::Identifier(table_name)) = parser.next_token() {
+        }
+        parser.expect_token(&Token::RightParen)?;
+        let mut statement = CreateStatement::new(table_name, columns);
+        statement.if_not_exists = if_not_exists;
+        statement.constraints = constraints;
+        Ok(SQLStatement::CreateTable(statement))
+    }
+}
+
+impl CreateStatementParser {
+    /// Parses `name [type] [constraint ...]`.
+    pub fn parse_column_definition<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<ColumnDefinition, ERR> {
+        let mut column = ColumnDefinition::new(parser.expect_identifier("column name")?);
+        let starts_constraint = [
+            "CONSTRAINT",
+            "DEFAULT",
+            "UNIQUE",
+            "PRIMARY",
+            "CHECK",
+            "REFERENCES",
+        ]
+        .iter()
+        .any(|keyword| parser.peek_nth_keyword(0, keyword));
+        if matches!(parser.peek_token(), Some(Token::Identifier(_))) && !starts_constraint {
+            column.data_type = Some(Self::parse_data_type(parser)?);
+        }
+        loop {
+            let name = match parser.consume_keyword("CONSTRAINT") {
+                true => Some(parser.expect_identifier("constraint name")?),
+                false => None,
+            };
+            let option = if parser.peek_token() == Some(&Token::Not) {
                 parser.consume_token();
-                let mut columns = Vec::new();
-                loop {
-                    if let Some(Token::Identifier(name)) = parser.next_token() {
-                        columns.push(ColumnDefinition::new(name));
-                        if parser.peek_token() == Some(&Token::Comma) {
-                            parser.consume_token();
-                        } else {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
+                parser.expect_token(&Token::Null)?;
+                ColumnOption::NotNull
+            } else if parser.peek_token() == Some(&Token::Null) {
+                parser.consume_token();
+                ColumnOption::Null
+            } else if parser.consume_keyword("DEFAULT") {
+                ColumnOption::Default(ExpressionParser::parse_operand(parser)?)
+            } else if parser.consume_keyword("UNIQUE") {
+                ColumnOption::Unique
+            } else if parser.consume_keyword("PRIMARY") {
+                parser.expect_keyword("KEY")?;
+                ColumnOption::PrimaryKey
+            } else if parser.consume_keyword("CHECK") {
+                ColumnOption::Check(Self::parse_check(parser)?)
+            } else if parser.consume_keyword("REFERENCES") {
+                ColumnOption::References(Self::parse_references(parser)?)
+            } else if name.is_some() {
+                return Err(parser.expected("column constraint"));
+            } else {
+                break;
+            };
+            column.constraints.push(ColumnConstraint { name, option });
+        }
+        Ok(column)
+    }
+
+    /// Parses a type name, which are non-reserved keywords, and its
+    /// parenthesized arguments.
+    fn parse_data_type<P: Parser, ERR: DBError>(parser: &mut P) -> Result<DataType, ERR> {
+        let Some(Token::Identifier(name)) = parser.peek_token() else {
+            return Err(parser.expected("data type"));
+        };
+        let data_type = match name.to_uppercase().as_str() {
+            "INTEGER" | "INT" => DataType::Integer,
+            "BIGINT" => DataType::BigInt,
+            "REAL" => DataType::Real,
+            "TEXT" => DataType::Text,
+            "VARCHAR" => DataType::Varchar(None),
+            "BOOLEAN" | "BOOL" => DataType::Boolean,
+            "BLOB" => DataType::Blob,
+            "DATE" => DataType::Date,
+            "TIMESTAMP" => DataType::Timestamp,
+            "DECIMAL" | "NUMERIC" => DataType::Decimal {
+                precision: None,
+                scale: None,
+            },
+            _ => return Err(parser.expected("data type")),
+        };
+        parser.consume_token();
+        Ok(match data_type {
+            DataType::Varchar(_) => {
+                DataType::Varchar(Self::parse_type_arguments(parser, 1)?.first().copied())
+            }
+            DataType::Decimal { .. } => {
+                let arguments = Self::parse_type_arguments(parser, 2)?;
+                DataType::Decimal {
+                    precision: arguments.first().copied(),
+                    scale: arguments.get(1).copied(),
                 }
-                if parser.peek_token() == Some(&Token::RightParen) {
+            }
+            data_type => data_type,
+        })
+    }
+
+    /// Parses `[(integer, ...)]` of at most `max` integers.
+    fn parse_type_arguments<P: Parser, ERR: DBError>(
+        parser: &mut P,
+        max: usize,
+    ) -> Result<Vec<u32>, ERR> {
+        let mut arguments = Vec::new();
+        if parser.peek_token() != Some(&Token::LeftParen) {
+            return Ok(arguments);
+        }
+        parser.consume_token();
+        loop {
+            match parser.peek_token() {
+                Some(Token::IntegerLiteral(value)) if u32::try_from(*value).is_ok() => {
+                    arguments.push(*value as u32);
                     parser.consume_token();
-                    return Ok(SQLStatement::CreateTable(CreateStatement::new(
-                        table_name, columns,
-                    )));
                 }
+                _ => return Err(parser.expected("integer")),
+            }
+            if arguments.len() == max || parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
+            parser.consume_token();
+        }
+        parser.expect_token(&Token::RightParen)?;
+        Ok(arguments)
+    }
+
+    /// Whether a table constraint rather than a column definition follows,
+    /// since their keywords can also name columns.
+    pub fn starts_table_constraint<P: Parser>(parser: &P) -> bool {
+        let starts_at = |n| {
+            ((parser.peek_nth_keyword(n, "PRIMARY") || parser.peek_nth_keyword(n, "FOREIGN"))
+                && parser.peek_nth_keyword(n + 1, "KEY"))
+                || ((parser.peek_nth_keyword(n, "UNIQUE") || parser.peek_nth_keyword(n, "CHECK"))
+                    && parser.peek_nth_token(n + 1) == Some(&Token::LeftParen))
+        };
+        starts_at(0) || (parser.peek_nth_keyword(0, "CONSTRAINT") && starts_at(2))
+    }
+
+    /// Parses `[CONSTRAINT name]` followed by `PRIMARY KEY (column, ...)`,
+    /// `UNIQUE (column, ...)`, `FOREIGN KEY (column, ...) REFERENCES ...` or
+    /// `CHECK (expression)`.
+    pub fn parse_table_constraint<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<TableConstraint, ERR> {
+        let name = match parser.consume_keyword("CONSTRAINT") {
+            true => Some(parser.expect_identifier("constraint name")?),
+            false => None,
+        };
+        let kind = if parser.consume_keyword("PRIMARY") {
+            parser.expect_keyword("KEY")?;
+            TableConstraintKind::PrimaryKey(Self::parse_column_names(parser)?)
+        } else if parser.consume_keyword("UNIQUE") {
+            TableConstraintKind::Unique(Self::parse_column_names(parser)?)
+        } else if parser.consume_keyword("FOREIGN") {
+            parser.expect_keyword("KEY")?;
+            let columns = Self::parse_column_names(parser)?;
+            parser.expect_keyword("REFERENCES")?;
+            TableConstraintKind::ForeignKey {
+                columns,
+                references: Self::parse_references(parser)?,
+            }
+        } else if parser.consume_keyword("CHECK") {
+            TableConstraintKind::Check(Self::parse_check(parser)?)
+        } else {
+            return Err(parser.expected("table constraint"));
+        };
+        Ok(TableConstraint { name, kind })
+    }
+
+    /// Parses the `table [(column, ...)]` following REFERENCES.
+    fn parse_references<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<ForeignKeyReference, ERR> {
+        let table = parser.expect_identifier("table name")?;
+        let columns = match parser.peek_token() {
+            Some(Token::LeftParen) => Self::parse_column_names(parser)?,
+            _ => Vec::new(),
+        };
+        Ok(ForeignKeyReference { table, columns })
+    }
+
+    /// Parses the `(expression)` following CHECK.
+    fn parse_check<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Expression, ERR> {
+        parser.expect_token(&Token::LeftParen)?;
+        let expression = ExpressionParser::parse(parser)?;
+        parser.expect_token(&Token::RightParen)?;
+        Ok(expression)
+    }
+
+    fn parse_column_names<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Vec<String>, ERR> {
+        parser.expect_token(&Token::LeftParen)?;
+        let mut columns = Vec::new();
+        loop {
+            columns.push(parser.expect_identifier("column name")?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
             }
+            parser.consume_token();
         }
-        Err(ERR::cause("empty token parse fail"))
+        parser.expect_token(&Token::RightParen)?;
+        Ok(columns)
     }
 }
 
+#[cfg(test)]
 mod tests {
     use crate::{
-        ast::{CreateStatement, SQLStatement},
+        ast::{
+            BinaryOperator, ColumnConstraint, ColumnDefinition, ColumnOption, CreateStatement,
+            DataType, Expression, ForeignKeyReference, SQLStatement, TableConstraint,
+            TableConstraintKind,
+        },
         parser::{
             create::CreateStatementParser,
-            parser::{Parser, ParserError, SQLParser, SimpleParser},
+            parse_sql,
+            parser::{ParserError, SQLParser, SimpleParser},
         },
         token::Token,
+        value::Value,
     };
 
     #[test]
@@ -69,4 +280,153 @@ mod tests {
         let ast: Result<SQLStatement, ParserError> = CreateStatementParser::parse(&mut parser);
         println!("{:?}", ast.unwrap());
     }
+
+    #[test]
+    fn test_if_not_exists() {
+        let mut expected = CreateStatement::new(
+            "t".to_string(),
+            vec![ColumnDefinition::new("a".to_string())],
+        );
+        expected.if_not_exists = true;
+        assert_eq!(
+            parse_sql("CREATE TABLE IF NOT EXISTS t (a)"),
+            Ok(SQLStatement::CreateTable(expected))
+        );
+        assert_eq!(
+            parse_sql("CREATE TABLE if (a)"),
+            Ok(SQLStatement::CreateTable(CreateStatement::new(
+                "if".to_string(),
+                vec![ColumnDefinition::new("a".to_string())]
+            )))
+        );
+        for sql in [
+            "CREATE TABLE IF NOT t (a)",
+            "CREATE TABLE IF NOT EXISTS (a)",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_types_and_constraints() {
+        let column = |name: &str, data_type, options: Vec<ColumnOption>| {
+            let mut column = ColumnDefinition::new(name.to_string());
+            column.data_type = data_type;
+            column.constraints = options
+                .into_iter()
+                .map(|option| ColumnConstraint { name: None, option })
+                .collect();
+            column
+        };
+        let mut expected = CreateStatement::new(
+            "orders".to_string(),
+            vec![
+                column(
+                    "id",
+                    Some(DataType::Integer),
+                    vec![ColumnOption::PrimaryKey],
+                ),
+                column(
+                    "user_id",
+                    Some(DataType::BigInt),
+                    vec![
+                        ColumnOption::NotNull,
+                        ColumnOption::References(ForeignKeyReference {
+                            table: "users".to_string(),
+                            columns: vec!["id".to_string()],
+                        }),
+                    ],
+                ),
+                column(
+                    "code",
+                    Some(DataType::Varchar(Some(20))),
+                    vec![ColumnOption::Unique, ColumnOption::Null],
+                ),
+                column(
+                    "price",
+                    Some(DataType::Decimal {
+                        precision: Some(10),
+                        scale: Some(2),
+                    }),
+                    vec![
+                        ColumnOption::Default(Expression::binary(
+                            Expression::Literal(Value::Int64(1)),
+                            BinaryOperator::Plus,
+                            Expression::Literal(Value::Int64(2)),
+                        )),
+                        ColumnOption::NotNull,
+                    ],
+                ),
+                column("note", None, Vec::new()),
+                column("paid", Some(DataType::Boolean), Vec::new()),
+                column("created", Some(DataType::Timestamp), Vec::new()),
+            ],
+        );
+        expected.columns[3].constraints.push(ColumnConstraint {
+            name: Some("positive".to_string()),
+            option: ColumnOption::Check(Expression::binary(
+                Expression::Column("price".to_string()),
+                BinaryOperator::GreaterThan,
+                Expression::Literal(Value::Int64(0)),
+            )),
+        });
+        expected.constraints = vec![
+            TableConstraint {
+                name: None,
+                kind: TableConstraintKind::Unique(vec!["user_id".to_string(), "code".to_string()]),
+            },
+            TableConstraint {
+                name: Some("fk_user".to_string()),
+                kind: TableConstraintKind::ForeignKey {
+                    columns: vec!["user_id".to_string()],
+                    references: ForeignKeyReference {
+                        table: "users".to_string(),
+                        columns: Vec::new(),
+                    },
+                },
+            },
+            TableConstraint {
+                name: None,
+                kind: TableConstraintKind::Check(Expression::Column("paid".to_string())),
+            },
+        ];
+        assert_eq!(
+            parse_sql(
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, \
+                 user_id BIGINT NOT NULL REFERENCES users (id), \
+                 code VARCHAR(20) UNIQUE NULL, \
+                 price DECIMAL(10, 2) DEFAULT 1 + 2 NOT NULL CONSTRAINT positive CHECK (price > 0), \
+                 note, paid bool, created timestamp, UNIQUE (user_id, code), \
+                 CONSTRAINT fk_user FOREIGN KEY (user_id) REFERENCES users, CHECK (paid))"
+            ),
+            Ok(SQLStatement::CreateTable(expected))
+        );
+
+        // constraint keywords are not reserved
+        assert!(parse_sql("CREATE TABLE t (unique INT, check TEXT, primary, key)").is_ok());
+
+        for sql in [
+            "CREATE TABLE t (a STRING)",
+            "CREATE TABLE t (a VARCHAR(1, 2))",
+            "CREATE TABLE t (a DECIMAL(1.5))",
+            "CREATE TABLE t (a INT NOT)",
+            "CREATE TABLE t (a INT PRIMARY)",
+            "CREATE TABLE t (a INT CONSTRAINT c)",
+            "CREATE TABLE t (a INT CHECK a > 0)",
+            "CREATE TABLE t (a INT, FOREIGN KEY (a))",
+            "CREATE TABLE t (a INT, a TEXT)",
+            "CREATE TABLE t (a INT NULL NOT NULL)",
+            "CREATE TABLE t (a INT PRIMARY KEY, b INT PRIMARY KEY)",
+            "CREATE TABLE t (a INT PRIMARY KEY, PRIMARY KEY (a))",
+            "CREATE TABLE t (a INT, UNIQUE (b))",
+            "CREATE TABLE t (a INT, FOREIGN KEY (a) REFERENCES u (b, c))",
+            "CREATE TABLE t (a INT DEFAULT b)",
+            "CREATE TABLE t (a INT DEFAULT (SELECT 1))",
+            "CREATE TABLE t (a INT CHECK (a > (SELECT 1)))",
+            "CREATE TABLE t (a INT CHECK (COUNT(*) > 1))",
+            "CREATE TABLE t (a INT DEFAULT ?)",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
 }
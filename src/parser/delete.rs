@@ -1,64 +1,53 @@
 use crate::{
-    ast::{DeleteStatement, Expression, SQLStatement, Value, WhereClause},
+    ast::{DeleteStatement, SQLStatement},
     token::Token,
     types::DBError,
 };
 
-use super::parser::{Parser, SQLParser};
+use super::{
+    expression::ExpressionParser,
+    parser::{Parser, SQLParser},
+    select::SelectStatementParser,
+};
 
-struct DeleteStatementParser;
+pub struct DeleteStatementParser;
 impl<P, ERR> SQLParser<P, ERR> for DeleteStatementParser
 where
     P: Parser,
     ERR: DBError,
 {
     fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
-        if parser.peek_token() == Some(&Token::Delete) {
-            parser.consume_token();
-            if parser.next_token() == Some(Token::From) {
-                if let Some(Token::Identifier(table_name)) = parser.next_token() {
-                    if let Some(token) = parser.next_token() {
-                        match token {
-                            Token::Semicolon => {
-                                return Ok(SQLStatement::Delete(DeleteStatement::new(
-                                    table_name, None,
-                                )))
-                            }
-                            Token::Where => {
-                                if let Some(Token::Identifier(column)) = parser.next_token() {
-                                    if let Some(Token::Equal) = parser.next_token() {
-                                        if let Some(Token::StringLiteral(value)) =
-                                            parser.next_token()
-                                        {
-                                            return Ok(SQLStatement::Delete(DeleteStatement::new(
-                                                table_name,
-                                                Some(WhereClause::new(Expression::new(
-                                                    column,
-                                                    Value::StrValue(value),
-                                                ))),
-                                            )));
-                                        }
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
+        parser.expect_token(&Token::Delete)?;
+        parser.expect_token(&Token::From)?;
+        let table_name = parser.expect_identifier("table name")?;
+        let using = match parser.peek_token() {
+            Some(Token::Using) => {
+                parser.consume_token();
+                SelectStatementParser::parse_from(parser)?
             }
-        }
-        return Err(ERR::cause("parse fail delete"));
+            _ => Vec::new(),
+        };
+        let where_clause = ExpressionParser::parse_where(parser)?;
+        let mut statement = DeleteStatement::new(table_name, where_clause);
+        statement.using = using;
+        statement.returning = SelectStatementParser::parse_returning(parser)?;
+        Ok(SQLStatement::Delete(statement))
     }
 }
 
+#[cfg(test)]
 mod tests {
     use crate::{
-        ast::SQLStatement,
+        ast::{
+            BinaryOperator, DeleteStatement, Expression, SQLStatement, TableReference, WhereClause,
+        },
         parser::{
             delete::DeleteStatementParser,
+            parse_sql,
             parser::{ParserError, SQLParser, SimpleParser},
         },
         token::Token,
+        value::Value,
     };
 
     #[test]
@@ -89,4 +78,52 @@ mod tests {
         let ast: Result<SQLStatement, ParserError> = DeleteStatementParser::parse(&mut parser);
         println!("{:?}", ast.unwrap());
     }
+
+    #[test]
+    fn test_using() {
+        let qualified = |table: &str, column: &str| Expression::QualifiedColumn {
+            table: table.to_string(),
+            column: column.to_string(),
+        };
+        let mut expected = DeleteStatement::new(
+            "sessions".to_string(),
+            Some(WhereClause::new(Expression::binary(
+                Expression::binary(
+                    qualified("sessions", "user_id"),
+                    BinaryOperator::Equal,
+                    qualified("u", "id"),
+                ),
+                BinaryOperator::And,
+                Expression::binary(
+                    Expression::Column("age".to_string()),
+                    BinaryOperator::GreaterThan,
+                    Expression::Literal(Value::Int64(30)),
+                ),
+            ))),
+        );
+        expected.using = vec![
+            TableReference::Table {
+                name: "users".to_string(),
+                alias: Some("u".to_string()),
+            },
+            TableReference::Table {
+                name: "tokens".to_string(),
+                alias: None,
+            },
+        ];
+        assert_eq!(
+            parse_sql(
+                "DELETE FROM sessions USING users u, tokens WHERE sessions.user_id = u.id AND age > 30"
+            ),
+            Ok(SQLStatement::Delete(expected))
+        );
+        for sql in [
+            "DELETE FROM t USING",
+            "DELETE FROM t USING u WHERE",
+            "DELETE FROM t USING (SELECT 1) WHERE a = 1",
+            "DELETE FROM t WHERE SUM(a) > 1",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
 }
@@ -1,33 +1,50 @@
 use crate::{
-    ast::{DropStatement, SQLStatement},
+    ast::{DropBehavior, DropStatement, SQLStatement},
     token::Token,
     types::DBError,
 };
 
 use super::parser::{Parser, SQLParser};
 
-struct DropStatementParser;
+pub struct DropStatementParser;
 impl<P, ERR> SQLParser<P, ERR> for DropStatementParser
 where
     ERR: DBError,
     P: Parser,
 {
-    fn parse(parser: &mut P) -> Result<crate::ast::SQLStatement, ERR> {
-        if parser.consume_token() && parser.peek_token() == Some(&Token::Table) {
-            parser.consume_token();
-            if let Some(Token::Identifier(table_name)) = parser.next_token() {
-                return Ok(SQLStatement::DropTable(DropStatement::new(table_name)));
+    fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
+        parser.expect_token(&Token::Drop)?;
+        parser.expect_token(&Token::Table)?;
+        let if_exists = parser.peek_nth_keyword(0, "IF")
+            && parser.peek_nth_token(1) == Some(&Token::Exists)
+            && parser.consume_token()
+            && parser.consume_token();
+        let mut table_names = Vec::new();
+        loop {
+            table_names.push(parser.expect_identifier("table name")?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
             }
+            parser.consume_token();
+        }
+        let mut statement = DropStatement::new(table_names);
+        statement.if_exists = if_exists;
+        if parser.consume_keyword("CASCADE") {
+            statement.behavior = DropBehavior::Cascade;
+        } else {
+            parser.consume_keyword("RESTRICT");
         }
-        Err(ERR::cause("parse fail drop table"))
+        Ok(SQLStatement::DropTable(statement))
     }
 }
 
+#[cfg(test)]
 mod tests {
     use crate::{
-        ast::SQLStatement,
+        ast::{DropBehavior, DropStatement, SQLStatement},
         parser::{
             drop::DropStatementParser,
+            parse_sql,
             parser::{ParserError, SQLParser, SimpleParser},
         },
         token::Token,
@@ -45,4 +62,42 @@ mod tests {
         let ast: Result<SQLStatement, ParserError> = DropStatementParser::parse(&mut parser);
         println!("{:?}", ast.unwrap());
     }
+
+    #[test]
+    fn test_options() {
+        let drop = |names: &[&str], if_exists, behavior| {
+            let mut statement =
+                DropStatement::new(names.iter().map(|name| name.to_string()).collect());
+            statement.if_exists = if_exists;
+            statement.behavior = behavior;
+            Ok(SQLStatement::DropTable(statement))
+        };
+        let test_cases = [
+            ("DROP TABLE a", drop(&["a"], false, DropBehavior::Restrict)),
+            (
+                "DROP TABLE IF EXISTS a, b CASCADE",
+                drop(&["a", "b"], true, DropBehavior::Cascade),
+            ),
+            (
+                "drop table a restrict",
+                drop(&["a"], false, DropBehavior::Restrict),
+            ),
+            // IF is only a keyword before EXISTS
+            (
+                "DROP TABLE if, \"exists\"",
+                drop(&["if", "exists"], false, DropBehavior::Restrict),
+            ),
+        ];
+        for (sql, expected) in test_cases {
+            assert_eq!(parse_sql(sql), expected, "{}", sql);
+        }
+        for sql in [
+            "DROP TABLE",
+            "DROP TABLE IF EXISTS",
+            "DROP TABLE a,",
+            "DROP TABLE a CASCADE RESTRICT",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
 }
@@ -0,0 +1,680 @@
+use crate::{
+    ast::{BinaryOperator, Expression, Query, UnaryOperator, WhereClause},
+    token::Token,
+    types::DBError,
+    value::{Decimal, Value},
+};
+
+use super::{parser::Parser, select::SelectStatementParser};
+
+const OR_PRECEDENCE: u8 = 1;
+const AND_PRECEDENCE: u8 = 2;
+const NOT_PRECEDENCE: u8 = 3;
+const COMPARISON_PRECEDENCE: u8 = 4;
+const ADDITIVE_PRECEDENCE: u8 = 5;
+const MULTIPLICATIVE_PRECEDENCE: u8 = 6;
+const UNARY_PRECEDENCE: u8 = 7;
+
+/// Precedence-climbing parser for the expressions of WHERE and SET clauses.
+pub struct ExpressionParser;
+impl ExpressionParser {
+    pub fn parse<P, ERR>(parser: &mut P) -> Result<Expression, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        Self::parse_expression(parser, 0)
+    }
+
+    /// Parses an expression without comparisons or logical operators, like
+    /// the value of DEFAULT, which other column constraints may follow.
+    pub fn parse_operand<P, ERR>(parser: &mut P) -> Result<Expression, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        Self::parse_expression(parser, COMPARISON_PRECEDENCE)
+    }
+
+    /// Parses `WHERE <expression>` if the next token is `WHERE`.
+    pub fn parse_where<P, ERR>(parser: &mut P) -> Result<Option<WhereClause>, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        if parser.peek_token() != Some(&Token::Where) {
+            return Ok(None);
+        }
+        parser.consume_token();
+        Ok(Some(WhereClause::new(Self::parse(parser)?)))
+    }
+
+    /// Parses operators binding tighter than `min_precedence`, left-associatively.
+    fn parse_expression<P, ERR>(parser: &mut P, min_precedence: u8) -> Result<Expression, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        let mut left = Self::parse_prefix(parser)?;
+        loop {
+            if COMPARISON_PRECEDENCE > min_precedence && starts_predicate(parser.peek_token()) {
+                left = Self::parse_predicate(parser, left)?;
+                continue;
+            }
+            let Some((operator, precedence)) = parser.peek_token().and_then(binary_operator) else {
+                break;
+            };
+            if precedence <= min_precedence {
+                break;
+            }
+            parser.consume_token();
+            let right = Self::parse_expression(parser, precedence)?;
+            left = Expression::binary(left, operator, right);
+        }
+        Ok(left)
+    }
+
+    /// Parses the `[NOT] IN`, `[NOT] BETWEEN`, `[NOT] LIKE|ILIKE` and
+    /// `IS [NOT] NULL` predicates following `expression`.
+    fn parse_predicate<P, ERR>(parser: &mut P, expression: Expression) -> Result<Expression, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        let expression = Box::new(expression);
+        let negated = parser.peek_token() == Some(&Token::Not);
+        if negated {
+            parser.consume_token();
+        }
+        match parser.peek_token() {
+            Some(Token::In) => {
+                parser.consume_token();
+                parser.expect_token(&Token::LeftParen)?;
+                if matches!(parser.peek_token(), Some(Token::Select | Token::With)) {
+                    let subquery = SelectStatementParser::parse_query(parser)?;
+                    parser.expect_token(&Token::RightParen)?;
+                    return Ok(Expression::InSubquery {
+                        expression,
+                        subquery: Box::new(subquery),
+                        negated,
+                    });
+                }
+                let mut list = Vec::new();
+                loop {
+                    list.push(Self::parse(parser)?);
+                    if parser.peek_token() != Some(&Token::Comma) {
+                        break;
+                    }
+                    parser.consume_token();
+                }
+                parser.expect_token(&Token::RightParen)?;
+                Ok(Expression::InList {
+                    expression,
+                    list,
+                    negated,
+                })
+            }
+            Some(Token::Between) => {
+                parser.consume_token();
+                let low = Self::parse_expression(parser, COMPARISON_PRECEDENCE)?;
+                parser.expect_token(&Token::And)?;
+                let high = Self::parse_expression(parser, COMPARISON_PRECEDENCE)?;
+                Ok(Expression::Between {
+                    expression,
+                    low: Box::new(low),
+                    high: Box::new(high),
+                    negated,
+                })
+            }
+            Some(Token::Like) | Some(Token::ILike) => {
+                let case_insensitive = parser.next_token() == Some(Token::ILike);
+                let pattern = Self::parse_expression(parser, COMPARISON_PRECEDENCE)?;
+                let mut escape = None;
+                if parser.peek_token() == Some(&Token::Escape) {
+                    parser.consume_token();
+                    // ESCAPE '' leaves the pattern without an escape character
+                    escape = match parser.peek_token() {
+                        Some(Token::StringLiteral(value)) if value.chars().count() <= 1 => {
+                            value.chars().next()
+                        }
+                        _ => return Err(parser.expected("single character escape string")),
+                    };
+                    parser.consume_token();
+                }
+                Ok(Expression::Like {
+                    expression,
+                    pattern: Box::new(pattern),
+                    escape,
+                    case_insensitive,
+                    negated,
+                })
+            }
+            Some(Token::Is) if !negated => {
+                parser.consume_token();
+                let negated = parser.peek_token() == Some(&Token::Not);
+                if negated {
+                    parser.consume_token();
+                }
+                parser.expect_token(&Token::Null)?;
+                Ok(Expression::IsNull {
+                    expression,
+                    negated,
+                })
+            }
+            _ => Err(parser.expected("IN, BETWEEN, LIKE or ILIKE")),
+        }
+    }
+
+    fn parse_prefix<P, ERR>(parser: &mut P) -> Result<Expression, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        let (operator, precedence) = match parser.peek_token() {
+            Some(Token::Not) => (UnaryOperator::Not, NOT_PRECEDENCE),
+            Some(Token::Minus) => (UnaryOperator::Minus, UNARY_PRECEDENCE),
+            Some(Token::Plus) => (UnaryOperator::Plus, UNARY_PRECEDENCE),
+            _ => return Self::parse_primary(parser),
+        };
+        parser.consume_token();
+        let operand = Self::parse_expression(parser, precedence)?;
+        // a negated number is a literal, so that the smallest bigint is one
+        let negated = match (operator, &operand) {
+            (UnaryOperator::Minus, Expression::Literal(Value::Int64(value))) => {
+                value.checked_neg().map(Value::Int64)
+            }
+            (UnaryOperator::Minus, Expression::Literal(Value::Decimal(value))) => value
+                .checked_neg()
+                .map(|value| match value == Decimal::from(i64::MIN) {
+                    true => Value::Int64(i64::MIN),
+                    false => Value::Decimal(value),
+                }),
+            (UnaryOperator::Minus, Expression::Literal(Value::Float64(value))) => {
+                Some(Value::Float64(-value))
+            }
+            _ => None,
+        };
+        Ok(match negated {
+            Some(value) => Expression::Literal(value),
+            None => Expression::unary(operator, operand),
+        })
+    }
+
+    fn parse_primary<P, ERR>(parser: &mut P) -> Result<Expression, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        let expression = match parser.peek_token() {
+            Some(Token::Identifier(_) | Token::QuotedIdentifier(_))
+                if parser.peek_nth_token(1) == Some(&Token::LeftParen) =>
+            {
+                return Self::parse_function(parser);
+            }
+            Some(Token::Identifier(_) | Token::QuotedIdentifier(_))
+                if parser.peek_nth_token(1) == Some(&Token::Dot) =>
+            {
+                let table = parser.expect_identifier("table name")?;
+                parser.consume_token();
+                let column = parser.expect_identifier("column name")?;
+                return Ok(Expression::QualifiedColumn { table, column });
+            }
+            // the SQL standard spells this call without parentheses
+            Some(Token::Identifier(name)) if name == "current_timestamp" => Expression::Function {
+                name: name.clone(),
+                arguments: Vec::new(),
+                distinct: false,
+                wildcard: false,
+            },
+            Some(Token::Identifier(name) | Token::QuotedIdentifier(name)) => {
+                Expression::Column(name.clone())
+            }
+            Some(Token::StringLiteral(value)) => Expression::Literal(Value::Text(value.clone())),
+            Some(Token::IntegerLiteral(value)) => Expression::Literal(Value::Int64(*value)),
+            Some(Token::DecimalLiteral(value)) => Expression::Literal(Value::Decimal(*value)),
+            Some(Token::FloatLiteral(value)) => Expression::Literal(Value::Float64(*value)),
+            Some(Token::BlobLiteral(bytes)) => Expression::Literal(Value::Bytes(bytes.clone())),
+            Some(Token::True) => Expression::Literal(Value::Boolean(true)),
+            Some(Token::False) => Expression::Literal(Value::Boolean(false)),
+            Some(Token::Null) => Expression::Literal(Value::Null),
+            Some(
+                placeholder @ (Token::QuestionMark
+                | Token::PositionalParameter(_)
+                | Token::NamedParameter(_)),
+            ) => {
+                let placeholder = placeholder.clone();
+                let span = parser.peek_span();
+                let index = parser
+                    .placeholders()
+                    .bind(&placeholder)
+                    .map_err(|err: ERR| err.at(span))?;
+                Expression::Parameter(index)
+            }
+            Some(Token::LeftParen)
+                if matches!(parser.peek_nth_token(1), Some(Token::Select | Token::With)) =>
+            {
+                return Ok(Expression::Subquery(Box::new(Self::parse_subquery(
+                    parser,
+                )?)));
+            }
+            Some(Token::Exists) => {
+                parser.consume_token();
+                return Ok(Expression::Exists(Box::new(Self::parse_subquery(parser)?)));
+            }
+            Some(Token::LeftParen) => {
+                parser.consume_token();
+                let expression = Self::parse(parser)?;
+                parser.expect_token(&Token::RightParen)?;
+                return Ok(expression);
+            }
+            _ => return Err(parser.expected("expression")),
+        };
+        parser.consume_token();
+        Ok(expression)
+    }
+
+    /// Parses a parenthesized `([WITH ...] SELECT ...)`.
+    pub fn parse_subquery<P, ERR>(parser: &mut P) -> Result<Query, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        parser.expect_token(&Token::LeftParen)?;
+        let subquery = SelectStatementParser::parse_query(parser)?;
+        parser.expect_token(&Token::RightParen)?;
+        Ok(subquery)
+    }
+
+    /// Parses `name()`, `name(*)` and `name([DISTINCT] expression, ...)`.
+    fn parse_function<P, ERR>(parser: &mut P) -> Result<Expression, ERR>
+    where
+        P: Parser,
+        ERR: DBError,
+    {
+        let name = parser.expect_identifier("function name")?;
+        parser.expect_token(&Token::LeftParen)?;
+        let mut arguments = Vec::new();
+        let mut distinct = false;
+        let mut wildcard = false;
+        match parser.peek_token() {
+            Some(Token::RightParen) => {}
+            Some(Token::Asterisk) => {
+                parser.consume_token();
+                wildcard = true;
+            }
+            _ => {
+                if parser.peek_token() == Some(&Token::Distinct) {
+                    parser.consume_token();
+                    distinct = true;
+                }
+                loop {
+                    arguments.push(Self::parse(parser)?);
+                    if parser.peek_token() != Some(&Token::Comma) {
+                        break;
+                    }
+                    parser.consume_token();
+                }
+            }
+        }
+        parser.expect_token(&Token::RightParen)?;
+        Ok(Expression::Function {
+            name,
+            arguments,
+            distinct,
+            wildcard,
+        })
+    }
+}
+
+fn starts_predicate(token: Option<&Token>) -> bool {
+    matches!(
+        token,
+        Some(Token::Not)
+            | Some(Token::In)
+            | Some(Token::Between)
+            | Some(Token::Like)
+            | Some(Token::ILike)
+            | Some(Token::Is)
+    )
+}
+
+fn binary_operator(token: &Token) -> Option<(BinaryOperator, u8)> {
+    let operator = match token {
+        Token::Or => (BinaryOperator::Or, OR_PRECEDENCE),
+        Token::And => (BinaryOperator::And, AND_PRECEDENCE),
+        Token::Equal => (BinaryOperator::Equal, COMPARISON_PRECEDENCE),
+        Token::NotEqual => (BinaryOperator::NotEqual, COMPARISON_PRECEDENCE),
+        Token::LessThan => (BinaryOperator::LessThan, COMPARISON_PRECEDENCE),
+        Token::LessThanOrEqual => (BinaryOperator::LessThanOrEqual, COMPARISON_PRECEDENCE),
+        Token::GreaterThan => (BinaryOperator::GreaterThan, COMPARISON_PRECEDENCE),
+        Token::GreaterThanOrEqual => (BinaryOperator::GreaterThanOrEqual, COMPARISON_PRECEDENCE),
+        Token::Plus => (BinaryOperator::Plus, ADDITIVE_PRECEDENCE),
+        Token::Minus => (BinaryOperator::Minus, ADDITIVE_PRECEDENCE),
+        Token::Asterisk => (BinaryOperator::Multiply, MULTIPLICATIVE_PRECEDENCE),
+        Token::Slash => (BinaryOperator::Divide, MULTIPLICATIVE_PRECEDENCE),
+        Token::Percent => (BinaryOperator::Modulo, MULTIPLICATIVE_PRECEDENCE),
+        _ => return None,
+    };
+    Some(operator)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            BinaryOperator, Expression, Query, SelectItem, SelectStatement, TableReference,
+            UnaryOperator,
+        },
+        lexer::{SimpleLexer, Tokenize},
+        parser::parser::{Parser, ParserError, SimpleParser},
+        value::Value,
+    };
+
+    use super::ExpressionParser;
+
+    fn parse(input: &str) -> Result<Expression, ParserError> {
+        let tokens = SimpleLexer::<ParserError>::tokenize_spanned(input.to_string())?;
+        let mut parser = SimpleParser::with_spans(tokens);
+        let expression = ExpressionParser::parse(&mut parser)?;
+        assert_eq!(parser.peek_token(), None);
+        Ok(expression)
+    }
+
+    fn column(name: &str) -> Expression {
+        Expression::Column(name.to_string())
+    }
+
+    fn number(value: i64) -> Expression {
+        Expression::Literal(Value::Int64(value))
+    }
+
+    #[test]
+    fn test_precedence() {
+        let test_cases = vec![
+            (
+                "age >= 18 AND (city = 'Seoul' OR vip)",
+                Expression::binary(
+                    Expression::binary(
+                        column("age"),
+                        BinaryOperator::GreaterThanOrEqual,
+                        number(18),
+                    ),
+                    BinaryOperator::And,
+                    Expression::binary(
+                        Expression::binary(
+                            column("city"),
+                            BinaryOperator::Equal,
+                            Expression::Literal(Value::Text("Seoul".to_string())),
+                        ),
+                        BinaryOperator::Or,
+                        column("vip"),
+                    ),
+                ),
+            ),
+            (
+                "a OR b AND NOT c",
+                Expression::binary(
+                    column("a"),
+                    BinaryOperator::Or,
+                    Expression::binary(
+                        column("b"),
+                        BinaryOperator::And,
+                        Expression::unary(UnaryOperator::Not, column("c")),
+                    ),
+                ),
+            ),
+            (
+                "1 + 2 * 3 - 4 % 5",
+                Expression::binary(
+                    Expression::binary(
+                        number(1),
+                        BinaryOperator::Plus,
+                        Expression::binary(number(2), BinaryOperator::Multiply, number(3)),
+                    ),
+                    BinaryOperator::Minus,
+                    Expression::binary(number(4), BinaryOperator::Modulo, number(5)),
+                ),
+            ),
+            (
+                "NOT -a * 2 <> b / (c - 1)",
+                Expression::unary(
+                    UnaryOperator::Not,
+                    Expression::binary(
+                        Expression::binary(
+                            Expression::unary(UnaryOperator::Minus, column("a")),
+                            BinaryOperator::Multiply,
+                            number(2),
+                        ),
+                        BinaryOperator::NotEqual,
+                        Expression::binary(
+                            column("b"),
+                            BinaryOperator::Divide,
+                            Expression::binary(column("c"), BinaryOperator::Minus, number(1)),
+                        ),
+                    ),
+                ),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(parse(input), Ok(expected), "{}", input);
+        }
+    }
+
+    fn string(value: &str) -> Expression {
+        Expression::Literal(Value::Text(value.to_string()))
+    }
+
+    #[test]
+    fn test_predicates() {
+        let test_cases = vec![
+            (
+                "status IN ('a', 'b')",
+                Expression::InList {
+                    expression: Box::new(column("status")),
+                    list: vec![string("a"), string("b")],
+                    negated: false,
+                },
+            ),
+            (
+                "created NOT BETWEEN x AND y + 1 AND ok",
+                Expression::binary(
+                    Expression::Between {
+                        expression: Box::new(column("created")),
+                        low: Box::new(column("x")),
+                        high: Box::new(Expression::binary(
+                            column("y"),
+                            BinaryOperator::Plus,
+                            number(1),
+                        )),
+                        negated: true,
+                    },
+                    BinaryOperator::And,
+                    column("ok"),
+                ),
+            ),
+            (
+                "name LIKE 'kim%'",
+                Expression::Like {
+                    expression: Box::new(column("name")),
+                    pattern: Box::new(string("kim%")),
+                    escape: None,
+                    case_insensitive: false,
+                    negated: false,
+                },
+            ),
+            (
+                "code NOT ILIKE '10!%_' ESCAPE '!'",
+                Expression::Like {
+                    expression: Box::new(column("code")),
+                    pattern: Box::new(string("10!%_")),
+                    escape: Some('!'),
+                    case_insensitive: true,
+                    negated: true,
+                },
+            ),
+            (
+                "code LIKE '10\\%' ESCAPE ''",
+                Expression::Like {
+                    expression: Box::new(column("code")),
+                    pattern: Box::new(string("10\\%")),
+                    escape: None,
+                    case_insensitive: false,
+                    negated: false,
+                },
+            ),
+            (
+                "deleted_at IS NULL OR NOT a IS NOT NULL",
+                Expression::binary(
+                    Expression::IsNull {
+                        expression: Box::new(column("deleted_at")),
+                        negated: false,
+                    },
+                    BinaryOperator::Or,
+                    Expression::unary(
+                        UnaryOperator::Not,
+                        Expression::IsNull {
+                            expression: Box::new(column("a")),
+                            negated: true,
+                        },
+                    ),
+                ),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(parse(input), Ok(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_functions() {
+        let test_cases = vec![
+            (
+                "COUNT(*)",
+                Expression::Function {
+                    name: "count".to_string(),
+                    arguments: vec![],
+                    distinct: false,
+                    wildcard: true,
+                },
+            ),
+            (
+                "count(DISTINCT a) + 1",
+                Expression::binary(
+                    Expression::Function {
+                        name: "count".to_string(),
+                        arguments: vec![column("a")],
+                        distinct: true,
+                        wildcard: false,
+                    },
+                    BinaryOperator::Plus,
+                    number(1),
+                ),
+            ),
+            (
+                "coalesce(a, b * 2)",
+                Expression::function(
+                    "coalesce",
+                    vec![
+                        column("a"),
+                        Expression::binary(column("b"), BinaryOperator::Multiply, number(2)),
+                    ],
+                ),
+            ),
+            ("now()", Expression::function("now", vec![])),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(parse(input), Ok(expected), "{}", input);
+        }
+        assert!(parse("SUM(a,)").is_err());
+        assert!(parse("SUM(a").is_err());
+    }
+
+    #[test]
+    fn test_subqueries() {
+        let select = |column: &str, table: &str| {
+            Query::from(SelectStatement::new(
+                vec![TableReference::table(table)],
+                vec![SelectItem::expression(Expression::Column(
+                    column.to_string(),
+                ))],
+                None,
+            ))
+        };
+        let test_cases = vec![
+            (
+                "id NOT IN (SELECT user_id FROM orders)",
+                Expression::InSubquery {
+                    expression: Box::new(column("id")),
+                    subquery: Box::new(select("user_id", "orders")),
+                    negated: true,
+                },
+            ),
+            (
+                "NOT EXISTS (SELECT id FROM t)",
+                Expression::unary(
+                    UnaryOperator::Not,
+                    Expression::Exists(Box::new(select("id", "t"))),
+                ),
+            ),
+            (
+                "(SELECT id FROM t) + (1)",
+                Expression::binary(
+                    Expression::Subquery(Box::new(select("id", "t"))),
+                    BinaryOperator::Plus,
+                    number(1),
+                ),
+            ),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(parse(input), Ok(expected), "{}", input);
+        }
+        assert!(parse("EXISTS id").is_err());
+        assert!(parse("a IN (SELECT b FROM t").is_err());
+    }
+
+    #[test]
+    fn test_literals() {
+        let literal = |value| Expression::Literal(value);
+        let test_cases = vec![
+            ("-5", number(-5)),
+            ("-9223372036854775808", number(i64::MIN)),
+            ("- 2.5", literal(Value::Decimal("-2.5".parse().unwrap()))),
+            ("-a", Expression::unary(UnaryOperator::Minus, column("a"))),
+            (
+                "1-2",
+                Expression::binary(number(1), BinaryOperator::Minus, number(2)),
+            ),
+            ("TRUE", literal(Value::Boolean(true))),
+            ("false", literal(Value::Boolean(false))),
+            ("NULL", literal(Value::Null)),
+            ("X'0A'", literal(Value::Bytes(vec![10]))),
+        ];
+        for (input, expected) in test_cases {
+            assert_eq!(parse(input), Ok(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse("a = (b + 1").unwrap_err().message(),
+            "expected `)`, found end of input"
+        );
+        assert_eq!(
+            parse("a AND OR b").unwrap_err().message(),
+            "expected expression, found `OR`"
+        );
+        assert_eq!(
+            parse("a NOT NULL").unwrap_err().message(),
+            "expected IN, BETWEEN, LIKE or ILIKE, found `NULL`"
+        );
+        assert_eq!(
+            parse("a LIKE 'x' ESCAPE 'ab'").unwrap_err().message(),
+            "expected single character escape string, found `'ab'`"
+        );
+        assert_eq!(
+            parse("a BETWEEN 1 OR 2").unwrap_err().message(),
+            "expected `AND`, found `OR`"
+        );
+    }
+}
@@ -1,76 +1,111 @@
 use crate::{
-    ast::{InsertStatement, SQLStatement, Value},
+    ast::{InsertSource, InsertStatement, InsertValue, SQLStatement},
     token::Token,
     types::DBError,
 };
 
-use super::parser::{Parser, SQLParser};
+use super::{
+    expression::ExpressionParser,
+    parser::{Parser, SQLParser},
+    select::SelectStatementParser,
+};
 
-struct InsertStatementParser;
+pub struct InsertStatementParser;
 impl<P, ERR> SQLParser<P, ERR> for InsertStatementParser
 where
     P: Parser,
     ERR: DBError,
 {
     fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
-        if parser.peek_token() == Some(&Token::Insert) {
+        parser.expect_token(&Token::Insert)?;
+        if parser.peek_token() == Some(&Token::Into) {
+            parser.consume_token();
+        }
+        let table_name = parser.expect_identifier("table name")?;
+        let mut columns = Vec::new();
+        // `INSERT INTO t (SELECT ...)` has a parenthesized query but no columns
+        let starts_query = matches!(
+            parser.peek_nth_token(1),
+            Some(Token::Select | Token::With | Token::LeftParen)
+        );
+        if parser.peek_token() == Some(&Token::LeftParen) && !starts_query {
             parser.consume_token();
-            if parser.peek_token() == Some(&Token::Into) {
+            loop {
+                columns.push(parser.expect_identifier("column name")?);
+                if parser.peek_token() != Some(&Token::Comma) {
+                    break;
+                }
                 parser.consume_token();
             }
-            if let Some(Token::Identifier(table_name)) = parser.next_token() {
-                parser.consume_token();
-                let mut columns = Vec::new();
-                loop {
-                    if let Some(Token::Identifier(name)) = parser.next_token() {
-                        columns.push(name);
-                        if parser.peek_token() == Some(&Token::Comma) {
-                            parser.consume_token();
-                        } else {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
-                }
-                if parser.peek_token() == Some(&Token::RightParen) {
-                    parser.consume_token();
-                    if let Some(Token::Values) = parser.next_token() {
-                        parser.consume_token();
-                        let mut values = Vec::new();
-                        loop {
-                            if let Some(Token::StringLiteral(value)) = parser.next_token() {
-                                values.push(Value::StrValue(value));
-                                if parser.peek_token() == Some(&Token::Comma) {
-                                    parser.consume_token();
-                                } else {
-                                    break;
-                                }
-                            } else {
-                                break;
-                            }
-                        }
-                        if parser.peek_token() == Some(&Token::RightParen) {
-                            return Ok(SQLStatement::Insert(InsertStatement::new(
-                                table_name, columns, values,
-                            )));
-                        }
-                    }
+            parser.expect_token(&Token::RightParen)?;
+        }
+        let source = if parser.peek_token() == Some(&Token::Values) {
+            parser.consume_token();
+            let mut values = Vec::new();
+            loop {
+                values.push(Self::parse_tuple(parser)?);
+                if parser.peek_token() != Some(&Token::Comma) {
+                    break;
                 }
+                parser.consume_token();
+            }
+            InsertSource::Values(values)
+        } else if matches!(
+            parser.peek_token(),
+            Some(Token::Select | Token::With | Token::LeftParen)
+        ) {
+            InsertSource::Query(Box::new(SelectStatementParser::parse_query(parser)?))
+        } else {
+            return Err(parser.expected("`VALUES` or a query"));
+        };
+        let mut statement = InsertStatement::new(table_name, columns, source);
+        statement.returning = SelectStatementParser::parse_returning(parser)?;
+        Ok(SQLStatement::Insert(statement))
+    }
+}
+
+impl InsertStatementParser {
+    /// Parses `(value, ...)`, where each value is an expression or DEFAULT.
+    fn parse_tuple<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Vec<InsertValue>, ERR> {
+        parser.expect_token(&Token::LeftParen)?;
+        let mut tuple = Vec::new();
+        loop {
+            // DEFAULT is not reserved, so it only stands alone as a value
+            let is_default = parser.peek_nth_keyword(0, "DEFAULT")
+                && matches!(
+                    parser.peek_nth_token(1),
+                    Some(Token::Comma | Token::RightParen)
+                );
+            if is_default {
+                parser.consume_token();
+                tuple.push(InsertValue::Default);
+            } else {
+                tuple.push(InsertValue::Expression(ExpressionParser::parse(parser)?));
+            }
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
             }
+            parser.consume_token();
         }
-        Err(ERR::cause("parse fail insert"))
+        parser.expect_token(&Token::RightParen)?;
+        Ok(tuple)
     }
 }
 
+#[cfg(test)]
 mod tests {
     use crate::{
-        ast::SQLStatement,
+        ast::{
+            BinaryOperator, Expression, InsertSource, InsertStatement, InsertValue, SQLStatement,
+            SelectItem,
+        },
         parser::{
             insert::InsertStatementParser,
+            parse_sql,
             parser::{ParserError, SQLParser, SimpleParser},
         },
         token::Token,
+        value::Value,
     };
 
     #[test]
@@ -96,4 +131,129 @@ mod tests {
         let ast: Result<SQLStatement, ParserError> = InsertStatementParser::parse(&mut parser);
         println!("{:?}", ast.unwrap());
     }
+
+    #[test]
+    fn test_values() {
+        let literal = |value| InsertValue::Expression(Expression::Literal(value));
+        assert_eq!(
+            parse_sql("INSERT INTO t VALUES (1, 2.5, 'a'), (-1, NULL, DEFAULT), (1 + 2, TRUE, ?)"),
+            Ok(SQLStatement::Insert(InsertStatement::new(
+                "t".to_string(),
+                Vec::new(),
+                InsertSource::Values(vec![
+                    vec![
+                        literal(Value::Int64(1)),
+                        literal(Value::Decimal("2.5".parse().unwrap())),
+                        literal(Value::Text("a".to_string())),
+                    ],
+                    vec![
+                        literal(Value::Int64(-1)),
+                        literal(Value::Null),
+                        InsertValue::Default,
+                    ],
+                    vec![
+                        InsertValue::Expression(Expression::binary(
+                            Expression::Literal(Value::Int64(1)),
+                            BinaryOperator::Plus,
+                            Expression::Literal(Value::Int64(2)),
+                        )),
+                        literal(Value::Boolean(true)),
+                        InsertValue::Expression(Expression::Parameter(0)),
+                    ],
+                ]),
+            )))
+        );
+        for sql in [
+            "INSERT INTO t VALUES",
+            "INSERT INTO t VALUES ()",
+            "INSERT INTO t () VALUES (1)",
+            "INSERT INTO t VALUES (1), ",
+            "INSERT INTO t VALUES (1), (2, 3)",
+            "INSERT INTO t (a, b) VALUES (1)",
+            "INSERT INTO t (a) VALUES (1, 2)",
+            "INSERT INTO t (a, a) VALUES (1, 2)",
+            "INSERT INTO t (a) VALUES (b)",
+            "INSERT INTO t (a) VALUES (-DEFAULT)",
+            "INSERT INTO t (a) VALUES (COUNT(*))",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_query_and_returning() {
+        let column = |name: &str| Expression::Column(name.to_string());
+        let Ok(SQLStatement::Insert(statement)) = parse_sql(
+            "INSERT INTO archive (id, kind) SELECT id, kind FROM events WHERE id < 10 \
+             RETURNING *, id + 1 AS next",
+        ) else {
+            panic!("expected an INSERT");
+        };
+        assert_eq!(statement.columns, ["id", "kind"]);
+        assert!(matches!(statement.source, InsertSource::Query(_)));
+        assert_eq!(
+            statement.returning,
+            vec![
+                SelectItem::Wildcard,
+                SelectItem::Expression {
+                    expression: Expression::binary(
+                        column("id"),
+                        BinaryOperator::Plus,
+                        Expression::Literal(Value::Int64(1)),
+                    ),
+                    alias: Some("next".to_string()),
+                },
+            ]
+        );
+        for sql in [
+            "INSERT INTO archive (SELECT * FROM events)",
+            "INSERT INTO archive ((SELECT * FROM events) UNION (SELECT * FROM events))",
+            "INSERT INTO archive WITH e AS (SELECT 1) SELECT * FROM e",
+        ] {
+            let Ok(SQLStatement::Insert(statement)) = parse_sql(sql) else {
+                panic!("{}", sql);
+            };
+            assert!(statement.columns.is_empty(), "{}", sql);
+            assert!(
+                matches!(statement.source, InsertSource::Query(_)),
+                "{}",
+                sql
+            );
+        }
+        let Ok(SQLStatement::Update(statement)) =
+            parse_sql("UPDATE t SET a = 1 WHERE b RETURNING a, t.*")
+        else {
+            panic!("expected an UPDATE");
+        };
+        assert_eq!(
+            statement.returning,
+            vec![
+                SelectItem::Expression {
+                    expression: column("a"),
+                    alias: None,
+                },
+                SelectItem::QualifiedWildcard("t".to_string()),
+            ]
+        );
+        let Ok(SQLStatement::Delete(statement)) = parse_sql("DELETE FROM t RETURNING a b") else {
+            panic!("expected a DELETE");
+        };
+        assert_eq!(
+            statement.returning,
+            vec![SelectItem::Expression {
+                expression: column("a"),
+                alias: Some("b".to_string()),
+            }]
+        );
+        for sql in [
+            "INSERT INTO t DEFAULT",
+            "INSERT INTO t (a) (SELECT 1",
+            "INSERT INTO t VALUES (1) RETURNING",
+            "INSERT INTO t SELECT COUNT(*) FROM u WHERE COUNT(*) > 1",
+            "UPDATE t SET a = 1 RETURNING COUNT(*)",
+            "DELETE FROM t RETURNING a,",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
 }
@@ -1,7 +1,164 @@
+mod alter;
 mod create;
 mod delete;
 mod drop;
+mod expression;
 mod insert;
+#[allow(clippy::module_inception)]
 mod parser;
+mod script;
+mod select;
+mod statement;
 mod update;
-mod select;
\ No newline at end of file
+
+use crate::{
+    ast::{Expression, SQLStatement},
+    lexer::{SimpleLexer, Tokenize},
+};
+
+use expression::ExpressionParser;
+use parser::{Parser, SQLParser, SimpleParser};
+pub use parser::{ParserError, Placeholders};
+pub use script::parse_script;
+use statement::StatementParser;
+
+/// Tokenizes `sql` and parses it as a single statement.
+pub fn parse_sql(sql: &str) -> Result<SQLStatement, ParserError> {
+    let tokens = SimpleLexer::<ParserError>::tokenize_spanned(sql.to_string())?;
+    let mut parser = SimpleParser::with_spans(tokens);
+    StatementParser::parse(&mut parser)
+}
+
+/// Tokenizes `sql` and parses it as a single statement, together with the
+/// parameter placeholders it contains.
+pub fn parse_prepared(sql: &str) -> Result<(SQLStatement, Placeholders), ParserError> {
+    let tokens = SimpleLexer::<ParserError>::tokenize_spanned(sql.to_string())?;
+    let mut parser = SimpleParser::with_spans(tokens);
+    let statement = StatementParser::parse(&mut parser)?;
+    Ok((statement, parser.into_placeholders()))
+}
+
+/// Tokenizes `sql` and parses it as a single expression.
+pub fn parse_expression(sql: &str) -> Result<Expression, ParserError> {
+    let tokens = SimpleLexer::<ParserError>::tokenize_spanned(sql.to_string())?;
+    let mut parser = SimpleParser::with_spans(tokens);
+    let expression = ExpressionParser::parse(&mut parser)?;
+    if parser.peek_token().is_some() {
+        return Err(parser.expected("end of expression"));
+    }
+    Ok(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        BinaryOperator, ColumnDefinition, CreateStatement, DeleteStatement, Expression,
+        InsertSource, InsertStatement, InsertValue, Query, SQLStatement, WhereClause,
+    };
+    use crate::value::Value;
+
+    use super::{parse_prepared, parse_sql};
+
+    #[test]
+    fn test_parse_sql() {
+        let test_cases = vec![
+            (
+                "CREATE TABLE users (id, name);",
+                SQLStatement::CreateTable(CreateStatement::new(
+                    "users".to_string(),
+                    vec![
+                        ColumnDefinition::new("id".to_string()),
+                        ColumnDefinition::new("name".to_string()),
+                    ],
+                )),
+            ),
+            (
+                "INSERT INTO users (id, name) VALUES ('1', 'kim');",
+                SQLStatement::Insert(InsertStatement::new(
+                    "users".to_string(),
+                    vec!["id".to_string(), "name".to_string()],
+                    InsertSource::Values(vec![vec![
+                        InsertValue::Expression(Expression::Literal(Value::Text("1".to_string()))),
+                        InsertValue::Expression(Expression::Literal(Value::Text(
+                            "kim".to_string(),
+                        ))),
+                    ]]),
+                )),
+            ),
+            (
+                "delete from users where name = 'kim'",
+                SQLStatement::Delete(DeleteStatement::new(
+                    "users".to_string(),
+                    Some(WhereClause::new(Expression::binary(
+                        Expression::Column("name".to_string()),
+                        BinaryOperator::Equal,
+                        Expression::Literal(Value::Text("kim".to_string())),
+                    ))),
+                )),
+            ),
+        ];
+        for (sql, expected) in test_cases {
+            assert_eq!(parse_sql(sql), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_parse_prepared() {
+        let test_cases = vec![
+            ("SELECT ? + ? FROM t WHERE a = ?", vec![0, 1, 2], 3, vec![]),
+            (
+                "SELECT $2 FROM t WHERE a = $2 OR b = $1",
+                vec![1, 1, 0],
+                2,
+                vec![],
+            ),
+            ("SELECT $3", vec![2], 3, vec![]),
+            (
+                "DELETE FROM t WHERE a = :id OR b = :Name OR c = :id",
+                vec![0, 1, 0],
+                2,
+                vec!["id", "Name"],
+            ),
+            ("SELECT a FROM t", vec![], 0, vec![]),
+        ];
+        for (sql, indexes, count, names) in test_cases {
+            let (statement, placeholders) = parse_prepared(sql).unwrap();
+            fn collect(expression: &Expression, found: &mut Vec<usize>) {
+                if let Expression::Parameter(index) = expression {
+                    found.push(*index);
+                }
+                for child in expression.children() {
+                    collect(child, found);
+                }
+            }
+            let expressions = match &statement {
+                SQLStatement::Select(Query::Select(select)) => select.expressions(),
+                SQLStatement::Delete(delete) => {
+                    delete.where_clause.iter().map(|w| &w.condition).collect()
+                }
+                _ => unreachable!(),
+            };
+            let mut found = Vec::new();
+            for expression in expressions {
+                collect(expression, &mut found);
+            }
+            assert_eq!(found, indexes, "{}", sql);
+            assert_eq!(placeholders.count(), count, "{}", sql);
+            assert_eq!(placeholders.names(), names, "{}", sql);
+        }
+
+        let err = parse_prepared("SELECT a FROM t WHERE a = ? AND b = $1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot mix ?, $n and :name parameters in a statement at line 1, column 37"
+        );
+        assert!(parse_prepared("SELECT $0").is_err());
+    }
+
+    #[test]
+    fn test_parse_sql_rejects_trailing_garbage() {
+        assert!(parse_sql("DROP TABLE users; DROP TABLE users;").is_err());
+        assert!(parse_sql("SELECT id FROM users u name").is_err());
+        assert!(parse_sql("").is_err());
+    }
+}
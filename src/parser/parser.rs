@@ -1,4 +1,13 @@
-use crate::{ast::SQLStatement, token::Token, types::DBError};
+use std::{
+    fmt,
+    mem::{self, Discriminant},
+};
+
+use crate::{
+    ast::SQLStatement,
+    token::{Span, Token},
+    types::DBError,
+};
 
 pub trait SQLParser<P, ERR> {
     fn parse(parser: &mut P) -> Result<SQLStatement, ERR>;
@@ -6,12 +15,114 @@ pub trait SQLParser<P, ERR> {
 pub trait Parser {
     fn next_token(&mut self) -> Option<Token>;
     fn peek_token(&self) -> Option<&Token>;
+    /// The token `n` positions after the one returned by `peek_token`.
+    fn peek_nth_token(&self, n: usize) -> Option<&Token>;
     fn consume_token(&mut self) -> bool;
+    /// Span of the token returned by `peek_token`, or of the end of input.
+    fn peek_span(&self) -> Span;
+    /// The parameter placeholders seen so far.
+    fn placeholders(&mut self) -> &mut Placeholders;
+
+    /// Error pointing at the next token, saying what was expected instead.
+    fn expected<ERR: DBError>(&self, expected: &str) -> ERR {
+        let found = match self.peek_token() {
+            Some(token) => format!("`{}`", token),
+            None => "end of input".to_string(),
+        };
+        ERR::cause(&format!("expected {}, found {}", expected, found)).at(self.peek_span())
+    }
+    fn expect_token<ERR: DBError>(&mut self, token: &Token) -> Result<(), ERR> {
+        if self.peek_token() == Some(token) {
+            self.consume_token();
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", token)))
+        }
+    }
+    /// Whether the token `n` positions after the next one is the non-reserved
+    /// keyword `keyword`.
+    fn peek_nth_keyword(&self, n: usize, keyword: &str) -> bool {
+        matches!(
+            self.peek_nth_token(n),
+            Some(Token::Identifier(name)) if name.eq_ignore_ascii_case(keyword)
+        )
+    }
+    /// Consumes the next token if it is the non-reserved keyword `keyword`.
+    /// Non-reserved keywords are lexed as identifiers so they stay usable as names.
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        self.peek_nth_keyword(0, keyword) && self.consume_token()
+    }
+    fn expect_keyword<ERR: DBError>(&mut self, keyword: &str) -> Result<(), ERR> {
+        if self.consume_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", keyword)))
+        }
+    }
+    fn expect_identifier<ERR: DBError>(&mut self, expected: &str) -> Result<String, ERR> {
+        if let Some(Token::Identifier(_) | Token::QuotedIdentifier(_)) = self.peek_token() {
+            if let Some(Token::Identifier(name) | Token::QuotedIdentifier(name)) = self.next_token()
+            {
+                return Ok(name);
+            }
+        }
+        Err(self.expected(expected))
+    }
+}
+
+/// Numbers the parameter placeholders of a statement. `?` are numbered in
+/// order of appearance, `$n` is parameter n and every `:name` of the same
+/// name is the same parameter, numbered by its first appearance. A statement
+/// uses only one of these styles.
+#[derive(Debug, Default)]
+pub struct Placeholders {
+    style: Option<Discriminant<Token>>,
+    count: usize,
+    names: Vec<String>,
+}
+
+impl Placeholders {
+    /// 0-based index of the parameter of `placeholder`.
+    pub fn bind<ERR: DBError>(&mut self, placeholder: &Token) -> Result<usize, ERR> {
+        let style = mem::discriminant(placeholder);
+        if *self.style.get_or_insert(style) != style {
+            return Err(ERR::cause(
+                "cannot mix ?, $n and :name parameters in a statement",
+            ));
+        }
+        let index = match placeholder {
+            Token::PositionalParameter(position) => position - 1,
+            Token::NamedParameter(name) => {
+                match self.names.iter().position(|other| other == name) {
+                    Some(index) => index,
+                    None => {
+                        self.names.push(name.clone());
+                        self.names.len() - 1
+                    }
+                }
+            }
+            _ => self.count,
+        };
+        self.count = self.count.max(index + 1);
+        Ok(index)
+    }
+
+    /// Number of parameters, the highest n of `$n` for positional ones.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Names of `:name` parameters by index, empty for the other styles.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
 }
 
 pub struct SimpleParser {
     tokens: Vec<Token>,
+    spans: Vec<Span>,
     position: usize,
+    placeholders: Placeholders,
 }
 impl Parser for SimpleParser {
     fn next_token(&mut self) -> Option<Token> {
@@ -32,6 +143,10 @@ impl Parser for SimpleParser {
         }
     }
 
+    fn peek_nth_token(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.position + n)
+    }
+
     fn consume_token(&mut self) -> bool {
         if self.position < self.tokens.len() {
             self.position += 1;
@@ -40,26 +155,176 @@ impl Parser for SimpleParser {
             false
         }
     }
+
+    fn peek_span(&self) -> Span {
+        match self.spans.get(self.position) {
+            Some(span) => *span,
+            None => self.spans.last().map_or(Span::default(), Span::after),
+        }
+    }
+
+    fn placeholders(&mut self) -> &mut Placeholders {
+        &mut self.placeholders
+    }
 }
 
 impl SimpleParser {
+    /// Parser over tokens without their positions, as tests build them.
+    #[cfg(test)]
     pub fn new(tokens: Vec<Token>) -> Self {
         SimpleParser {
             tokens,
+            spans: Vec::new(),
             position: 0,
+            placeholders: Placeholders::default(),
         }
     }
+    pub fn with_spans(tokens: Vec<(Token, Span)>) -> Self {
+        let (tokens, spans) = tokens.into_iter().unzip();
+        SimpleParser {
+            tokens,
+            spans,
+            position: 0,
+            placeholders: Placeholders::default(),
+        }
+    }
+
+    pub fn into_placeholders(self) -> Placeholders {
+        self.placeholders
+    }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct ParserError(String);
+pub struct ParserError {
+    message: String,
+    span: Option<Span>,
+}
 impl DBError for ParserError {
     fn cause(msg: &str) -> Self {
-        ParserError(msg.to_string())
+        ParserError {
+            message: msg.to_string(),
+            span: None,
+        }
     }
 
     fn and_cause(mut self, msg: &str) -> Self {
-        self.0.push_str(msg);
+        self.message.push_str(msg);
+        self
+    }
+
+    fn at(mut self, span: Span) -> Self {
+        self.span = Some(span);
         self
     }
 }
+
+impl ParserError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Formats the error with the offending line of `source` and a caret
+    /// under the token the error points at.
+    pub fn render(&self, source: &str) -> String {
+        let Some(span) = self.span.filter(|span| span.start <= source.len()) else {
+            return format!("error: {}\n", self.message);
+        };
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |i| span.start + i);
+        let indent = source[line_start..span.start].chars().count();
+        let width = source[span.start..span.end.clamp(span.start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let line_number = span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}\n",
+            self.message,
+            gutter,
+            span.line,
+            span.column,
+            gutter,
+            line_number,
+            &source[line_start..line_end],
+            gutter,
+            " ".repeat(indent),
+            "^".repeat(width),
+        )
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(
+                f,
+                "{} at line {}, column {}",
+                self.message, span.line, span.column
+            ),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::SQLStatement,
+        lexer::{SimpleLexer, Tokenize},
+        parser::statement::StatementParser,
+    };
+
+    use super::{ParserError, SQLParser, SimpleParser};
+
+    fn parse(sql: &str) -> Result<SQLStatement, ParserError> {
+        let tokens = SimpleLexer::<ParserError>::tokenize_spanned(sql.to_string())?;
+        StatementParser::parse(&mut SimpleParser::with_spans(tokens))
+    }
+
+    #[test]
+    fn test_render() {
+        let sql = "SELECT id,\n       name\n  FROM ;";
+        let err = parse(sql).unwrap_err();
+        assert_eq!(err.message(), "expected table name, found `;`");
+        assert_eq!(
+            err.render(sql),
+            "error: expected table name, found `;`\n --> 3:8\n  |\n3 |   FROM ;\n  |        ^\n"
+        );
+
+        let sql = "INSERT INTO users (id, name VALUES ('1', 'kim')";
+        let err = parse(sql).unwrap_err();
+        assert_eq!(err.message(), "expected `)`, found `VALUES`");
+        assert_eq!(
+            err.render(sql).lines().last(),
+            Some("  |                             ^^^^^^")
+        );
+    }
+
+    #[test]
+    fn test_end_of_input() {
+        let sql = "DELETE FROM";
+        let err = parse(sql).unwrap_err();
+        assert_eq!(err.message(), "expected table name, found end of input");
+        assert_eq!(
+            err.to_string(),
+            "expected table name, found end of input at line 1, column 12"
+        );
+        assert_eq!(err.render(sql).lines().last(), Some("  |            ^"));
+
+        // the position right behind the last token counts characters
+        for (sql, position) in [
+            ("INSERT INTO 사용자", "line 1, column 16"),
+            ("INSERT INTO t VALUES ('a\nbc'", "line 2, column 4"),
+        ] {
+            let err = parse(sql).unwrap_err();
+            assert!(err.to_string().ends_with(position), "{}", err);
+        }
+    }
+}
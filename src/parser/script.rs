@@ -0,0 +1,113 @@
+use std::ops::Range;
+
+use crate::{
+    ast::SQLStatement,
+    lexer::{SimpleLexer, Tokenize},
+};
+
+use super::{
+    parser::{ParserError, SQLParser, SimpleParser},
+    statement::StatementParser,
+};
+
+/// A statement of a script together with its byte range in the script text.
+#[derive(Debug, PartialEq)]
+pub struct ParsedStatement {
+    pub statement: SQLStatement,
+    pub range: Range<usize>,
+}
+
+/// A statement of a script that failed to tokenize or parse.
+#[derive(Debug, PartialEq)]
+pub struct ScriptError {
+    pub error: ParserError,
+    pub range: Range<usize>,
+}
+
+/// Parses every `;`-separated statement of `sql`. Empty statements are skipped
+/// and the final `;` may be omitted. A failing statement does not stop the
+/// remaining ones from being parsed, so all errors of the script are returned.
+pub fn parse_script(sql: &str) -> Result<Vec<ParsedStatement>, Vec<ScriptError>> {
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    for tokenized in SimpleLexer::<ParserError>::tokenize_statements(sql.to_string()) {
+        let range = tokenized.range;
+        let result = tokenized.tokens.and_then(|tokens| {
+            let mut parser = SimpleParser::with_spans(tokens);
+            StatementParser::parse(&mut parser)
+        });
+        match result {
+            Ok(statement) => statements.push(ParsedStatement { statement, range }),
+            Err(error) => errors.push(ScriptError { error, range }),
+        }
+    }
+    if errors.is_empty() {
+        Ok(statements)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{
+        DropStatement, Expression, SQLStatement, SelectItem, SelectStatement, TableReference,
+    };
+
+    use super::{parse_script, ParsedStatement};
+
+    #[test]
+    fn test_parse_script() {
+        let script = "DROP TABLE a;\n;\nSELECT id, name FROM users;\nDROP TABLE b";
+        let statements = parse_script(script).unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                ParsedStatement {
+                    statement: SQLStatement::DropTable(DropStatement::new(vec!["a".to_string()])),
+                    range: 0..13,
+                },
+                ParsedStatement {
+                    statement: SQLStatement::Select(
+                        SelectStatement::new(
+                            vec![TableReference::table("users")],
+                            vec![
+                                SelectItem::expression(Expression::Column("id".to_string())),
+                                SelectItem::expression(Expression::Column("name".to_string())),
+                            ],
+                            None,
+                        )
+                        .into()
+                    ),
+                    range: 16..43,
+                },
+                ParsedStatement {
+                    statement: SQLStatement::DropTable(DropStatement::new(vec!["b".to_string()])),
+                    range: 44..56,
+                },
+            ]
+        );
+        assert_eq!(&script[16..43], "SELECT id, name FROM users;");
+    }
+
+    #[test]
+    fn test_parse_script_reports_every_error() {
+        let script = "DROP TABLE;\nSELECT 1.2.3 FROM t;\nDROP TABLE ok;\nDELETE users;";
+        let errors = parse_script(script).unwrap_err();
+        let ranges: Vec<&str> = errors.iter().map(|e| &script[e.range.clone()]).collect();
+        assert_eq!(
+            ranges,
+            vec!["DROP TABLE;", "SELECT 1.2.3 FROM t;", "DELETE users;"]
+        );
+    }
+
+    #[test]
+    fn test_error_spans_are_relative_to_script() {
+        let script = "DROP TABLE a;\nDELETE users;";
+        let errors = parse_script(script).unwrap_err();
+        assert_eq!(
+            errors[0].error.render(script),
+            "error: expected `FROM`, found `users`\n --> 2:8\n  |\n2 | DELETE users;\n  |        ^^^^^\n"
+        );
+    }
+}
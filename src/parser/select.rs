@@ -1,12 +1,19 @@
 use crate::{
-    ast::{SelectStatement, SQLStatement, Expression},
+    ast::{
+        CommonTableExpression, Distinct, Expression, JoinConstraint, JoinOperator,
+        OrderByExpression, Query, SQLStatement, SelectItem, SelectStatement, SetOperation,
+        SetOperator, TableReference, With,
+    },
     token::Token,
     types::DBError,
 };
 
-use super::parser::{Parser, SQLParser};
+use super::{
+    expression::ExpressionParser,
+    parser::{Parser, SQLParser},
+};
 
-struct SelectStatementParser;
+pub struct SelectStatementParser;
 
 impl<P, ERR> SQLParser<P, ERR> for SelectStatementParser
 where
@@ -14,45 +21,486 @@ where
     ERR: DBError,
 {
     fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
-        if parser.peek_token() != Some(&Token::Select) {
-            return Err(ERR::cause("Expected 'Select' token but not found"));
+        Ok(SQLStatement::Select(Self::parse_query(parser)?))
+    }
+}
+
+impl SelectStatementParser {
+    /// Parses a query, also used for subqueries: SELECTs combined by set
+    /// operations, where INTERSECT binds tighter than UNION and EXCEPT,
+    /// followed by ORDER BY and limits applying to the whole query.
+    pub fn parse_query<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Query, ERR> {
+        let with = match parser.peek_token() {
+            Some(Token::With) => Some(Self::parse_with(parser)?),
+            _ => None,
+        };
+        Self::parse_query_with(parser, with)
+    }
+
+    /// Parses the rest of a query after its WITH clause.
+    pub fn parse_query_with<P: Parser, ERR: DBError>(
+        parser: &mut P,
+        with: Option<With>,
+    ) -> Result<Query, ERR> {
+        let mut query = Self::parse_query_body(parser, 0)?;
+        let order_by = Self::parse_order_by(parser)?;
+        let (limit, offset) = Self::parse_limit(parser)?;
+        let (query_with, query_order_by, query_limit, query_offset) = match &mut query {
+            Query::Select(statement) => (
+                &mut statement.with,
+                &mut statement.order_by,
+                &mut statement.limit,
+                &mut statement.offset,
+            ),
+            Query::SetOperation(operation) => (
+                &mut operation.with,
+                &mut operation.order_by,
+                &mut operation.limit,
+                &mut operation.offset,
+            ),
+        };
+        // a parenthesized query may already have its own clauses
+        if with.is_some() {
+            if query_with.is_some() {
+                return Err(ERR::cause("multiple WITH clauses not allowed"));
+            }
+            *query_with = with;
         }
-        parser.consume_token();
+        if !order_by.is_empty() {
+            if !query_order_by.is_empty() {
+                return Err(ERR::cause("multiple ORDER BY clauses not allowed"));
+            }
+            *query_order_by = order_by;
+        }
+        if limit.is_some() || offset.is_some() {
+            if query_limit.is_some() || query_offset.is_some() {
+                return Err(ERR::cause("multiple LIMIT or OFFSET clauses not allowed"));
+            }
+            (*query_limit, *query_offset) = (limit, offset);
+        }
+        Ok(query)
+    }
+
+    /// Parses set operations of operators binding at least as tight as
+    /// `precedence`, which associate to the left.
+    fn parse_query_body<P: Parser, ERR: DBError>(
+        parser: &mut P,
+        precedence: u8,
+    ) -> Result<Query, ERR> {
+        let mut left = Self::parse_query_term(parser)?;
+        loop {
+            let (operator, operator_precedence) = match parser.peek_token() {
+                Some(Token::Union) => (SetOperator::Union, 1),
+                Some(Token::Except) => (SetOperator::Except, 1),
+                Some(Token::Intersect) => (SetOperator::Intersect, 2),
+                _ => break,
+            };
+            if operator_precedence < precedence {
+                break;
+            }
+            parser.consume_token();
+            let all = match parser.peek_token() {
+                Some(Token::All) => {
+                    parser.consume_token();
+                    true
+                }
+                Some(Token::Distinct) => {
+                    parser.consume_token();
+                    false
+                }
+                _ => false,
+            };
+            let right = Self::parse_query_body(parser, operator_precedence + 1)?;
+            left = Query::SetOperation(Box::new(SetOperation::new(left, operator, all, right)));
+        }
+        Ok(left)
+    }
 
-        let mut columns = Vec::new();
-        while let Some(token) = parser.next_token() {
-            match token {
-                Token::Identifier(column_name) => {
-                    columns.push(column_name);
+    /// Parses a SELECT without ORDER BY and limits, or a parenthesized query.
+    fn parse_query_term<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Query, ERR> {
+        if parser.peek_token() == Some(&Token::LeftParen) {
+            parser.consume_token();
+            let query = Self::parse_query(parser)?;
+            parser.expect_token(&Token::RightParen)?;
+            return Ok(query);
+        }
+        parser.expect_token(&Token::Select)?;
+        let distinct = Self::parse_distinct(parser)?;
+
+        let columns = Self::parse_select_items(parser)?;
+
+        let from = match parser.peek_token() {
+            Some(Token::From) => {
+                parser.consume_token();
+                Self::parse_from(parser)?
+            }
+            _ => Vec::new(),
+        };
+        let where_clause = ExpressionParser::parse_where(parser)?;
+        let mut statement = SelectStatement::new(from, columns, where_clause);
+        statement.distinct = distinct;
+        statement.group_by = Self::parse_group_by(parser)?;
+        if parser.peek_token() == Some(&Token::Having) {
+            parser.consume_token();
+            statement.having = Some(ExpressionParser::parse(parser)?);
+        }
+        Ok(statement.into())
+    }
+
+    /// Parses `WITH [RECURSIVE] name [(column, ...)] AS (query), ...`.
+    pub fn parse_with<P: Parser, ERR: DBError>(parser: &mut P) -> Result<With, ERR> {
+        parser.expect_token(&Token::With)?;
+        let recursive = parser.consume_keyword("RECURSIVE");
+        let mut queries = Vec::new();
+        loop {
+            let name = parser.expect_identifier("query name")?;
+            let mut columns = Vec::new();
+            if parser.peek_token() == Some(&Token::LeftParen) {
+                parser.consume_token();
+                loop {
+                    columns.push(parser.expect_identifier("column name")?);
                     if parser.peek_token() != Some(&Token::Comma) {
                         break;
                     }
-                    parser.consume_token(); // Consume the comma
-                },
-                Token::From => break,
-                _ => return Err(ERR::cause("Unexpected token while expecting column name or 'From'")),
+                    parser.consume_token();
+                }
+                parser.expect_token(&Token::RightParen)?;
+            }
+            parser.expect_token(&Token::As)?;
+            parser.expect_token(&Token::LeftParen)?;
+            let query = Self::parse_query(parser)?;
+            parser.expect_token(&Token::RightParen)?;
+            queries.push(CommonTableExpression {
+                name,
+                columns,
+                query,
+            });
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
             }
+            parser.consume_token();
         }
+        Ok(With { recursive, queries })
+    }
 
-        if parser.next_token() != Some(Token::From) {
-            return Err(ERR::cause("Expected 'From' after columns but not found"));
+    /// Parses `[ALL | DISTINCT | DISTINCT ON (expression, ...)]` after SELECT.
+    fn parse_distinct<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Option<Distinct>, ERR> {
+        match parser.peek_token() {
+            Some(Token::All) => {
+                parser.consume_token();
+                return Ok(None);
+            }
+            Some(Token::Distinct) => {
+                parser.consume_token();
+            }
+            _ => return Ok(None),
         }
+        if parser.peek_token() != Some(&Token::On) {
+            return Ok(Some(Distinct::Distinct));
+        }
+        parser.consume_token();
+        parser.expect_token(&Token::LeftParen)?;
+        let mut expressions = Vec::new();
+        loop {
+            expressions.push(ExpressionParser::parse(parser)?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
+            parser.consume_token();
+        }
+        parser.expect_token(&Token::RightParen)?;
+        Ok(Some(Distinct::On(expressions)))
+    }
 
-        if let Some(Token::Identifier(table_name)) = parser.next_token() {
-            Ok(SQLStatement::Select(SelectStatement::new(table_name, columns)))
-        } else {
-            Err(ERR::cause("Expected table name after 'From' but not found"))
+    /// Parses the comma separated items of a select list.
+    fn parse_select_items<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Vec<SelectItem>, ERR> {
+        let mut items = Vec::new();
+        loop {
+            items.push(Self::parse_select_item(parser)?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
+            parser.consume_token(); // Consume the comma
+        }
+        Ok(items)
+    }
+
+    /// Parses the `RETURNING` list of INSERT, UPDATE and DELETE, empty when
+    /// there is none.
+    pub fn parse_returning<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<Vec<SelectItem>, ERR> {
+        if parser.peek_token() != Some(&Token::Returning) {
+            return Ok(Vec::new());
+        }
+        parser.consume_token();
+        Self::parse_select_items(parser)
+    }
+
+    /// Parses `*`, `table.*` and `expression [[AS] alias]`.
+    fn parse_select_item<P: Parser, ERR: DBError>(parser: &mut P) -> Result<SelectItem, ERR> {
+        if parser.peek_token() == Some(&Token::Asterisk) {
+            parser.consume_token();
+            return Ok(SelectItem::Wildcard);
+        }
+        if let (
+            Some(Token::Identifier(_) | Token::QuotedIdentifier(_)),
+            Some(Token::Dot),
+            Some(Token::Asterisk),
+        ) = (
+            parser.peek_token(),
+            parser.peek_nth_token(1),
+            parser.peek_nth_token(2),
+        ) {
+            let name = parser.expect_identifier("table name")?;
+            parser.consume_token();
+            parser.expect_token(&Token::Asterisk)?;
+            return Ok(SelectItem::QualifiedWildcard(name));
+        }
+        let expression = ExpressionParser::parse(parser)?;
+        let alias = match parser.peek_token() {
+            Some(Token::As) => {
+                parser.consume_token();
+                Some(parser.expect_identifier("alias")?)
+            }
+            Some(Token::Identifier(_) | Token::QuotedIdentifier(_)) => {
+                Some(parser.expect_identifier("alias")?)
+            }
+            _ => None,
+        };
+        Ok(SelectItem::Expression { expression, alias })
+    }
+
+    /// Parses the comma separated table references following FROM.
+    pub fn parse_from<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Vec<TableReference>, ERR> {
+        let mut from = Vec::new();
+        loop {
+            from.push(Self::parse_table_reference(parser)?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
+            parser.consume_token();
+        }
+        Ok(from)
+    }
+
+    /// Parses a table followed by any number of joins, which associate to the left.
+    fn parse_table_reference<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<TableReference, ERR> {
+        let mut reference = Self::parse_table_factor(parser)?;
+        while let Some(operator) = Self::parse_join_operator(parser)? {
+            let right = Self::parse_table_factor(parser)?;
+            let constraint = match (operator, parser.peek_token()) {
+                (JoinOperator::Cross, _) => JoinConstraint::None,
+                (_, Some(Token::On)) => {
+                    parser.consume_token();
+                    JoinConstraint::On(ExpressionParser::parse(parser)?)
+                }
+                (_, Some(Token::Using)) => {
+                    parser.consume_token();
+                    parser.expect_token(&Token::LeftParen)?;
+                    let mut columns = Vec::new();
+                    loop {
+                        columns.push(parser.expect_identifier("column name")?);
+                        if parser.peek_token() != Some(&Token::Comma) {
+                            break;
+                        }
+                        parser.consume_token();
+                    }
+                    parser.expect_token(&Token::RightParen)?;
+                    JoinConstraint::Using(columns)
+                }
+                _ => return Err(parser.expected("`ON` or `USING`")),
+            };
+            reference = TableReference::Join {
+                left: Box::new(reference),
+                operator,
+                right: Box::new(right),
+                constraint,
+            };
+        }
+        Ok(reference)
+    }
+
+    /// Parses `name [[AS] alias]` and `(SELECT ...) [AS] alias`.
+    fn parse_table_factor<P: Parser, ERR: DBError>(parser: &mut P) -> Result<TableReference, ERR> {
+        if parser.peek_token() == Some(&Token::LeftParen) {
+            let subquery = ExpressionParser::parse_subquery(parser)?;
+            if parser.peek_token() == Some(&Token::As) {
+                parser.consume_token();
+            }
+            let alias = parser.expect_identifier("alias for the subquery")?;
+            return Ok(TableReference::Derived {
+                subquery: Box::new(subquery),
+                alias,
+            });
+        }
+        let name = parser.expect_identifier("table name")?;
+        let alias = Self::parse_alias(parser)?;
+        Ok(TableReference::Table { name, alias })
+    }
+
+    /// Parses the `[AS] alias` that may follow a table name.
+    pub fn parse_alias<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Option<String>, ERR> {
+        match parser.peek_token() {
+            Some(Token::As) => {
+                parser.consume_token();
+                Ok(Some(parser.expect_identifier("alias")?))
+            }
+            Some(Token::Identifier(_) | Token::QuotedIdentifier(_)) => {
+                Ok(Some(parser.expect_identifier("alias")?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Parses `[INNER] JOIN`, `{LEFT | RIGHT | FULL} [OUTER] JOIN` and `CROSS JOIN`.
+    fn parse_join_operator<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<Option<JoinOperator>, ERR> {
+        let operator = match parser.peek_token() {
+            Some(Token::Join) => {
+                parser.consume_token();
+                return Ok(Some(JoinOperator::Inner));
+            }
+            Some(Token::Inner) => JoinOperator::Inner,
+            Some(Token::Cross) => JoinOperator::Cross,
+            Some(Token::Left) => JoinOperator::Left,
+            Some(Token::Right) => JoinOperator::Right,
+            Some(Token::Full) => JoinOperator::Full,
+            _ => return Ok(None),
+        };
+        parser.consume_token();
+        if matches!(
+            operator,
+            JoinOperator::Left | JoinOperator::Right | JoinOperator::Full
+        ) && parser.peek_token() == Some(&Token::Outer)
+        {
+            parser.consume_token();
+        }
+        parser.expect_token(&Token::Join)?;
+        Ok(Some(operator))
+    }
+
+    /// Parses `GROUP BY expression, ...`.
+    fn parse_group_by<P: Parser, ERR: DBError>(parser: &mut P) -> Result<Vec<Expression>, ERR> {
+        let mut group_by = Vec::new();
+        if parser.peek_token() != Some(&Token::Group) {
+            return Ok(group_by);
+        }
+        parser.consume_token();
+        parser.expect_token(&Token::By)?;
+        loop {
+            group_by.push(ExpressionParser::parse(parser)?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
+            parser.consume_token();
+        }
+        Ok(group_by)
+    }
+
+    /// Parses `ORDER BY expression [ASC | DESC] [NULLS FIRST | NULLS LAST], ...`.
+    fn parse_order_by<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<Vec<OrderByExpression>, ERR> {
+        let mut order_by = Vec::new();
+        if parser.peek_token() != Some(&Token::Order) {
+            return Ok(order_by);
+        }
+        parser.consume_token();
+        parser.expect_token(&Token::By)?;
+        loop {
+            let expression = ExpressionParser::parse(parser)?;
+            let ascending = match parser.peek_token() {
+                Some(Token::Asc) => {
+                    parser.consume_token();
+                    true
+                }
+                Some(Token::Desc) => {
+                    parser.consume_token();
+                    false
+                }
+                _ => true,
+            };
+            order_by.push(Self::parse_nulls_order(parser, expression, ascending)?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
+            parser.consume_token();
+        }
+        Ok(order_by)
+    }
+
+    fn parse_nulls_order<P: Parser, ERR: DBError>(
+        parser: &mut P,
+        expression: Expression,
+        ascending: bool,
+    ) -> Result<OrderByExpression, ERR> {
+        let mut nulls_first = None;
+        if parser.consume_keyword("NULLS") {
+            if parser.consume_keyword("FIRST") {
+                nulls_first = Some(true);
+            } else if parser.consume_keyword("LAST") {
+                nulls_first = Some(false);
+            } else {
+                return Err(parser.expected("`FIRST` or `LAST`"));
+            }
+        }
+        Ok(OrderByExpression::new(expression, ascending, nulls_first))
+    }
+
+    /// Parses `LIMIT n`, `OFFSET n [ROW | ROWS]` and
+    /// `FETCH {FIRST | NEXT} n {ROW | ROWS} ONLY` in any order.
+    fn parse_limit<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<(Option<Expression>, Option<Expression>), ERR> {
+        let (mut limit, mut offset) = (None, None);
+        loop {
+            match parser.peek_token() {
+                Some(Token::Limit) if limit.is_none() => {
+                    parser.consume_token();
+                    limit = Some(ExpressionParser::parse(parser)?);
+                }
+                Some(Token::Offset) if offset.is_none() => {
+                    parser.consume_token();
+                    offset = Some(ExpressionParser::parse(parser)?);
+                    let _ = parser.consume_keyword("ROWS") || parser.consume_keyword("ROW");
+                }
+                Some(Token::Fetch) if limit.is_none() => {
+                    parser.consume_token();
+                    if !parser.consume_keyword("FIRST") {
+                        parser.expect_keyword("NEXT")?;
+                    }
+                    limit = Some(ExpressionParser::parse(parser)?);
+                    if !parser.consume_keyword("ROWS") {
+                        parser.expect_keyword("ROW")?;
+                    }
+                    parser.expect_keyword("ONLY")?;
+                }
+                _ => return Ok((limit, offset)),
+            }
         }
     }
 }
 
+#[cfg(test)]
 mod tests {
     use crate::{
-        ast::{SQLStatement, SelectStatement},
+        ast::{
+            BinaryOperator, CommonTableExpression, Distinct, Expression, JoinConstraint,
+            JoinOperator, OrderByExpression, Query, SQLStatement, SelectItem, SelectStatement,
+            SetOperation, SetOperator, TableReference, WhereClause, With,
+        },
+        lexer::{SimpleLexer, Tokenize},
         parser::{
-            parser::{ParserError, SQLParser, SimpleParser}, select::SelectStatementParser
+            parse_sql,
+            parser::{ParserError, SQLParser, SimpleParser},
+            select::SelectStatementParser,
         },
-        token::Token, types::DBError,
+        token::Token,
+        value::Value,
     };
 
     #[test]
@@ -68,7 +516,17 @@ mod tests {
                     Token::Identifier("table_name".to_string()),
                     Token::Semicolon,
                 ],
-                Ok(SQLStatement::Select(SelectStatement::new("table_name".to_string(), vec!["column1".to_string(), "column2".to_string()])))
+                Ok(SQLStatement::Select(
+                    SelectStatement::new(
+                        vec![TableReference::table("table_name")],
+                        vec![
+                            SelectItem::expression(Expression::Column("column1".to_string())),
+                            SelectItem::expression(Expression::Column("column2".to_string())),
+                        ],
+                        None,
+                    )
+                    .into(),
+                )),
             ),
             (
                 vec![
@@ -78,7 +536,16 @@ mod tests {
                     Token::Identifier("another_table".to_string()),
                     Token::Semicolon,
                 ],
-                Ok(SQLStatement::Select(SelectStatement::new("another_table".to_string(), vec!["column3".to_string()])))
+                Ok(SQLStatement::Select(
+                    SelectStatement::new(
+                        vec![TableReference::table("another_table")],
+                        vec![SelectItem::expression(Expression::Column(
+                            "column3".to_string(),
+                        ))],
+                        None,
+                    )
+                    .into(),
+                )),
             ),
             (
                 vec![
@@ -92,16 +559,455 @@ mod tests {
                     Token::Identifier("table_name".to_string()),
                     Token::Semicolon,
                 ],
-                Ok(SQLStatement::Select(SelectStatement::new("table_name".to_string(), vec!["column1".to_string(), "column4".to_string(), "column5".to_string()])))
+                Ok(SQLStatement::Select(
+                    SelectStatement::new(
+                        vec![TableReference::table("table_name")],
+                        vec![
+                            SelectItem::expression(Expression::Column("column1".to_string())),
+                            SelectItem::expression(Expression::Column("column4".to_string())),
+                            SelectItem::expression(Expression::Column("column5".to_string())),
+                        ],
+                        None,
+                    )
+                    .into(),
+                )),
+            ),
+            (
+                vec![
+                    Token::Select,
+                    Token::Asterisk,
+                    Token::Comma,
+                    Token::Identifier("t".to_string()),
+                    Token::Dot,
+                    Token::Asterisk,
+                    Token::From,
+                    Token::Identifier("table_name".to_string()),
+                    Token::Where,
+                    Token::Identifier("column_name".to_string()),
+                    Token::Equal,
+                    Token::StringLiteral("value".to_string()),
+                    Token::Semicolon,
+                ],
+                Ok(SQLStatement::Select(
+                    SelectStatement::new(
+                        vec![TableReference::table("table_name")],
+                        vec![
+                            SelectItem::Wildcard,
+                            SelectItem::QualifiedWildcard("t".to_string()),
+                        ],
+                        Some(WhereClause::new(Expression::binary(
+                            Expression::Column("column_name".to_string()),
+                            BinaryOperator::Equal,
+                            Expression::Literal(Value::Text("value".to_string())),
+                        ))),
+                    )
+                    .into(),
+                )),
             ),
         ];
 
         for (tokens, expected) in test_cases {
             let mut parser = SimpleParser::new(tokens);
-            let result: Result<SQLStatement, ParserError> = SelectStatementParser::parse(&mut parser);
+            let result: Result<SQLStatement, ParserError> =
+                SelectStatementParser::parse(&mut parser);
             assert_eq!(result, expected);
             println!("{:?}", result.unwrap());
         }
     }
-}
 
+    #[test]
+    fn test_order_by_and_limit() {
+        let tokens = SimpleLexer::<ParserError>::tokenize(
+            "SELECT a FROM t ORDER BY a DESC NULLS FIRST, b + 1 LIMIT 10 OFFSET 5".to_string(),
+        )
+        .unwrap();
+        let mut parser = SimpleParser::new(tokens);
+        let Ok(SQLStatement::Select(Query::Select(statement))) =
+            SelectStatementParser::parse(&mut parser) as Result<SQLStatement, ParserError>
+        else {
+            panic!("expected a select statement");
+        };
+        assert_eq!(
+            statement.order_by,
+            vec![
+                OrderByExpression::new(Expression::Column("a".to_string()), false, Some(true)),
+                OrderByExpression::new(
+                    Expression::binary(
+                        Expression::Column("b".to_string()),
+                        BinaryOperator::Plus,
+                        Expression::Literal(Value::Int64(1)),
+                    ),
+                    true,
+                    None,
+                ),
+            ]
+        );
+        assert_eq!(statement.limit, Some(Expression::Literal(Value::Int64(10))));
+        assert_eq!(statement.offset, Some(Expression::Literal(Value::Int64(5))));
+
+        let tokens = SimpleLexer::<ParserError>::tokenize(
+            "SELECT a FROM t OFFSET 1 ROW FETCH FIRST 3 ROWS ONLY".to_string(),
+        )
+        .unwrap();
+        let mut parser = SimpleParser::new(tokens);
+        let Ok(SQLStatement::Select(Query::Select(statement))) =
+            SelectStatementParser::parse(&mut parser) as Result<SQLStatement, ParserError>
+        else {
+            panic!("expected a select statement");
+        };
+        assert_eq!(statement.limit, Some(Expression::Literal(Value::Int64(3))));
+        assert_eq!(statement.offset, Some(Expression::Literal(Value::Int64(1))));
+
+        for sql in [
+            "SELECT a FROM t ORDER a",
+            "SELECT a FROM t ORDER BY a NULLS",
+            "SELECT a FROM t FETCH FIRST 3 ONLY",
+        ] {
+            let tokens = SimpleLexer::<ParserError>::tokenize(sql.to_string()).unwrap();
+            let mut parser = SimpleParser::new(tokens);
+            let result: Result<SQLStatement, ParserError> =
+                SelectStatementParser::parse(&mut parser);
+            assert!(result.is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_select_items() {
+        let Ok(SQLStatement::Select(Query::Select(statement))) =
+            parse_sql("SELECT DISTINCT price * qty AS total, name n, t.* FROM t ORDER BY total, 2")
+        else {
+            panic!("expected a select statement");
+        };
+        assert_eq!(statement.distinct, Some(Distinct::Distinct));
+        assert_eq!(
+            statement.columns,
+            vec![
+                SelectItem::Expression {
+                    expression: Expression::binary(
+                        Expression::Column("price".to_string()),
+                        BinaryOperator::Multiply,
+                        Expression::Column("qty".to_string()),
+                    ),
+                    alias: Some("total".to_string()),
+                },
+                SelectItem::Expression {
+                    expression: Expression::Column("name".to_string()),
+                    alias: Some("n".to_string()),
+                },
+                SelectItem::QualifiedWildcard("t".to_string()),
+            ]
+        );
+        assert_eq!(
+            statement.order_by,
+            vec![
+                OrderByExpression::new(Expression::Column("total".to_string()), true, None),
+                OrderByExpression::new(Expression::Literal(Value::Int64(2)), true, None),
+            ]
+        );
+
+        let Ok(SQLStatement::Select(Query::Select(statement))) =
+            parse_sql("SELECT DISTINCT ON (city, age + 1) city FROM t")
+        else {
+            panic!("expected a select statement");
+        };
+        assert_eq!(
+            statement.distinct,
+            Some(Distinct::On(vec![
+                Expression::Column("city".to_string()),
+                Expression::binary(
+                    Expression::Column("age".to_string()),
+                    BinaryOperator::Plus,
+                    Expression::Literal(Value::Int64(1)),
+                ),
+            ]))
+        );
+
+        let Ok(SQLStatement::Select(Query::Select(statement))) = parse_sql("SELECT ALL a FROM t")
+        else {
+            panic!("expected a select statement");
+        };
+        assert_eq!(statement.distinct, None);
+
+        for sql in [
+            "SELECT DISTINCT ON city FROM t",
+            "SELECT DISTINCT ON () city FROM t",
+            "SELECT a AS FROM t",
+            "SELECT a b c FROM t",
+            "SELECT * AS a FROM t",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_from_and_joins() {
+        let tokens = SimpleLexer::<ParserError>::tokenize(
+            "SELECT u.name, o.* FROM users AS u LEFT OUTER JOIN orders o ON u.id = o.user_id \
+             CROSS JOIN items, tags t JOIN labels USING (tag_id, name)"
+                .to_string(),
+        )
+        .unwrap();
+        let mut parser = SimpleParser::new(tokens);
+        let Ok(SQLStatement::Select(Query::Select(statement))) =
+            SelectStatementParser::parse(&mut parser) as Result<SQLStatement, ParserError>
+        else {
+            panic!("expected a select statement");
+        };
+        let qualified = |table: &str, column: &str| Expression::QualifiedColumn {
+            table: table.to_string(),
+            column: column.to_string(),
+        };
+        let aliased = |name: &str, alias: &str| TableReference::Table {
+            name: name.to_string(),
+            alias: Some(alias.to_string()),
+        };
+        assert_eq!(
+            statement.columns,
+            vec![
+                SelectItem::expression(qualified("u", "name")),
+                SelectItem::QualifiedWildcard("o".to_string()),
+            ]
+        );
+        assert_eq!(
+            statement.from,
+            vec![
+                TableReference::Join {
+                    left: Box::new(TableReference::Join {
+                        left: Box::new(aliased("users", "u")),
+                        operator: JoinOperator::Left,
+                        right: Box::new(aliased("orders", "o")),
+                        constraint: JoinConstraint::On(Expression::binary(
+                            qualified("u", "id"),
+                            BinaryOperator::Equal,
+                            qualified("o", "user_id"),
+                        )),
+                    }),
+                    operator: JoinOperator::Cross,
+                    right: Box::new(TableReference::table("items")),
+                    constraint: JoinConstraint::None,
+                },
+                TableReference::Join {
+                    left: Box::new(aliased("tags", "t")),
+                    operator: JoinOperator::Inner,
+                    right: Box::new(TableReference::table("labels")),
+                    constraint: JoinConstraint::Using(vec![
+                        "tag_id".to_string(),
+                        "name".to_string()
+                    ]),
+                },
+            ]
+        );
+
+        let tokens = SimpleLexer::<ParserError>::tokenize(
+            "SELECT v.a FROM (SELECT a FROM t) AS v, (SELECT * FROM u) w".to_string(),
+        )
+        .unwrap();
+        let mut parser = SimpleParser::new(tokens);
+        let Ok(SQLStatement::Select(Query::Select(statement))) =
+            SelectStatementParser::parse(&mut parser) as Result<SQLStatement, ParserError>
+        else {
+            panic!("expected a select statement");
+        };
+        assert_eq!(
+            statement.from,
+            vec![
+                TableReference::Derived {
+                    subquery: Box::new(
+                        SelectStatement::new(
+                            vec![TableReference::table("t")],
+                            vec![SelectItem::expression(Expression::Column("a".to_string()))],
+                            None,
+                        )
+                        .into()
+                    ),
+                    alias: "v".to_string(),
+                },
+                TableReference::Derived {
+                    subquery: Box::new(
+                        SelectStatement::new(
+                            vec![TableReference::table("u")],
+                            vec![SelectItem::Wildcard],
+                            None,
+                        )
+                        .into()
+                    ),
+                    alias: "w".to_string(),
+                },
+            ]
+        );
+
+        for sql in [
+            "SELECT a FROM (SELECT a FROM t)",
+            "SELECT a FROM (SELECT a FROM t AS v",
+            "SELECT a FROM t JOIN u",
+            "SELECT a FROM t LEFT u ON a = b",
+            "SELECT a FROM t JOIN u USING ()",
+            "SELECT a FROM t AS",
+            "SELECT t. FROM t",
+        ] {
+            let tokens = SimpleLexer::<ParserError>::tokenize(sql.to_string()).unwrap();
+            let mut parser = SimpleParser::new(tokens);
+            let result: Result<SQLStatement, ParserError> =
+                SelectStatementParser::parse(&mut parser);
+            assert!(result.is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_with() {
+        let Ok(SQLStatement::Select(Query::Select(statement))) = parse_sql(
+            "WITH RECURSIVE t (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM t WHERE n < 3), \
+             u AS (SELECT n FROM t) SELECT n FROM u",
+        ) else {
+            panic!("expected a select statement");
+        };
+        let n = || SelectItem::expression(Expression::Column("n".to_string()));
+        let recursive_term = SelectStatement::new(
+            vec![TableReference::table("t")],
+            vec![SelectItem::expression(Expression::binary(
+                Expression::Column("n".to_string()),
+                BinaryOperator::Plus,
+                Expression::Literal(Value::Int64(1)),
+            ))],
+            Some(WhereClause::new(Expression::binary(
+                Expression::Column("n".to_string()),
+                BinaryOperator::LessThan,
+                Expression::Literal(Value::Int64(3)),
+            ))),
+        );
+        assert_eq!(
+            statement.with,
+            Some(With {
+                recursive: true,
+                queries: vec![
+                    CommonTableExpression {
+                        name: "t".to_string(),
+                        columns: vec!["n".to_string()],
+                        query: Query::SetOperation(Box::new(SetOperation::new(
+                            SelectStatement::new(
+                                Vec::new(),
+                                vec![SelectItem::expression(Expression::Literal(Value::Int64(1)))],
+                                None,
+                            )
+                            .into(),
+                            SetOperator::Union,
+                            true,
+                            recursive_term.into(),
+                        ))),
+                    },
+                    CommonTableExpression {
+                        name: "u".to_string(),
+                        columns: Vec::new(),
+                        query: SelectStatement::new(
+                            vec![TableReference::table("t")],
+                            vec![n()],
+                            None
+                        )
+                        .into(),
+                    },
+                ],
+            })
+        );
+        assert_eq!(statement.from, vec![TableReference::table("u")]);
+
+        for sql in [
+            "WITH t AS (SELECT 1) DELETE FROM users WHERE id IN (SELECT * FROM t)",
+            "WITH t AS (SELECT 1) UPDATE users SET id = (SELECT * FROM t)",
+            "SELECT * FROM (WITH t AS (SELECT 1) SELECT * FROM t) AS v",
+            "SELECT (WITH t AS (SELECT 1) SELECT * FROM t)",
+        ] {
+            assert!(parse_sql(sql).is_ok(), "{}", sql);
+        }
+        for sql in [
+            "WITH t AS SELECT 1 SELECT * FROM t",
+            "WITH t (SELECT 1) SELECT * FROM t",
+            "WITH t AS (SELECT 1)",
+            "WITH t AS (SELECT 1) CREATE TABLE u (id)",
+            "WITH t () AS (SELECT 1) SELECT * FROM t",
+            "WITH t AS (SELECT 1 UNION) SELECT * FROM t",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let select = |column: &str, table: &str| -> Query {
+            SelectStatement::new(
+                vec![TableReference::table(table)],
+                vec![SelectItem::expression(Expression::Column(
+                    column.to_string(),
+                ))],
+                None,
+            )
+            .into()
+        };
+        let set_operation = |left, operator, all, right| {
+            Query::SetOperation(Box::new(SetOperation::new(left, operator, all, right)))
+        };
+
+        let Ok(SQLStatement::Select(Query::SetOperation(operation))) = parse_sql(
+            "SELECT a FROM t UNION SELECT b FROM u INTERSECT ALL SELECT c FROM v \
+             ORDER BY a LIMIT 1",
+        ) else {
+            panic!("expected a set operation");
+        };
+        let mut expected = SetOperation::new(
+            select("a", "t"),
+            SetOperator::Union,
+            false,
+            set_operation(
+                select("b", "u"),
+                SetOperator::Intersect,
+                true,
+                select("c", "v"),
+            ),
+        );
+        expected.order_by = vec![OrderByExpression::new(
+            Expression::Column("a".to_string()),
+            true,
+            None,
+        )];
+        expected.limit = Some(Expression::Literal(Value::Int64(1)));
+        assert_eq!(*operation, expected);
+
+        assert_eq!(
+            parse_sql("SELECT a FROM t EXCEPT ALL SELECT b FROM u UNION DISTINCT SELECT c FROM v"),
+            Ok(SQLStatement::Select(set_operation(
+                set_operation(
+                    select("a", "t"),
+                    SetOperator::Except,
+                    true,
+                    select("b", "u")
+                ),
+                SetOperator::Union,
+                false,
+                select("c", "v"),
+            )))
+        );
+
+        let Ok(SQLStatement::Select(Query::SetOperation(operation))) =
+            parse_sql("(SELECT a FROM t ORDER BY a LIMIT 2) UNION ALL (SELECT b FROM u)")
+        else {
+            panic!("expected a set operation");
+        };
+        let Query::Select(left) = &operation.left else {
+            panic!("expected a select");
+        };
+        assert_eq!(left.order_by.len(), 1);
+        assert_eq!(left.limit, Some(Expression::Literal(Value::Int64(2))));
+        assert_eq!(operation.right, select("b", "u"));
+        assert!(operation.order_by.is_empty() && operation.limit.is_none());
+
+        for sql in [
+            "SELECT a FROM t UNION",
+            "SELECT a FROM t UNION ALL ALL SELECT b FROM u",
+            "SELECT a FROM t ORDER BY a UNION SELECT b FROM u",
+            "(SELECT a FROM t LIMIT 1) LIMIT 2",
+            "(SELECT a FROM t ORDER BY a) ORDER BY a",
+            "(SELECT a FROM t UNION SELECT b FROM u",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{ast::SQLStatement, syntax_analysis::SyntaxAnalyzer, token::Token, types::DBError};
+
+use super::{
+    alter::AlterStatementParser,
+    create::CreateStatementParser,
+    delete::DeleteStatementParser,
+    drop::DropStatementParser,
+    insert::InsertStatementParser,
+    parser::{Parser, SQLParser},
+    select::SelectStatementParser,
+    update::UpdateStatementParser,
+};
+
+/// Routes a token stream to the statement parser matching its leading keyword
+/// and makes sure nothing but an optional `;` follows the statement, which is
+/// then checked by the syntax analyzer.
+pub struct StatementParser;
+impl<P, ERR> SQLParser<P, ERR> for StatementParser
+where
+    P: Parser,
+    ERR: DBError,
+{
+    fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
+        let start = parser.peek_span();
+        let statement = match parser.peek_token() {
+            Some(Token::Create) => CreateStatementParser::parse(parser)?,
+            Some(Token::Drop) => DropStatementParser::parse(parser)?,
+            Some(Token::Alter) => AlterStatementParser::parse(parser)?,
+            Some(Token::Select | Token::LeftParen) => SelectStatementParser::parse(parser)?,
+            Some(Token::Insert) => InsertStatementParser::parse(parser)?,
+            Some(Token::Update) => UpdateStatementParser::parse(parser)?,
+            Some(Token::Delete) => DeleteStatementParser::parse(parser)?,
+            Some(Token::With) => Self::parse_with(parser)?,
+            _ => return Err(parser.expected("statement")),
+        };
+        if parser.peek_token() == Some(&Token::Semicolon) {
+            parser.consume_token();
+        }
+        if parser.peek_token().is_some() {
+            return Err(parser.expected("end of statement"));
+        }
+        SyntaxAnalyzer::analyze(&statement).map_err(|error: ERR| error.at(start))?;
+        Ok(statement)
+    }
+}
+
+impl StatementParser {
+    /// Parses a statement preceded by a WITH clause.
+    fn parse_with<P: Parser, ERR: DBError>(parser: &mut P) -> Result<SQLStatement, ERR> {
+        let with = Some(SelectStatementParser::parse_with(parser)?);
+        let mut statement = match parser.peek_token() {
+            Some(Token::Select | Token::LeftParen) => {
+                return Ok(SQLStatement::Select(
+                    SelectStatementParser::parse_query_with(parser, with)?,
+                ))
+            }
+            Some(Token::Insert) => InsertStatementParser::parse(parser)?,
+            Some(Token::Update) => UpdateStatementParser::parse(parser)?,
+            Some(Token::Delete) => DeleteStatementParser::parse(parser)?,
+            _ => return Err(parser.expected("`SELECT`, `INSERT`, `UPDATE` or `DELETE`")),
+        };
+        match &mut statement {
+            SQLStatement::Insert(statement) => statement.with = with,
+            SQLStatement::Update(statement) => statement.with = with,
+            SQLStatement::Delete(statement) => statement.with = with,
+            _ => {}
+        }
+        Ok(statement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{
+            DropStatement, Expression, SQLStatement, SelectItem, SelectStatement, TableReference,
+        },
+        parser::{
+            parser::{ParserError, SQLParser, SimpleParser},
+            statement::StatementParser,
+        },
+        token::Token,
+    };
+
+    #[test]
+    fn test_dispatch() {
+        let tokens = vec![
+            Token::Drop,
+            Token::Table,
+            Token::Identifier("my_table".to_string()),
+            Token::Semicolon,
+        ];
+        let mut parser = SimpleParser::new(tokens);
+        let ast: Result<SQLStatement, ParserError> = StatementParser::parse(&mut parser);
+        assert_eq!(
+            ast,
+            Ok(SQLStatement::DropTable(DropStatement::new(vec![
+                "my_table".to_string()
+            ])))
+        );
+
+        let tokens = vec![
+            Token::Select,
+            Token::Identifier("id".to_string()),
+            Token::From,
+            Token::Identifier("users".to_string()),
+        ];
+        let mut parser = SimpleParser::new(tokens);
+        let ast: Result<SQLStatement, ParserError> = StatementParser::parse(&mut parser);
+        assert_eq!(
+            ast,
+            Ok(SQLStatement::Select(
+                SelectStatement::new(
+                    vec![TableReference::table("users")],
+                    vec![SelectItem::expression(Expression::Column("id".to_string()))],
+                    None,
+                )
+                .into()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_trailing_tokens() {
+        let test_cases = vec![
+            vec![
+                Token::Drop,
+                Token::Table,
+                Token::Identifier("my_table".to_string()),
+                Token::Semicolon,
+                Token::Identifier("garbage".to_string()),
+            ],
+            vec![
+                Token::Drop,
+                Token::Table,
+                Token::Identifier("my_table".to_string()),
+                Token::Identifier("garbage".to_string()),
+            ],
+            vec![Token::Identifier("garbage".to_string())],
+            vec![],
+        ];
+        for tokens in test_cases {
+            let mut parser = SimpleParser::new(tokens);
+            let ast: Result<SQLStatement, ParserError> = StatementParser::parse(&mut parser);
+            assert!(ast.is_err());
+        }
+    }
+}
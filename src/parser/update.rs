@@ -1,114 +1,116 @@
-use core::num;
-use std::io::stdout;
-
 use crate::{
-    ast::{Expression, SQLStatement, SetClause, UpdateStatement, Value, WhereClause},
+    ast::{SQLStatement, SetClause, UpdateStatement},
     token::Token,
     types::DBError,
 };
 
-use super::parser::{Parser, SQLParser};
+use super::{
+    expression::ExpressionParser,
+    parser::{Parser, SQLParser},
+    select::SelectStatementParser,
+};
 
-struct UpdateStatementParser;
+pub struct UpdateStatementParser;
 impl<P, ERR> SQLParser<P, ERR> for UpdateStatementParser
 where
     P: Parser,
     ERR: DBError,
 {
-    fn parse(parser: &mut P) -> Result<crate::ast::SQLStatement, ERR> {
-        if parser.peek_token() == Some(&Token::Update) {
+    fn parse(parser: &mut P) -> Result<SQLStatement, ERR> {
+        parser.expect_token(&Token::Update)?;
+        let table_name = parser.expect_identifier("table name")?;
+        let alias = SelectStatementParser::parse_alias(parser)?;
+        parser.expect_token(&Token::Set)?;
+        let mut set_clauses: Vec<SetClause> = Vec::new();
+        loop {
+            if parser.peek_token() == Some(&Token::LeftParen) {
+                set_clauses.extend(Self::parse_tuple_assignment(parser)?);
+            } else {
+                let column_name = parser.expect_identifier("column name")?;
+                parser.expect_token(&Token::Equal)?;
+                set_clauses.push(SetClause::new(
+                    column_name,
+                    ExpressionParser::parse(parser)?,
+                ));
+            }
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
+            parser.consume_token();
+        }
+        let from = match parser.peek_token() {
+            Some(Token::From) => {
+                parser.consume_token();
+                SelectStatementParser::parse_from(parser)?
+            }
+            _ => Vec::new(),
+        };
+        let where_clause = ExpressionParser::parse_where(parser)?;
+        let mut statement = UpdateStatement::new(table_name, set_clauses, where_clause);
+        statement.alias = alias;
+        statement.from = from;
+        statement.returning = SelectStatementParser::parse_returning(parser)?;
+        Ok(SQLStatement::Update(statement))
+    }
+}
+
+impl UpdateStatementParser {
+    /// Parses `(column, ...) = (value, ...)` into one assignment per column.
+    fn parse_tuple_assignment<P: Parser, ERR: DBError>(
+        parser: &mut P,
+    ) -> Result<Vec<SetClause>, ERR> {
+        let span = parser.peek_span();
+        parser.expect_token(&Token::LeftParen)?;
+        let mut columns = Vec::new();
+        loop {
+            columns.push(parser.expect_identifier("column name")?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
+            }
             parser.consume_token();
-            if let Some(Token::Identifier(table_name)) = parser.next_token() {
-                if parser.peek_token() == Some(&Token::Set) {
-                    parser.consume_token();
-                    let mut set_clauses: Vec<SetClause> = Vec::new();
-                    loop {
-                        if parser.peek_token() == Some(&Token::Where) {
-                            break;
-                        }
-                        if let Some(token) = parser.next_token() {
-                            match token {
-                                Token::Identifier(column_name) => {
-                                    if parser.peek_token() == Some(&Token::Equal) {
-                                        parser.consume_token();
-                                        if let Some(token) = parser.next_token() {
-                                            match token {
-                                                Token::StringLiteral(str_value) => set_clauses
-                                                    .push(SetClause::new(
-                                                        column_name,
-                                                        Value::StrValue(str_value),
-                                                    )),
-                                                Token::NumericLiteral(num_value) => set_clauses
-                                                    .push(SetClause::new(
-                                                        column_name,
-                                                        Value::NumValue(num_value),
-                                                    )),
-                                                _ => break,
-                                            }
-                                        }
-                                    }
-                                }
-                                Token::Comma => {}
-                                _ => break,
-                            }
-                        } else {
-                            break;
-                        }
-                    }
-                    if parser.peek_token() == Some(&Token::Where) {
-                        parser.consume_token();
-                        if let Some(Token::Identifier(column)) = parser.next_token() {
-                            if parser.peek_token() == Some(&Token::Equal) {
-                                parser.consume_token();
-                                if let Some(value_token) = parser.next_token() {
-                                    match value_token {
-                                        Token::StringLiteral(str_value) => {
-                                            return Ok(SQLStatement::Update(UpdateStatement::new(
-                                                table_name,
-                                                set_clauses,
-                                                Some(WhereClause::new(Expression::new(
-                                                    column,
-                                                    Value::StrValue(str_value),
-                                                ))),
-                                            )))
-                                        }
-                                        Token::NumericLiteral(num_value) => {
-                                            return Ok(SQLStatement::Update(UpdateStatement::new(
-                                                table_name,
-                                                set_clauses,
-                                                Some(WhereClause::new(Expression::new(
-                                                    column,
-                                                    Value::NumValue(num_value),
-                                                ))),
-                                            )))
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        }
-                    } else {
-                        return Ok(SQLStatement::Update(UpdateStatement::new(
-                            table_name,
-                            set_clauses,
-                            None,
-                        )));
-                    }
-                }
+        }
+        parser.expect_token(&Token::RightParen)?;
+        parser.expect_token(&Token::Equal)?;
+        parser.expect_token(&Token::LeftParen)?;
+        let mut values = Vec::new();
+        loop {
+            values.push(ExpressionParser::parse(parser)?);
+            if parser.peek_token() != Some(&Token::Comma) {
+                break;
             }
+            parser.consume_token();
+        }
+        parser.expect_token(&Token::RightParen)?;
+        if columns.len() != values.len() {
+            return Err(ERR::cause(&format!(
+                "{} columns are assigned {} values",
+                columns.len(),
+                values.len()
+            ))
+            .at(span));
         }
-        Err(ERR::cause("msg"))
+        Ok(columns
+            .into_iter()
+            .zip(values)
+            .map(|(column, value)| SetClause::new(column, value))
+            .collect())
     }
 }
 
+#[cfg(test)]
 mod tests {
     use crate::{
-        ast::SQLStatement,
+        ast::{
+            BinaryOperator, Expression, SQLStatement, SetClause, TableReference, UpdateStatement,
+            WhereClause,
+        },
         parser::{
+            parse_sql,
             parser::{ParserError, SQLParser, SimpleParser},
             update::UpdateStatementParser,
         },
         token::Token,
+        value::Value,
     };
 
     #[test]
@@ -132,7 +134,7 @@ mod tests {
                     Token::Where,
                     Token::Identifier("id".to_string()),
                     Token::Equal,
-                    Token::NumericLiteral(1.0),
+                    Token::IntegerLiteral(1),
                     Token::Semicolon,
                 ],
             },
@@ -173,4 +175,86 @@ mod tests {
             println!("{:?}", ast.unwrap());
         }
     }
+
+    #[test]
+    fn test_assignments_and_from() {
+        let column = |name: &str| Expression::Column(name.to_string());
+        let qualified = |table: &str, column: &str| Expression::QualifiedColumn {
+            table: table.to_string(),
+            column: column.to_string(),
+        };
+        let mut expected = UpdateStatement::new(
+            "accounts".to_string(),
+            vec![
+                SetClause::new(
+                    "balance".to_string(),
+                    Expression::binary(
+                        column("balance"),
+                        BinaryOperator::Minus,
+                        qualified("t", "amount"),
+                    ),
+                ),
+                SetClause::new("status".to_string(), qualified("t", "status")),
+                SetClause::new(
+                    "checked".to_string(),
+                    Expression::Literal(Value::Boolean(true)),
+                ),
+            ],
+            Some(WhereClause::new(Expression::binary(
+                qualified("accounts", "id"),
+                BinaryOperator::Equal,
+                qualified("t", "account_id"),
+            ))),
+        );
+        expected.from = vec![TableReference::Table {
+            name: "transfers".to_string(),
+            alias: Some("t".to_string()),
+        }];
+        assert_eq!(
+            parse_sql(
+                "UPDATE accounts SET balance = balance - t.amount, (status, checked) = (t.status, TRUE) \
+                 FROM transfers t WHERE accounts.id = t.account_id"
+            ),
+            Ok(SQLStatement::Update(expected))
+        );
+        for sql in [
+            "UPDATE t SET (a, b) = (1)",
+            "UPDATE t SET (a) = (1, 2)",
+            "UPDATE t SET (a, b) = 1",
+            "UPDATE t SET () = ()",
+            "UPDATE t SET a = 1, (b, a) = (2, 3)",
+            "UPDATE t SET a = 1 FROM",
+            "UPDATE t SET a = 1 FROM u WHERE COUNT(*) > 1",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_alias() {
+        let expected = || {
+            let mut statement = UpdateStatement::new(
+                "events".to_string(),
+                vec![SetClause::new(
+                    "seen".to_string(),
+                    Expression::Literal(Value::Boolean(true)),
+                )],
+                None,
+            );
+            statement.alias = Some("e".to_string());
+            SQLStatement::Update(statement)
+        };
+        for sql in [
+            "UPDATE events e SET seen = TRUE",
+            "UPDATE events AS e SET seen = TRUE",
+        ] {
+            assert_eq!(parse_sql(sql), Ok(expected()), "{}", sql);
+        }
+        for sql in [
+            "UPDATE events e f SET seen = TRUE",
+            "UPDATE events AS SET seen = TRUE",
+        ] {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
 }
@@ -1 +1,3659 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    rc::Rc,
+};
 
+use crate::{
+    access_manager::{AccessManager, Table},
+    ast::{
+        AlterColumnAction, AlterOperation, AlterStatement, ColumnDefinition, ColumnOption,
+        CommonTableExpression, CreateStatement, DataType, DeleteStatement, Distinct, DropBehavior,
+        DropStatement, Expression, ForeignKeyReference, InsertSource, InsertStatement, InsertValue,
+        JoinConstraint, JoinOperator, OrderByExpression, Query, SQLStatement, SelectItem,
+        SelectStatement, SetOperation, SetOperator, TableConstraint, TableConstraintKind,
+        TableReference, UpdateStatement, WhereClause, With,
+    },
+    evaluator::{current_time, EmptyRow, ExpressionEvaluator, RowContext},
+    parser::parse_prepared,
+    query_planner::{projection, sort_key, QueryPlanner, SortKey, SubqueryPlan},
+    relation::{combine, distinct_rows, GroupKey, Relation, RelationColumn},
+    token::Span,
+    types::DBError,
+    value::Value,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum QueryResult {
+    Rows {
+        columns: Vec<String>,
+        rows: Vec<Vec<Value>>,
+    },
+    RowsAffected(usize),
+    Done,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExecutorError(String);
+impl DBError for ExecutorError {
+    fn cause(msg: &str) -> Self {
+        ExecutorError(msg.to_string())
+    }
+
+    fn and_cause(mut self, msg: &str) -> Self {
+        self.0.push_str(msg);
+        self
+    }
+
+    fn at(mut self, span: Span) -> Self {
+        self.0
+            .push_str(&format!(" at line {}, column {}", span.line, span.column));
+        self
+    }
+}
+
+impl fmt::Display for ExecutorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A statement parsed once, to be executed any number of times with values
+/// bound to its parameters.
+#[derive(Debug)]
+pub struct PreparedStatement {
+    statement: SQLStatement,
+    names: Vec<String>,
+    types: Vec<Option<DataType>>,
+}
+
+impl PreparedStatement {
+    pub fn parameter_count(&self) -> usize {
+        self.types.len()
+    }
+
+    /// Types inferred for the parameters when the statement was prepared,
+    /// `None` for parameters that take values of any type.
+    pub fn parameter_types(&self) -> &[Option<DataType>] {
+        &self.types
+    }
+
+    /// Index of the `:name` parameter `name`.
+    pub fn parameter_index(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|other| other == name)
+    }
+}
+
+/// Evaluation context of a row, resolving columns against the row first and
+/// then against the current rows of the enclosing queries, which is how
+/// correlated subqueries see outer columns.
+#[derive(Clone, Copy)]
+struct Scope<'a> {
+    executor: &'a QueryExecutor,
+    columns: &'a [RelationColumn],
+    values: &'a [Value],
+    outer: Option<&'a Scope<'a>>,
+}
+impl RowContext for Scope<'_> {
+    fn column<ERR: DBError>(&self, table: Option<&str>, name: &str) -> Result<Value, ERR> {
+        let mut scope = Some(self);
+        while let Some(current) = scope {
+            if let Some(index) = RelationColumn::find(current.columns, table, name)? {
+                return Ok(current.values[index].clone());
+            }
+            scope = current.outer;
+        }
+        Err(RelationColumn::not_found(self.columns, table, name))
+    }
+
+    fn parameter<ERR: DBError>(&self, index: usize) -> Result<Value, ERR> {
+        EmptyRow(&self.executor.parameters).parameter(index)
+    }
+
+    fn subquery<ERR: DBError>(&self, subquery: &Query) -> Result<Rc<Vec<Vec<Value>>>, ERR> {
+        self.executor
+            .subquery(subquery, self)
+            .map_err(|error| ERR::cause(&error.0))
+    }
+
+    fn statement_time(&self) -> i64 {
+        self.executor.statement_time
+    }
+}
+
+/// The rows of one group of a grouped query. Columns outside aggregates are
+/// read from the first row, as the syntax analyzer only lets through columns
+/// that are equal across the group.
+struct GroupRow<'a> {
+    executor: &'a QueryExecutor,
+    columns: &'a [RelationColumn],
+    rows: Vec<&'a [Value]>,
+    outer: Option<&'a Scope<'a>>,
+}
+impl<'a> GroupRow<'a> {
+    fn scope(&'a self, values: &'a [Value]) -> Scope<'a> {
+        Scope {
+            executor: self.executor,
+            columns: self.columns,
+            values,
+            outer: self.outer,
+        }
+    }
+}
+impl RowContext for GroupRow<'_> {
+    fn column<ERR: DBError>(&self, table: Option<&str>, name: &str) -> Result<Value, ERR> {
+        if let Some(values) = self.rows.first() {
+            return self.scope(values).column(table, name);
+        }
+        match self.outer {
+            Some(outer) if RelationColumn::find::<ERR>(self.columns, table, name)?.is_none() => {
+                outer.column(table, name)
+            }
+            _ => Err(ERR::cause(&format!(
+                "column {} must appear in the GROUP BY clause or be used in an aggregate function",
+                name
+            ))),
+        }
+    }
+
+    fn aggregate<ERR: DBError>(&self, function: &Expression) -> Result<Value, ERR> {
+        let Expression::Function {
+            name,
+            arguments,
+            distinct,
+            wildcard,
+        } = function
+        else {
+            return Err(ERR::cause("expected an aggregate function"));
+        };
+        if *wildcard {
+            return Ok(Value::Int64(self.rows.len() as i64));
+        }
+        let [argument] = arguments.as_slice() else {
+            return Err(ERR::cause(&format!("{} takes exactly one argument", name)));
+        };
+        let mut values = Vec::with_capacity(self.rows.len());
+        for row in &self.rows {
+            values.push(ExpressionEvaluator::evaluate(argument, &self.scope(row))?);
+        }
+        ExpressionEvaluator::aggregate(name, *distinct, values)
+    }
+
+    fn parameter<ERR: DBError>(&self, index: usize) -> Result<Value, ERR> {
+        EmptyRow(&self.executor.parameters).parameter(index)
+    }
+
+    fn subquery<ERR: DBError>(&self, subquery: &Query) -> Result<Rc<Vec<Vec<Value>>>, ERR> {
+        let nulls = vec![Value::Null; self.columns.len()];
+        let values = self.rows.first().copied().unwrap_or(&nulls);
+        self.scope(values).subquery(subquery)
+    }
+
+    fn statement_time(&self) -> i64 {
+        self.executor.statement_time
+    }
+}
+
+/// What is kept of a subquery between the outer rows of a statement.
+enum SubqueryResult {
+    Rows(Rc<Vec<Vec<Value>>>),
+    /// Result rows of a keyed subquery by the values of its `outer`
+    /// expressions, `empty` being the result for values without rows.
+    Keyed {
+        /// Kept alive, as the results of its own subqueries are cached by address.
+        _statement: Box<SelectStatement>,
+        outer: Vec<Expression>,
+        parts: HashMap<GroupKey, Rc<Vec<Vec<Value>>>>,
+        empty: Rc<Vec<Vec<Value>>>,
+    },
+    Correlated,
+}
+
+/// The rows of a WITH query, read like a table by the rest of its statement.
+struct CommonTable {
+    name: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// The rows a statement writes to a table: `removed` flags the stored rows
+/// it deletes or replaces by position, `added` holds the rows it inserts or
+/// their replacements.
+struct TableWrite<'a> {
+    table: &'a Table,
+    removed: &'a [bool],
+    added: &'a [Vec<Value>],
+}
+
+impl<'a> TableWrite<'a> {
+    /// The rows of the table once written.
+    fn rows(&self) -> impl Iterator<Item = Cow<'a, [Value]>> + '_ {
+        self.table
+            .scan()
+            .enumerate()
+            .filter(|(position, _)| !self.removed.get(*position).copied().unwrap_or(false))
+            .map(|(_, values)| values)
+            .chain(self.added.iter().map(|values| Cow::Borrowed(&values[..])))
+    }
+}
+
+/// How many times the recursive term of a WITH RECURSIVE query may run
+/// before the query is considered runaway.
+const MAX_RECURSION: usize = 1000;
+
+/// Executes statements against the tables kept by the access manager.
+#[derive(Default)]
+pub struct QueryExecutor {
+    access_manager: AccessManager,
+    /// Subqueries of the statement being executed, by address.
+    subqueries: RefCell<HashMap<usize, Rc<SubqueryResult>>>,
+    /// The WITH queries in scope, innermost last.
+    common_tables: RefCell<Vec<CommonTable>>,
+    /// Values bound to the parameters of the statement being executed.
+    parameters: Vec<Value>,
+    /// Time the statement being executed started at, so that every `now()`
+    /// of it is the same.
+    statement_time: i64,
+}
+
+impl QueryExecutor {
+    pub fn new() -> Self {
+        QueryExecutor::default()
+    }
+
+    /// Parses `sql` and infers the types of its parameters from the tables
+    /// as they are now.
+    pub fn prepare(&self, sql: &str) -> Result<PreparedStatement, ExecutorError> {
+        let (statement, placeholders) =
+            parse_prepared(sql).map_err(|error| ExecutorError::cause(&error.to_string()))?;
+        let types = QueryPlanner::new(&self.access_manager, Vec::new())
+            .parameter_types(&statement, placeholders.count())?;
+        Ok(PreparedStatement {
+            statement,
+            names: placeholders.names().to_vec(),
+            types,
+        })
+    }
+
+    /// Executes a prepared statement with `parameters` bound to its
+    /// parameters in order, each converted to the type inferred for it.
+    pub fn execute_prepared(
+        &mut self,
+        prepared: &PreparedStatement,
+        parameters: &[Value],
+    ) -> Result<QueryResult, ExecutorError> {
+        if parameters.len() != prepared.parameter_count() {
+            return Err(ExecutorError::cause(&format!(
+                "statement takes {} parameters but {} were given",
+                prepared.parameter_count(),
+                parameters.len()
+            )));
+        }
+        let mut values = Vec::with_capacity(parameters.len());
+        for (value, data_type) in parameters.iter().zip(&prepared.types) {
+            values.push(match data_type {
+                Some(data_type) => value.clone().cast(data_type)?,
+                None => value.clone(),
+            });
+        }
+        self.parameters = values;
+        let result = self.execute(&prepared.statement);
+        self.parameters.clear();
+        result
+    }
+
+    pub fn execute(&mut self, statement: &SQLStatement) -> Result<QueryResult, ExecutorError> {
+        self.statement_time = current_time();
+        let result = match statement {
+            SQLStatement::CreateTable(statement) => self.create_table(statement),
+            SQLStatement::AlterTable(statement) => self.alter_table(statement),
+            SQLStatement::DropTable(statement) => self.drop_table(statement),
+            SQLStatement::Select(statement) => self.select(statement),
+            SQLStatement::Insert(statement) => self.insert(statement),
+            SQLStatement::Update(statement) => self.update(statement),
+            SQLStatement::Delete(statement) => self.delete(statement),
+        };
+        self.subqueries.get_mut().clear();
+        result
+    }
+
+    fn create_table(&mut self, statement: &CreateStatement) -> Result<QueryResult, ExecutorError> {
+        if statement.if_not_exists
+            && self
+                .access_manager
+                .table::<ExecutorError>(&statement.table_name)
+                .is_ok()
+        {
+            return Ok(QueryResult::Done);
+        }
+        let columns = statement
+            .columns
+            .iter()
+            .map(|column| column.name.clone())
+            .collect();
+        let mut table = Table::new(statement.table_name.clone(), columns);
+        table.types = statement
+            .columns
+            .iter()
+            .map(|column| column.data_type)
+            .collect();
+        table.defaults = statement.columns.iter().map(column_default).collect();
+        table.not_null = statement.columns.iter().map(column_not_null).collect();
+        for column in &statement.columns {
+            for constraint in column_constraints(column) {
+                table.add_constraint::<ExecutorError>(constraint)?;
+            }
+        }
+        for constraint in &statement.constraints {
+            if let TableConstraintKind::PrimaryKey(columns) = &constraint.kind {
+                for column in columns {
+                    let index = table.resolve_column::<ExecutorError>(column)?;
+                    table.not_null[index] = true;
+                }
+            }
+            table.add_constraint::<ExecutorError>(constraint.clone())?;
+        }
+        for (index, default) in table.defaults.iter().enumerate() {
+            if let Some(default) = default {
+                self.evaluate_default(&table, index, default)?;
+            }
+        }
+        self.check_constraint_targets(&table, &table.constraints)?;
+        self.access_manager.create_table(table)?;
+        Ok(QueryResult::Done)
+    }
+
+    fn drop_table(&mut self, statement: &DropStatement) -> Result<QueryResult, ExecutorError> {
+        let mut dropped: Vec<String> = Vec::new();
+        for name in &statement.table_names {
+            match self.access_manager.table::<ExecutorError>(name) {
+                Ok(_) if !dropped.contains(name) => dropped.push(name.clone()),
+                Ok(_) => {}
+                Err(_) if statement.if_exists => {}
+                Err(error) => return Err(error),
+            }
+        }
+        // foreign keys of the remaining tables referencing a dropped one, as
+        // (table, constraint, referenced table)
+        let mut dependents = Vec::new();
+        for table in self.access_manager.tables() {
+            if dropped.contains(&table.name) {
+                continue;
+            }
+            for constraint in &table.constraints {
+                if let (Some(name), TableConstraintKind::ForeignKey { references, .. }) =
+                    (&constraint.name, &constraint.kind)
+                {
+                    if dropped.contains(&references.table) {
+                        dependents.push((
+                            table.name.clone(),
+                            name.clone(),
+                            references.table.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        dependents.sort();
+        if let (DropBehavior::Restrict, Some((table, constraint, referenced))) =
+            (statement.behavior, dependents.first())
+        {
+            return Err(ExecutorError::cause(&format!(
+                "cannot drop table {} because constraint {} on table {} depends on it",
+                referenced, constraint, table
+            )));
+        }
+        // CASCADE drops the foreign keys, but keeps the tables that have them
+        for (table, constraint, _) in dependents {
+            self.access_manager
+                .table_mut::<ExecutorError>(&table)?
+                .constraints
+                .retain(|other| other.name.as_ref() != Some(&constraint));
+        }
+        for name in dropped {
+            self.access_manager.drop_table::<ExecutorError>(&name)?;
+        }
+        Ok(QueryResult::Done)
+    }
+
+    fn alter_table(&mut self, statement: &AlterStatement) -> Result<QueryResult, ExecutorError> {
+        let table_name = &statement.table_name;
+        match &statement.operation {
+            AlterOperation::AddColumn(column) => self.add_column(table_name, column)?,
+            AlterOperation::DropColumn { name, behavior } => {
+                self.drop_column(table_name, name, *behavior)?
+            }
+            AlterOperation::RenameColumn { old, new } => {
+                self.rename_column(table_name, old, new)?
+            }
+            AlterOperation::RenameTable(new_name) => {
+                self.access_manager
+                    .rename_table::<ExecutorError>(table_name, new_name)?;
+                for table in self.access_manager.tables_mut() {
+                    for constraint in &mut table.constraints {
+                        if let TableConstraintKind::ForeignKey { references, .. } =
+                            &mut constraint.kind
+                        {
+                            if references.table == *table_name {
+                                references.table = new_name.clone();
+                            }
+                        }
+                    }
+                }
+            }
+            AlterOperation::AlterColumn { name, action } => {
+                self.alter_column(table_name, name, action)?
+            }
+            AlterOperation::AddConstraint(constraint) => {
+                self.add_table_constraint(table_name, constraint)?
+            }
+            AlterOperation::DropConstraint(name) => self.drop_constraint(table_name, name)?,
+        }
+        Ok(QueryResult::Done)
+    }
+
+    /// Adds `column` without rewriting the stored rows, which read its
+    /// default evaluated once, or NULL without one.
+    fn add_column(
+        &mut self,
+        table_name: &str,
+        column: &ColumnDefinition,
+    ) -> Result<(), ExecutorError> {
+        let table = self.access_manager.table::<ExecutorError>(table_name)?;
+        if table.column_index(&column.name).is_some() {
+            return Err(ExecutorError::cause(&format!(
+                "column {} of relation {} already exists",
+                column.name, table.name
+            )));
+        }
+        let default = column_default(column);
+        let missing = match (&default, &column.data_type) {
+            (Some(default), data_type) => {
+                let value = ExpressionEvaluator::evaluate(default, &self.empty_scope())?;
+                match data_type {
+                    Some(data_type) => value.cast::<ExecutorError>(data_type)?,
+                    None => value,
+                }
+            }
+            (None, _) => Value::Null,
+        };
+        let not_null = column_not_null(column);
+        if not_null && missing == Value::Null && !table.rows.is_empty() {
+            return Err(ExecutorError::cause(&format!(
+                "column {} of relation {} contains null values",
+                column.name, table.name
+            )));
+        }
+
+        let table = self.access_manager.table_mut::<ExecutorError>(table_name)?;
+        let primary_key = column
+            .constraints
+            .iter()
+            .any(|constraint| constraint.option == ColumnOption::PrimaryKey);
+        if primary_key && table.primary_key().is_some() {
+            return Err(ExecutorError::cause(&format!(
+                "multiple primary keys for table {} are not allowed",
+                table.name
+            )));
+        }
+        let constraints = table.constraints.clone();
+        for constraint in column_constraints(column) {
+            if let Err(error) = table.add_constraint::<ExecutorError>(constraint) {
+                table.constraints = constraints;
+                return Err(error);
+            }
+        }
+        table.add_column(column.name.clone(), column.data_type, default, missing);
+        *table.not_null.last_mut().unwrap() = not_null;
+        if let Err(error) = self.validate_constraints(table_name, constraints.len()) {
+            let table = self.access_manager.table_mut::<ExecutorError>(table_name)?;
+            table.constraints = constraints;
+            table.drop_column(table.columns.len() - 1);
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Drops a column along with the constraints of its table on it. The
+    /// foreign keys referencing it are only dropped with CASCADE.
+    fn drop_column(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        behavior: DropBehavior,
+    ) -> Result<(), ExecutorError> {
+        let table = self.access_manager.table::<ExecutorError>(table_name)?;
+        let index = table.resolve_column::<ExecutorError>(column)?;
+        // as (table, constraint)
+        let mut dependents = Vec::new();
+        for other in self.access_manager.tables() {
+            for constraint in &other.constraints {
+                if let TableConstraintKind::ForeignKey { references, .. } = &constraint.kind {
+                    let dropped =
+                        other.name == table.name && constraint_involves(constraint, column);
+                    if !dropped
+                        && references.table == table.name
+                        && referenced_columns(table, &references.columns)
+                            .contains(&column.to_string())
+                    {
+                        dependents.push((other.name.clone(), constraint.name.clone()));
+                    }
+                }
+            }
+        }
+        dependents.sort();
+        if let (DropBehavior::Restrict, Some((other, Some(constraint)))) =
+            (behavior, dependents.first())
+        {
+            return Err(ExecutorError::cause(&format!(
+                "cannot drop column {} of table {} because constraint {} on table {} depends on it",
+                column, table_name, constraint, other
+            )));
+        }
+        for (other, constraint) in dependents {
+            self.access_manager
+                .table_mut::<ExecutorError>(&other)?
+                .constraints
+                .retain(|other| other.name != constraint);
+        }
+        let table = self.access_manager.table_mut::<ExecutorError>(table_name)?;
+        table
+            .constraints
+            .retain(|constraint| !constraint_involves(constraint, column));
+        table.drop_column(index);
+        Ok(())
+    }
+
+    /// Renames a column, and the references to it in constraints.
+    fn rename_column(
+        &mut self,
+        table_name: &str,
+        old: &str,
+        new: &str,
+    ) -> Result<(), ExecutorError> {
+        let table = self.access_manager.table_mut::<ExecutorError>(table_name)?;
+        let index = table.resolve_column::<ExecutorError>(old)?;
+        if table.column_index(new).is_some() {
+            return Err(ExecutorError::cause(&format!(
+                "column {} of relation {} already exists",
+                new, table.name
+            )));
+        }
+        table.columns[index] = new.to_string();
+        let rename = |columns: &mut Vec<String>| {
+            for column in columns.iter_mut().filter(|column| *column == old) {
+                *column = new.to_string();
+            }
+        };
+        for other in self.access_manager.tables_mut() {
+            let own = other.name == table_name;
+            for constraint in &mut other.constraints {
+                match &mut constraint.kind {
+                    TableConstraintKind::PrimaryKey(columns)
+                    | TableConstraintKind::Unique(columns) => {
+                        if own {
+                            rename(columns)
+                        }
+                    }
+                    TableConstraintKind::ForeignKey {
+                        columns,
+                        references,
+                    } => {
+                        if own {
+                            rename(columns)
+                        }
+                        if references.table == table_name {
+                            rename(&mut references.columns)
+                        }
+                    }
+                    TableConstraintKind::Check(expression) => {
+                        if own {
+                            rename_column_references(expression, old, new)
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn alter_column(
+        &mut self,
+        table_name: &str,
+        column: &str,
+        action: &AlterColumnAction,
+    ) -> Result<(), ExecutorError> {
+        if let AlterColumnAction::SetDefault(expression) = action {
+            let table = self.access_manager.table::<ExecutorError>(table_name)?;
+            let index = table.resolve_column::<ExecutorError>(column)?;
+            self.evaluate_default(table, index, expression)?;
+        }
+        let table = self.access_manager.table_mut::<ExecutorError>(table_name)?;
+        let index = table.resolve_column::<ExecutorError>(column)?;
+        match action {
+            AlterColumnAction::SetNotNull => {
+                check_no_nulls(table, &[column.to_string()])?;
+                table.not_null[index] = true;
+            }
+            AlterColumnAction::DropNotNull => {
+                if let Some(primary_key) = table.primary_key() {
+                    if primary_key.iter().any(|other| other == column) {
+                        return Err(ExecutorError::cause(&format!(
+                            "column {} is in a primary key",
+                            column
+                        )));
+                    }
+                }
+                table.not_null[index] = false;
+            }
+            // the stored rows keep the value they read before
+            AlterColumnAction::SetDefault(expression) => {
+                table.defaults[index] = Some(expression.clone());
+            }
+            AlterColumnAction::DropDefault => table.defaults[index] = None,
+        }
+        Ok(())
+    }
+
+    fn add_table_constraint(
+        &mut self,
+        table_name: &str,
+        constraint: &TableConstraint,
+    ) -> Result<(), ExecutorError> {
+        let table = self.access_manager.table_mut::<ExecutorError>(table_name)?;
+        let columns = constraint_columns(constraint);
+        for column in columns {
+            table.resolve_column::<ExecutorError>(column)?;
+        }
+        let primary_key = matches!(constraint.kind, TableConstraintKind::PrimaryKey(_));
+        if primary_key {
+            if table.primary_key().is_some() {
+                return Err(ExecutorError::cause(&format!(
+                    "multiple primary keys for table {} are not allowed",
+                    table.name
+                )));
+            }
+            check_no_nulls(table, columns)?;
+        }
+        let count = table.constraints.len();
+        table.add_constraint::<ExecutorError>(constraint.clone())?;
+        if let Err(error) = self.validate_constraints(table_name, count) {
+            self.access_manager
+                .table_mut::<ExecutorError>(table_name)?
+                .constraints
+                .truncate(count);
+            return Err(error);
+        }
+        if primary_key {
+            let table = self.access_manager.table_mut::<ExecutorError>(table_name)?;
+            for column in columns {
+                let index = table.resolve_column::<ExecutorError>(column)?;
+                table.not_null[index] = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the stored rows of a table against its constraints from the
+    /// `from`th on, which were just added.
+    fn validate_constraints(&self, table_name: &str, from: usize) -> Result<(), ExecutorError> {
+        let table = self.access_manager.table::<ExecutorError>(table_name)?;
+        let rows: Vec<Vec<Value>> = table.scan().map(Cow::into_owned).collect();
+        let removed = vec![true; rows.len()];
+        let write = TableWrite {
+            table,
+            removed: &removed,
+            added: &rows,
+        };
+        let constraints = &table.constraints[from..];
+        self.check_constraint_targets(table, constraints)?;
+        self.check_constraints(&write, constraints)
+    }
+
+    /// Checks that the foreign keys among `constraints` of `table` reference
+    /// a key of an existing table and that its checks reference its columns,
+    /// even while there is no row to check.
+    fn check_constraint_targets(
+        &self,
+        table: &Table,
+        constraints: &[TableConstraint],
+    ) -> Result<(), ExecutorError> {
+        for constraint in constraints {
+            match &constraint.kind {
+                TableConstraintKind::ForeignKey {
+                    columns,
+                    references,
+                } => {
+                    self.referenced_table(table, columns, references)?;
+                }
+                TableConstraintKind::Check(expression) => {
+                    check_column_references(
+                        expression,
+                        &RelationColumn::of_table(table, &table.name),
+                    )?;
+                }
+                TableConstraintKind::PrimaryKey(_) | TableConstraintKind::Unique(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates the DEFAULT of the column at `index` of `table`, so that one
+    /// that cannot give a value of the column type fails when declared
+    /// rather than on the first INSERT using it.
+    fn evaluate_default(
+        &self,
+        table: &Table,
+        index: usize,
+        expression: &Expression,
+    ) -> Result<Value, ExecutorError> {
+        let value = ExpressionEvaluator::evaluate(expression, &self.empty_scope())?;
+        table.cast(index, value)
+    }
+
+    /// Checks the rows `write` adds against `constraints` of its table.
+    fn check_constraints(
+        &self,
+        write: &TableWrite,
+        constraints: &[TableConstraint],
+    ) -> Result<(), ExecutorError> {
+        if write.added.is_empty() {
+            return Ok(());
+        }
+        let table = write.table;
+        let columns = RelationColumn::of_table(table, &table.name);
+        for constraint in constraints {
+            let name = constraint.name.as_deref().unwrap_or_default();
+            match &constraint.kind {
+                // NULL, unknown, satisfies a check
+                TableConstraintKind::Check(expression) => {
+                    for values in write.added {
+                        let row = Scope {
+                            executor: self,
+                            columns: &columns,
+                            values,
+                            outer: None,
+                        };
+                        if ExpressionEvaluator::evaluate(expression, &row)? == Value::Boolean(false)
+                        {
+                            return Err(ExecutorError::cause(&format!(
+                                "new row for relation {} violates check constraint {}",
+                                table.name, name
+                            )));
+                        }
+                    }
+                }
+                TableConstraintKind::PrimaryKey(key) | TableConstraintKind::Unique(key) => {
+                    let indexes = column_indexes(table, key)?;
+                    let mut seen = HashSet::new();
+                    for values in write.rows() {
+                        if let Some(key) = row_key(&values, &indexes) {
+                            if !seen.insert(key) {
+                                return Err(ExecutorError::cause(&format!(
+                                    "duplicate key value violates unique constraint {}",
+                                    name
+                                )));
+                            }
+                        }
+                    }
+                }
+                TableConstraintKind::ForeignKey {
+                    columns: key,
+                    references,
+                } => {
+                    let indexes = column_indexes(table, key)?;
+                    let keys: Vec<GroupKey> = write
+                        .added
+                        .iter()
+                        .filter_map(|values| row_key(values, &indexes))
+                        .collect();
+                    // rows with a NULL in the key reference nothing
+                    if keys.is_empty() {
+                        continue;
+                    }
+                    let referenced = self.referenced_keys(write, key, references)?;
+                    if keys.iter().any(|key| !referenced.contains(key)) {
+                        return Err(ExecutorError::cause(&format!(
+                            "insert or update on table {} violates foreign key constraint {}",
+                            table.name, name
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the rows `write` removes are not referenced by foreign
+    /// keys, unless rows it adds have the same key.
+    fn check_references(&self, write: &TableWrite) -> Result<(), ExecutorError> {
+        if !write.removed.contains(&true) {
+            return Ok(());
+        }
+        for child in self.access_manager.tables() {
+            for constraint in &child.constraints {
+                let TableConstraintKind::ForeignKey {
+                    columns,
+                    references,
+                } = &constraint.kind
+                else {
+                    continue;
+                };
+                if references.table != write.table.name {
+                    continue;
+                }
+                let referenced = self.referenced_keys(write, columns, references)?;
+                let indexes = column_indexes(child, columns)?;
+                let rows: Box<dyn Iterator<Item = Cow<[Value]>>> =
+                    match child.name == write.table.name {
+                        true => Box::new(write.rows()),
+                        false => Box::new(child.scan()),
+                    };
+                for values in rows {
+                    match row_key(&values, &indexes) {
+                        Some(key) if !referenced.contains(&key) => {
+                            return Err(ExecutorError::cause(&format!(
+                                "update or delete on table {} violates foreign key constraint {} on table {}",
+                                write.table.name,
+                                constraint.name.as_deref().unwrap_or_default(),
+                                child.name
+                            )))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys a foreign key of `columns` may reference once `write` is done.
+    fn referenced_keys(
+        &self,
+        write: &TableWrite,
+        columns: &[String],
+        references: &ForeignKeyReference,
+    ) -> Result<HashSet<GroupKey>, ExecutorError> {
+        let (parent, indexes) = self.referenced_table(write.table, columns, references)?;
+        let keys = match references.table == write.table.name {
+            true => write
+                .rows()
+                .filter_map(|values| row_key(&values, &indexes))
+                .collect(),
+            false => parent
+                .scan()
+                .filter_map(|values| row_key(&values, &indexes))
+                .collect(),
+        };
+        Ok(keys)
+    }
+
+    /// The table a foreign key of `columns` of `table` references, with the
+    /// indexes of the referenced columns.
+    fn referenced_table<'t>(
+        &'t self,
+        table: &'t Table,
+        columns: &[String],
+        references: &ForeignKeyReference,
+    ) -> Result<(&'t Table, Vec<usize>), ExecutorError> {
+        let parent = match references.table == table.name {
+            true => table,
+            false => self.access_manager.table(&references.table)?,
+        };
+        if references.columns.is_empty() && parent.primary_key().is_none() {
+            return Err(ExecutorError::cause(&format!(
+                "there is no primary key for referenced table {}",
+                parent.name
+            )));
+        }
+        let referenced = referenced_columns(parent, &references.columns);
+        if referenced.len() != columns.len() {
+            return Err(ExecutorError::cause(
+                "number of referencing and referenced columns for foreign key disagree",
+            ));
+        }
+        Ok((parent, column_indexes(parent, referenced)?))
+    }
+
+    /// Drops a constraint, unless it is a key a foreign key references.
+    fn drop_constraint(&mut self, table_name: &str, name: &str) -> Result<(), ExecutorError> {
+        let table = self.access_manager.table::<ExecutorError>(table_name)?;
+        let constraint = table.constraint(name).ok_or_else(|| {
+            ExecutorError::cause(&format!(
+                "constraint {} of relation {} does not exist",
+                name, table.name
+            ))
+        })?;
+        if let TableConstraintKind::PrimaryKey(columns) | TableConstraintKind::Unique(columns) =
+            &constraint.kind
+        {
+            for other in self.access_manager.tables() {
+                for dependent in &other.constraints {
+                    if let TableConstraintKind::ForeignKey { references, .. } = &dependent.kind {
+                        if references.table == table.name
+                            && referenced_columns(table, &references.columns) == &columns[..]
+                        {
+                            return Err(ExecutorError::cause(&format!(
+                                "cannot drop constraint {} on table {} because constraint {} on table {} depends on it",
+                                name,
+                                table.name,
+                                dependent.name.as_deref().unwrap_or_default(),
+                                other.name
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        self.access_manager
+            .table_mut::<ExecutorError>(table_name)?
+            .constraints
+            .retain(|constraint| constraint.name.as_deref() != Some(name));
+        Ok(())
+    }
+
+    /// Context of the expressions of a statement outside any row, like the
+    /// VALUES of an INSERT or a DEFAULT.
+    fn empty_scope(&self) -> Scope<'_> {
+        Scope {
+            executor: self,
+            columns: &[],
+            values: &[],
+            outer: None,
+        }
+    }
+
+    fn select(&self, query: &Query) -> Result<QueryResult, ExecutorError> {
+        let (columns, rows) = self.query_rows(query, None)?;
+        Ok(QueryResult::Rows { columns, rows })
+    }
+
+    /// Executes a query, where `outer` is the current row of the enclosing
+    /// query when this is a correlated subquery.
+    fn query_rows(
+        &self,
+        query: &Query,
+        outer: Option<&Scope>,
+    ) -> Result<(Vec<String>, Vec<Vec<Value>>), ExecutorError> {
+        match query {
+            Query::Select(statement) => self.select_rows(statement, outer),
+            Query::SetOperation(operation) => self.set_operation_rows(operation, outer),
+        }
+    }
+
+    fn select_rows(
+        &self,
+        statement: &SelectStatement,
+        outer: Option<&Scope>,
+    ) -> Result<(Vec<String>, Vec<Vec<Value>>), ExecutorError> {
+        self.with_common_tables(statement.with.as_ref(), outer, || {
+            let relation = self.scan_from(&statement.from, outer)?;
+            let rows = self.filter(&relation, statement.where_clause.as_ref(), outer)?;
+            self.finish_select(statement, &relation.columns, rows, outer)
+        })
+    }
+
+    /// Runs `f` with the queries of `with` readable as tables.
+    fn with_common_tables<T>(
+        &self,
+        with: Option<&With>,
+        outer: Option<&Scope>,
+        f: impl FnOnce() -> Result<T, ExecutorError>,
+    ) -> Result<T, ExecutorError> {
+        let Some(with) = with else {
+            return f();
+        };
+        self.scoped(|| {
+            for query in &with.queries {
+                let table = match query.is_recursive(with) {
+                    true => self.recursive_common_table(query, outer)?,
+                    false => self.common_table(query, outer)?,
+                };
+                self.common_tables.borrow_mut().push(table);
+            }
+            f()
+        })
+    }
+
+    /// Runs `f`, then forgets the WITH queries it bound as well as the
+    /// subqueries it executed, whose cached results may have read them.
+    fn scoped<T>(&self, f: impl FnOnce() -> Result<T, ExecutorError>) -> Result<T, ExecutorError> {
+        let depth = self.common_tables.borrow().len();
+        let cached: HashSet<usize> = self.subqueries.borrow().keys().copied().collect();
+        let result = f();
+        self.common_tables.borrow_mut().truncate(depth);
+        self.subqueries
+            .borrow_mut()
+            .retain(|address, _| cached.contains(address));
+        result
+    }
+
+    fn common_table(
+        &self,
+        query: &CommonTableExpression,
+        outer: Option<&Scope>,
+    ) -> Result<CommonTable, ExecutorError> {
+        let (names, rows) = self.query_rows(&query.query, outer)?;
+        Ok(CommonTable {
+            name: query.name.clone(),
+            columns: common_table_columns(query, names)?,
+            rows,
+        })
+    }
+
+    /// Evaluates `non-recursive-term UNION [ALL] recursive-term` by running
+    /// the recursive term against the rows the previous run added, until a
+    /// run adds none.
+    fn recursive_common_table(
+        &self,
+        query: &CommonTableExpression,
+        outer: Option<&Scope>,
+    ) -> Result<CommonTable, ExecutorError> {
+        let operation = match &query.query {
+            Query::SetOperation(operation) if operation.operator == SetOperator::Union => operation,
+            _ => {
+                return Err(ExecutorError::cause(&format!(
+                    "recursive query {} does not have the form non-recursive-term UNION [ALL] recursive-term",
+                    query.name
+                )))
+            }
+        };
+        self.with_common_tables(operation.with.as_ref(), outer, || {
+            let (names, rows) = self.query_rows(&operation.left, outer)?;
+            let columns = common_table_columns(query, names)?;
+            let mut seen = HashSet::new();
+            let mut working = match operation.all {
+                true => rows,
+                false => distinct_rows(rows, &mut seen),
+            };
+            let mut rows = working.clone();
+            for _ in 0..MAX_RECURSION {
+                if working.is_empty() {
+                    return Ok(CommonTable {
+                        name: query.name.clone(),
+                        columns,
+                        rows,
+                    });
+                }
+                let (new_names, new_rows) = self.scoped(|| {
+                    self.common_tables.borrow_mut().push(CommonTable {
+                        name: query.name.clone(),
+                        columns: columns.clone(),
+                        rows: working,
+                    });
+                    self.query_rows(&operation.right, outer)
+                })?;
+                check_set_operation(
+                    SetOperator::Union,
+                    (&columns, &rows),
+                    (&new_names, &new_rows),
+                )?;
+                working = match operation.all {
+                    true => new_rows,
+                    false => distinct_rows(new_rows, &mut seen),
+                };
+                rows.extend(working.iter().cloned());
+            }
+            Err(ExecutorError::cause(&format!(
+                "recursive query {} did not finish within {} iterations",
+                query.name, MAX_RECURSION
+            )))
+        })
+    }
+
+    /// Executes a set operation, sorting and limiting the combined rows by
+    /// their output columns.
+    fn set_operation_rows(
+        &self,
+        operation: &SetOperation,
+        outer: Option<&Scope>,
+    ) -> Result<(Vec<String>, Vec<Vec<Value>>), ExecutorError> {
+        self.with_common_tables(operation.with.as_ref(), outer, || {
+            let (names, left) = self.query_rows(&operation.left, outer)?;
+            let (right_names, right) = self.query_rows(&operation.right, outer)?;
+            check_set_operation(operation.operator, (&names, &left), (&right_names, &right))?;
+            let columns: Vec<RelationColumn> = names
+                .iter()
+                .map(|name| RelationColumn::unqualified(name))
+                .collect();
+            let projection: Vec<Expression> = names
+                .iter()
+                .map(|name| Expression::Column(name.clone()))
+                .collect();
+            let mut keys = Vec::with_capacity(operation.order_by.len());
+            for order_by in &operation.order_by {
+                keys.push(sort_key(&order_by.expression, &names, &projection)?);
+            }
+            let mut output = Vec::new();
+            for values in combine(operation.operator, operation.all, left, right) {
+                let row = Scope {
+                    executor: self,
+                    columns: &columns,
+                    values: &values,
+                    outer,
+                };
+                output.push((Self::sort_keys(&row, &keys, &values)?, values));
+            }
+            let rows = self.sort_and_limit(
+                &operation.order_by,
+                false,
+                operation.limit.as_ref(),
+                operation.offset.as_ref(),
+                output,
+            )?;
+            Ok((names, rows))
+        })
+    }
+
+    fn filter<'r>(
+        &self,
+        relation: &'r Relation,
+        where_clause: Option<&WhereClause>,
+        outer: Option<&Scope>,
+    ) -> Result<Vec<&'r [Value]>, ExecutorError> {
+        let mut rows = Vec::new();
+        for values in &relation.rows {
+            if let Some(where_clause) = where_clause {
+                let row = Scope {
+                    executor: self,
+                    columns: &relation.columns,
+                    values,
+                    outer,
+                };
+                if !ExpressionEvaluator::matches(&where_clause.condition, &row)? {
+                    continue;
+                }
+            }
+            rows.push(values.as_slice());
+        }
+        Ok(rows)
+    }
+
+    /// Groups, projects, sorts and limits the rows that passed WHERE.
+    fn finish_select(
+        &self,
+        statement: &SelectStatement,
+        columns: &[RelationColumn],
+        rows: Vec<&[Value]>,
+        outer: Option<&Scope>,
+    ) -> Result<(Vec<String>, Vec<Vec<Value>>), ExecutorError> {
+        let (names, projection) = projection(&statement.columns, columns)?;
+        let mut keys = Vec::with_capacity(statement.order_by.len());
+        for order_by in &statement.order_by {
+            keys.push(sort_key(&order_by.expression, &names, &projection)?);
+        }
+        // the rows are deduplicated on the keys following the ORDER BY keys
+        let distinct_keys = match &statement.distinct {
+            None => Vec::new(),
+            Some(Distinct::Distinct) => {
+                if keys.iter().any(|key| matches!(key, SortKey::Expression(_))) {
+                    return Err(ExecutorError(
+                        "for SELECT DISTINCT, ORDER BY expressions must appear in select list"
+                            .to_string(),
+                    ));
+                }
+                (0..names.len()).map(SortKey::Output).collect()
+            }
+            Some(Distinct::On(expressions)) => {
+                let mut distinct_keys = Vec::with_capacity(expressions.len());
+                for expression in expressions {
+                    distinct_keys.push(sort_key(expression, &names, &projection)?);
+                }
+                if !keys
+                    .iter()
+                    .take(distinct_keys.len())
+                    .all(|key| distinct_keys.contains(key))
+                {
+                    return Err(ExecutorError(
+                        "SELECT DISTINCT ON expressions must match initial ORDER BY expressions"
+                            .to_string(),
+                    ));
+                }
+                distinct_keys
+            }
+        };
+        keys.extend(distinct_keys);
+        let grouped = !statement.group_by.is_empty()
+            || statement.having.is_some()
+            || projection.iter().any(Expression::contains_aggregate)
+            || keys.iter().any(
+                |key| matches!(key, SortKey::Expression(expression) if expression.contains_aggregate()),
+            );
+        let mut output = Vec::new();
+        if grouped {
+            for group in self.group_rows(columns, rows, &statement.group_by, outer)? {
+                if let Some(having) = &statement.having {
+                    if !ExpressionEvaluator::matches(having, &group)? {
+                        continue;
+                    }
+                }
+                let values = Self::project(&group, &projection)?;
+                output.push((Self::sort_keys(&group, &keys, &values)?, values));
+            }
+        } else {
+            for values in rows {
+                let row = Scope {
+                    executor: self,
+                    columns,
+                    values,
+                    outer,
+                };
+                let values = Self::project(&row, &projection)?;
+                output.push((Self::sort_keys(&row, &keys, &values)?, values));
+            }
+        }
+
+        let rows = self.sort_and_limit(
+            &statement.order_by,
+            statement.distinct.is_some(),
+            statement.limit.as_ref(),
+            statement.offset.as_ref(),
+            output,
+        )?;
+        Ok((names, rows))
+    }
+
+    /// Sorts output rows by their sort keys, keeps the first row of each set
+    /// of rows equal on the keys after those of ORDER BY if `distinct`, then
+    /// applies OFFSET and LIMIT.
+    fn sort_and_limit(
+        &self,
+        order_by: &[OrderByExpression],
+        distinct: bool,
+        limit: Option<&Expression>,
+        offset: Option<&Expression>,
+        mut output: Vec<(Vec<Value>, Vec<Value>)>,
+    ) -> Result<Vec<Vec<Value>>, ExecutorError> {
+        if !order_by.is_empty() {
+            output.sort_by(|(left, _), (right, _)| compare_sort_keys(order_by, left, right));
+        }
+        if distinct {
+            let mut seen = HashSet::new();
+            output.retain(|(keys, _)| seen.insert(GroupKey(keys[order_by.len()..].to_vec())));
+        }
+        let offset = self.evaluate_count(offset)?.unwrap_or(0);
+        let limit = self.evaluate_count(limit)?.unwrap_or(usize::MAX);
+        Ok(output
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(_, values)| values)
+            .collect())
+    }
+
+    /// Evaluates the FROM clause, cross joining its comma separated entries.
+    fn scan_from(
+        &self,
+        from: &[TableReference],
+        outer: Option<&Scope>,
+    ) -> Result<Relation, ExecutorError> {
+        let mut relation: Option<Relation> = None;
+        for reference in from {
+            let right = self.table_reference(reference, outer)?;
+            relation = Some(match relation {
+                Some(left) => Relation::join(
+                    left,
+                    JoinOperator::Cross,
+                    right,
+                    &JoinConstraint::None,
+                    |_, _, _| Ok(true),
+                )?,
+                None => right,
+            });
+        }
+        // without FROM a query reads a single row without columns
+        Ok(relation.unwrap_or(Relation {
+            columns: Vec::new(),
+            rows: vec![Vec::new()],
+        }))
+    }
+
+    fn table_reference(
+        &self,
+        reference: &TableReference,
+        outer: Option<&Scope>,
+    ) -> Result<Relation, ExecutorError> {
+        match reference {
+            TableReference::Table { name, alias } => {
+                let qualifier = alias.as_deref().unwrap_or(name);
+                let common_tables = self.common_tables.borrow();
+                if let Some(table) = common_tables.iter().rev().find(|t| t.name == *name) {
+                    return Ok(Relation {
+                        columns: table
+                            .columns
+                            .iter()
+                            .map(|column| RelationColumn::new(qualifier, column))
+                            .collect(),
+                        rows: table.rows.clone(),
+                    });
+                }
+                let table = self.access_manager.table(name)?;
+                Ok(Relation::scan(table, qualifier))
+            }
+            TableReference::Derived { subquery, alias } => {
+                let (names, rows) = self.query_rows(subquery, outer)?;
+                let columns = names
+                    .iter()
+                    .map(|name| RelationColumn::new(alias, name))
+                    .collect();
+                Ok(Relation { columns, rows })
+            }
+            TableReference::Join {
+                left,
+                operator,
+                right,
+                constraint,
+            } => Relation::join(
+                self.table_reference(left, outer)?,
+                *operator,
+                self.table_reference(right, outer)?,
+                constraint,
+                |condition, columns, values| {
+                    let row = Scope {
+                        executor: self,
+                        columns,
+                        values,
+                        outer,
+                    };
+                    ExpressionEvaluator::matches(condition, &row)
+                },
+            ),
+        }
+    }
+
+    /// Result rows of `subquery` for the outer row `scope`. Subqueries are
+    /// planned once per statement: uncorrelated ones run once, keyed ones run
+    /// once split by their correlation keys, and only the rest run again for
+    /// every outer row.
+    fn subquery(
+        &self,
+        subquery: &Query,
+        scope: &Scope,
+    ) -> Result<Rc<Vec<Vec<Value>>>, ExecutorError> {
+        let address = subquery as *const Query as usize;
+        let cached = self.subqueries.borrow().get(&address).cloned();
+        let result = match cached {
+            Some(result) => result,
+            None => {
+                let common_tables = self
+                    .common_tables
+                    .borrow()
+                    .iter()
+                    .map(|table| (table.name.clone(), table.columns.clone()))
+                    .collect();
+                let plan = QueryPlanner::new(&self.access_manager, common_tables)
+                    .plan_subquery(subquery)?;
+                let result = Rc::new(self.prepare_subquery(plan, subquery)?);
+                self.subqueries
+                    .borrow_mut()
+                    .insert(address, Rc::clone(&result));
+                result
+            }
+        };
+        match result.as_ref() {
+            SubqueryResult::Rows(rows) => Ok(Rc::clone(rows)),
+            SubqueryResult::Keyed {
+                outer,
+                parts,
+                empty,
+                ..
+            } => {
+                let mut key = Vec::with_capacity(outer.len());
+                for expression in outer {
+                    match ExpressionEvaluator::evaluate(expression, scope)? {
+                        // `inner = NULL` holds for no row
+                        Value::Null => return Ok(Rc::clone(empty)),
+                        value => key.push(value),
+                    }
+                }
+                Ok(Rc::clone(parts.get(&GroupKey(key)).unwrap_or(empty)))
+            }
+            SubqueryResult::Correlated => Ok(Rc::new(self.query_rows(subquery, Some(scope))?.1)),
+        }
+    }
+
+    fn prepare_subquery(
+        &self,
+        plan: SubqueryPlan,
+        subquery: &Query,
+    ) -> Result<SubqueryResult, ExecutorError> {
+        let (statement, inner, outer) = match plan {
+            SubqueryPlan::Uncorrelated => {
+                let (_, rows) = self.query_rows(subquery, None)?;
+                return Ok(SubqueryResult::Rows(Rc::new(rows)));
+            }
+            SubqueryPlan::Correlated => return Ok(SubqueryResult::Correlated),
+            SubqueryPlan::Keyed {
+                statement,
+                inner,
+                outer,
+            } => (statement, inner, outer),
+        };
+        let (parts, empty) = self.with_common_tables(statement.with.as_ref(), None, || {
+            self.split_subquery(&statement, &inner)
+        })?;
+        Ok(SubqueryResult::Keyed {
+            _statement: statement,
+            outer,
+            parts,
+            empty: Rc::new(empty),
+        })
+    }
+
+    /// Executes a keyed subquery once for every value of its `inner`
+    /// expressions, and once for values without rows.
+    #[allow(clippy::type_complexity)]
+    fn split_subquery(
+        &self,
+        statement: &SelectStatement,
+        inner: &[Expression],
+    ) -> Result<(HashMap<GroupKey, Rc<Vec<Vec<Value>>>>, Vec<Vec<Value>>), ExecutorError> {
+        let relation = self.scan_from(&statement.from, None)?;
+        let rows = self.filter(&relation, statement.where_clause.as_ref(), None)?;
+        let mut keyed_rows: HashMap<GroupKey, Vec<&[Value]>> = HashMap::new();
+        'rows: for values in rows {
+            let row = Scope {
+                executor: self,
+                columns: &relation.columns,
+                values,
+                outer: None,
+            };
+            let mut key = Vec::with_capacity(inner.len());
+            for expression in inner {
+                match ExpressionEvaluator::evaluate(expression, &row)? {
+                    Value::Null => continue 'rows,
+                    value => key.push(value),
+                }
+            }
+            keyed_rows.entry(GroupKey(key)).or_default().push(values);
+        }
+        let mut parts = HashMap::new();
+        for (key, rows) in keyed_rows {
+            let (_, rows) = self.finish_select(statement, &relation.columns, rows, None)?;
+            parts.insert(key, Rc::new(rows));
+        }
+        let (_, empty) = self.finish_select(statement, &relation.columns, Vec::new(), None)?;
+        Ok((parts, empty))
+    }
+
+    /// Splits `rows` into groups of equal `group_by` values, in order of their
+    /// first row. Without GROUP BY all rows form a single, possibly empty, group.
+    fn group_rows<'a>(
+        &'a self,
+        columns: &'a [RelationColumn],
+        rows: Vec<&'a [Value]>,
+        group_by: &[Expression],
+        outer: Option<&'a Scope<'a>>,
+    ) -> Result<Vec<GroupRow<'a>>, ExecutorError> {
+        let group = |rows| GroupRow {
+            executor: self,
+            columns,
+            rows,
+            outer,
+        };
+        if group_by.is_empty() {
+            return Ok(vec![group(rows)]);
+        }
+        let mut groups: Vec<GroupRow> = Vec::new();
+        let mut indexes = HashMap::new();
+        for values in rows {
+            let row = Scope {
+                executor: self,
+                columns,
+                values,
+                outer,
+            };
+            let mut key = Vec::with_capacity(group_by.len());
+            for expression in group_by {
+                key.push(ExpressionEvaluator::evaluate(expression, &row)?);
+            }
+            let index = *indexes.entry(GroupKey(key)).or_insert_with(|| {
+                groups.push(group(Vec::new()));
+                groups.len() - 1
+            });
+            groups[index].rows.push(values);
+        }
+        Ok(groups)
+    }
+
+    /// Evaluates the projected values of one output row.
+    fn project<C: RowContext>(
+        row: &C,
+        projection: &[Expression],
+    ) -> Result<Vec<Value>, ExecutorError> {
+        let mut values = Vec::with_capacity(projection.len());
+        for expression in projection {
+            values.push(ExpressionEvaluator::evaluate(expression, row)?);
+        }
+        Ok(values)
+    }
+
+    /// Evaluates the sort keys of one output row with projected `values`.
+    fn sort_keys<C: RowContext>(
+        row: &C,
+        keys: &[SortKey],
+        values: &[Value],
+    ) -> Result<Vec<Value>, ExecutorError> {
+        let mut key_values = Vec::with_capacity(keys.len());
+        for key in keys {
+            key_values.push(match key {
+                SortKey::Output(index) => values[*index].clone(),
+                SortKey::Expression(expression) => ExpressionEvaluator::evaluate(expression, row)?,
+            });
+        }
+        Ok(key_values)
+    }
+
+    fn insert(&mut self, statement: &InsertStatement) -> Result<QueryResult, ExecutorError> {
+        // evaluate every row before writing, so a failing row inserts nothing
+        let (rows, returned) = self.with_common_tables(statement.with.as_ref(), None, || {
+            let table = self.access_manager.table(&statement.table_name)?;
+            let targets = Self::insert_targets(table, &statement.columns)?;
+            let tuples = match &statement.source {
+                InsertSource::Values(values) => self.insert_values(table, &targets, values)?,
+                InsertSource::Query(query) => {
+                    let (names, rows) = self.query_rows(query, None)?;
+                    Self::check_insert_width(&statement.columns, &targets, names.len())?;
+                    let mut tuples = Vec::with_capacity(rows.len());
+                    for row in rows {
+                        let mut values = vec![None; table.columns.len()];
+                        for (index, value) in targets.iter().zip(row) {
+                            values[*index] = Some(table.cast(*index, value)?);
+                        }
+                        tuples.push(values);
+                    }
+                    tuples
+                }
+            };
+            let mut rows = Vec::with_capacity(tuples.len());
+            for values in tuples {
+                let row = self.fill_defaults(table, values)?;
+                table.check_not_null::<ExecutorError>(&row)?;
+                rows.push(row);
+            }
+            let write = TableWrite {
+                table,
+                removed: &[],
+                added: &rows,
+            };
+            self.check_constraints(&write, &table.constraints)?;
+            let columns = RelationColumn::of_table(table, &statement.table_name);
+            let returned = self.returning(&columns, &statement.returning, &rows)?;
+            Ok((rows, returned))
+        })?;
+
+        let table = self
+            .access_manager
+            .table_mut::<ExecutorError>(&statement.table_name)?;
+        let inserted = rows.len();
+        table.rows.extend(rows);
+        Ok(returned.unwrap_or(QueryResult::RowsAffected(inserted)))
+    }
+
+    /// Evaluates the tuples of `INSERT ... VALUES` into the `targets` columns
+    /// of `table`, leaving the columns without a value for their default.
+    fn insert_values(
+        &self,
+        table: &Table,
+        targets: &[usize],
+        values: &[Vec<InsertValue>],
+    ) -> Result<Vec<Vec<Option<Value>>>, ExecutorError> {
+        let row = self.empty_scope();
+        let mut tuples = Vec::with_capacity(values.len());
+        for tuple in values {
+            if tuple.len() > targets.len() {
+                return Err(ExecutorError::cause(
+                    "INSERT has more expressions than target columns",
+                ));
+            }
+            let mut values = vec![None; table.columns.len()];
+            for (index, value) in targets.iter().zip(tuple) {
+                if let InsertValue::Expression(expression) = value {
+                    let value = ExpressionEvaluator::evaluate(expression, &row)?;
+                    values[*index] = Some(table.cast(*index, value)?);
+                }
+            }
+            tuples.push(values);
+        }
+        Ok(tuples)
+    }
+
+    /// Indexes of the columns an INSERT fills, all columns in table order
+    /// when it names none.
+    fn insert_targets(table: &Table, columns: &[String]) -> Result<Vec<usize>, ExecutorError> {
+        if columns.is_empty() {
+            return Ok((0..table.columns.len()).collect());
+        }
+        columns
+            .iter()
+            .map(|column| {
+                table.column_index(column).ok_or_else(|| {
+                    ExecutorError::cause(&format!(
+                        "column {} of relation {} does not exist",
+                        column, table.name
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that a query inserting into `targets` returns `width` columns,
+    /// or fewer when the INSERT names no `columns`.
+    fn check_insert_width(
+        columns: &[String],
+        targets: &[usize],
+        width: usize,
+    ) -> Result<(), ExecutorError> {
+        if width > targets.len() {
+            return Err(ExecutorError::cause(
+                "INSERT has more expressions than target columns",
+            ));
+        }
+        if !columns.is_empty() && width < targets.len() {
+            return Err(ExecutorError::cause(
+                "INSERT has more target columns than expressions",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Completes a new row of `table`, giving the columns without a value
+    /// their default.
+    fn fill_defaults(
+        &self,
+        table: &Table,
+        values: Vec<Option<Value>>,
+    ) -> Result<Vec<Value>, ExecutorError> {
+        let row = self.empty_scope();
+        let mut filled = Vec::with_capacity(values.len());
+        for (index, value) in values.into_iter().enumerate() {
+            filled.push(match (value, &table.defaults[index]) {
+                (Some(value), _) => value,
+                (None, Some(default)) => {
+                    table.cast(index, ExpressionEvaluator::evaluate(default, &row)?)?
+                }
+                (None, None) => Value::Null,
+            });
+        }
+        Ok(filled)
+    }
+
+    /// Evaluates the RETURNING list of a statement over the `rows` it wrote
+    /// or deleted, `None` when there is no RETURNING clause.
+    fn returning(
+        &self,
+        columns: &[RelationColumn],
+        items: &[SelectItem],
+        rows: &[Vec<Value>],
+    ) -> Result<Option<QueryResult>, ExecutorError> {
+        if items.is_empty() {
+            return Ok(None);
+        }
+        let (names, projection) = projection(items, columns)?;
+        let mut returned = Vec::with_capacity(rows.len());
+        for values in rows {
+            let row = Scope {
+                executor: self,
+                columns,
+                values,
+                outer: None,
+            };
+            returned.push(Self::project(&row, &projection)?);
+        }
+        Ok(Some(QueryResult::Rows {
+            columns: names,
+            rows: returned,
+        }))
+    }
+
+    fn update(&mut self, statement: &UpdateStatement) -> Result<QueryResult, ExecutorError> {
+        // evaluate everything before writing, so a failing row leaves the
+        // table unchanged and every SET expression sees the old row
+        let (updates, returned) = self.with_common_tables(statement.with.as_ref(), None, || {
+            let table = self.access_manager.table(&statement.table_name)?;
+            let from = self.scan_from(&statement.from, None)?;
+            let mut columns = RelationColumn::of_table(table, statement.qualifier());
+            columns.extend(from.columns);
+            let mut assignments = Vec::with_capacity(statement.set_clauses.len());
+            for set_clause in &statement.set_clauses {
+                let index = table.column_index(&set_clause.field).ok_or_else(|| {
+                    ExecutorError::cause(&format!("column {} does not exist", set_clause.field))
+                })?;
+                assignments.push((index, &set_clause.value));
+            }
+            let mut updates = Vec::new();
+            let mut returned = Vec::new();
+            for (position, values) in table.scan().enumerate() {
+                // a row joining several rows of FROM is updated by the first
+                for joined in &from.rows {
+                    let mut values = values.to_vec();
+                    values.extend(joined.iter().cloned());
+                    let row = Scope {
+                        executor: self,
+                        columns: &columns,
+                        values: &values,
+                        outer: None,
+                    };
+                    if let Some(where_clause) = &statement.where_clause {
+                        if !ExpressionEvaluator::matches(&where_clause.condition, &row)? {
+                            continue;
+                        }
+                    }
+                    let mut new_values = values.clone();
+                    for (index, expression) in &assignments {
+                        let value = ExpressionEvaluator::evaluate(expression, &row)?;
+                        new_values[*index] = table.cast(*index, value)?;
+                    }
+                    if !statement.returning.is_empty() {
+                        returned.push(new_values.clone());
+                    }
+                    new_values.truncate(table.columns.len());
+                    table.check_not_null::<ExecutorError>(&new_values)?;
+                    updates.push((position, new_values));
+                    break;
+                }
+            }
+            let mut removed = vec![false; table.rows.len()];
+            let mut added = Vec::with_capacity(updates.len());
+            for (position, new_values) in &updates {
+                removed[*position] = true;
+                added.push(new_values.clone());
+            }
+            let write = TableWrite {
+                table,
+                removed: &removed,
+                added: &added,
+            };
+            self.check_constraints(&write, &table.constraints)?;
+            self.check_references(&write)?;
+            let returned = self.returning(&columns, &statement.returning, &returned)?;
+            Ok((updates, returned))
+        })?;
+
+        let table = self
+            .access_manager
+            .table_mut::<ExecutorError>(&statement.table_name)?;
+        let updated = updates.len();
+        for (position, new_values) in updates {
+            table.rows[position] = new_values;
+        }
+        Ok(returned.unwrap_or(QueryResult::RowsAffected(updated)))
+    }
+
+    fn delete(&mut self, statement: &DeleteStatement) -> Result<QueryResult, ExecutorError> {
+        let (deleted, returned) = self.with_common_tables(statement.with.as_ref(), None, || {
+            let table = self.access_manager.table(&statement.table_name)?;
+            let using = self.scan_from(&statement.using, None)?;
+            let mut columns = RelationColumn::of_table(table, &statement.table_name);
+            columns.extend(using.columns);
+            let mut deleted = vec![false; table.rows.len()];
+            let mut returned = Vec::new();
+            for (position, values) in table.scan().enumerate() {
+                // a row joining several rows of USING is returned with the first
+                for joined in &using.rows {
+                    let mut values = values.to_vec();
+                    values.extend(joined.iter().cloned());
+                    if let Some(where_clause) = &statement.where_clause {
+                        let row = Scope {
+                            executor: self,
+                            columns: &columns,
+                            values: &values,
+                            outer: None,
+                        };
+                        if !ExpressionEvaluator::matches(&where_clause.condition, &row)? {
+                            continue;
+                        }
+                    }
+                    deleted[position] = true;
+                    if !statement.returning.is_empty() {
+                        returned.push(values);
+                    }
+                    break;
+                }
+            }
+            self.check_references(&TableWrite {
+                table,
+                removed: &deleted,
+                added: &[],
+            })?;
+            let returned = self.returning(&columns, &statement.returning, &returned)?;
+            Ok((deleted, returned))
+        })?;
+
+        let table = self
+            .access_manager
+            .table_mut::<ExecutorError>(&statement.table_name)?;
+        let mut deleted_iter = deleted.iter();
+        table
+            .rows
+            .retain(|_| !deleted_iter.next().unwrap_or(&false));
+        Ok(returned.unwrap_or(QueryResult::RowsAffected(
+            deleted.into_iter().filter(|deleted| *deleted).count(),
+        )))
+    }
+
+    /// Evaluates a LIMIT or OFFSET count, NULL meaning no count was given.
+    fn evaluate_count(
+        &self,
+        expression: Option<&Expression>,
+    ) -> Result<Option<usize>, ExecutorError> {
+        let Some(expression) = expression else {
+            return Ok(None);
+        };
+        match ExpressionEvaluator::evaluate(expression, &EmptyRow(&self.parameters))? {
+            Value::Null => Ok(None),
+            Value::Int64(count) if count >= 0 => Ok(Some(count as usize)),
+            value => Err(ExecutorError::cause(&format!(
+                "LIMIT and OFFSET must be non-negative integers, found {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// The DEFAULT expression of a column definition.
+fn column_default(column: &ColumnDefinition) -> Option<Expression> {
+    column
+        .constraints
+        .iter()
+        .find_map(|constraint| match &constraint.option {
+            ColumnOption::Default(expression) => Some(expression.clone()),
+            _ => None,
+        })
+}
+
+/// Whether a column definition is NOT NULL, explicitly or as a PRIMARY KEY.
+fn column_not_null(column: &ColumnDefinition) -> bool {
+    column.constraints.iter().any(|constraint| {
+        matches!(
+            constraint.option,
+            ColumnOption::NotNull | ColumnOption::PrimaryKey
+        )
+    })
+}
+
+/// The constraints declared with a column, as table constraints.
+fn column_constraints(column: &ColumnDefinition) -> Vec<TableConstraint> {
+    column
+        .constraints
+        .iter()
+        .filter_map(|constraint| {
+            let columns = vec![column.name.clone()];
+            let kind = match &constraint.option {
+                ColumnOption::PrimaryKey => TableConstraintKind::PrimaryKey(columns),
+                ColumnOption::Unique => TableConstraintKind::Unique(columns),
+                ColumnOption::Check(expression) => TableConstraintKind::Check(expression.clone()),
+                ColumnOption::References(references) => TableConstraintKind::ForeignKey {
+                    columns,
+                    references: references.clone(),
+                },
+                ColumnOption::NotNull | ColumnOption::Null | ColumnOption::Default(_) => {
+                    return None
+                }
+            };
+            Some(TableConstraint {
+                name: constraint.name.clone(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// The columns of a key or foreign key constraint, none for a check.
+fn constraint_columns(constraint: &TableConstraint) -> &[String] {
+    match &constraint.kind {
+        TableConstraintKind::PrimaryKey(columns)
+        | TableConstraintKind::Unique(columns)
+        | TableConstraintKind::ForeignKey { columns, .. } => columns,
+        TableConstraintKind::Check(_) => &[],
+    }
+}
+
+/// Whether a constraint of a table is on its column `column`.
+fn constraint_involves(constraint: &TableConstraint, column: &str) -> bool {
+    match &constraint.kind {
+        TableConstraintKind::Check(expression) => references_column(expression, column),
+        _ => constraint_columns(constraint)
+            .iter()
+            .any(|other| other == column),
+    }
+}
+
+/// The columns of `table` a foreign key referencing `columns` of it refers
+/// to, its primary key when no columns are given.
+fn referenced_columns<'a>(table: &'a Table, columns: &'a [String]) -> &'a [String] {
+    match columns.is_empty() {
+        true => table.primary_key().unwrap_or_default(),
+        false => columns,
+    }
+}
+
+fn references_column(expression: &Expression, column: &str) -> bool {
+    match expression {
+        Expression::Column(name) | Expression::QualifiedColumn { column: name, .. } => {
+            name == column
+        }
+        _ => expression
+            .children()
+            .into_iter()
+            .any(|child| references_column(child, column)),
+    }
+}
+
+fn rename_column_references(expression: &mut Expression, old: &str, new: &str) {
+    match expression {
+        Expression::Column(name) | Expression::QualifiedColumn { column: name, .. } => {
+            if name == old {
+                *name = new.to_string();
+            }
+        }
+        _ => {
+            for child in expression.children_mut() {
+                rename_column_references(child, old, new);
+            }
+        }
+    }
+}
+
+fn column_indexes(table: &Table, columns: &[String]) -> Result<Vec<usize>, ExecutorError> {
+    columns
+        .iter()
+        .map(|column| table.resolve_column(column))
+        .collect()
+}
+
+/// Values of the `indexes` columns of a row as a key, `None` when one is
+/// NULL, as such a key neither conflicts with nor references any other.
+fn row_key(values: &[Value], indexes: &[usize]) -> Option<GroupKey> {
+    let key: Vec<Value> = indexes.iter().map(|index| values[*index].clone()).collect();
+    match key.contains(&Value::Null) {
+        true => None,
+        false => Some(GroupKey(key)),
+    }
+}
+
+/// Fails if `expression` references a column that is not among `columns`.
+fn check_column_references(
+    expression: &Expression,
+    columns: &[RelationColumn],
+) -> Result<(), ExecutorError> {
+    let (table, name) = match expression {
+        Expression::Column(name) => (None, name),
+        Expression::QualifiedColumn { table, column } => (Some(table.as_str()), column),
+        _ => {
+            for child in expression.children() {
+                check_column_references(child, columns)?;
+            }
+            return Ok(());
+        }
+    };
+    match RelationColumn::find(columns, table, name)? {
+        Some(_) => Ok(()),
+        None => Err(RelationColumn::not_found(columns, table, name)),
+    }
+}
+
+/// Fails if a stored row of `table` has NULL in one of `columns`.
+fn check_no_nulls(table: &Table, columns: &[String]) -> Result<(), ExecutorError> {
+    for column in columns {
+        let index = table.resolve_column::<ExecutorError>(column)?;
+        if table.scan().any(|row| row[index] == Value::Null) {
+            return Err(ExecutorError::cause(&format!(
+                "column {} of relation {} contains null values",
+                column, table.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Names of the columns of a WITH query, given the names of its own columns.
+fn common_table_columns(
+    query: &CommonTableExpression,
+    names: Vec<String>,
+) -> Result<Vec<String>, ExecutorError> {
+    if query.columns.is_empty() {
+        return Ok(names);
+    }
+    if query.columns.len() != names.len() {
+        return Err(ExecutorError::cause(&format!(
+            "WITH query {} has {} columns available but {} columns specified",
+            query.name,
+            names.len(),
+            query.columns.len()
+        )));
+    }
+    Ok(query.columns.clone())
+}
+
+/// Checks that the operands of a set operation, given by their column names
+/// and rows, have as many columns and that no column mixes types.
+fn check_set_operation(
+    operator: SetOperator,
+    (left_names, left_rows): (&[String], &[Vec<Value>]),
+    (right_names, right_rows): (&[String], &[Vec<Value>]),
+) -> Result<(), ExecutorError> {
+    if left_names.len() != right_names.len() {
+        return Err(ExecutorError::cause(&format!(
+            "each {} query must have the same number of columns",
+            operator
+        )));
+    }
+    fn first_value(rows: &[Vec<Value>], index: usize) -> Option<&Value> {
+        rows.iter()
+            .filter_map(|row| row.get(index))
+            .find(|value| **value != Value::Null)
+    }
+    for index in 0..left_names.len() {
+        if let (Some(left), Some(right)) = (
+            first_value(left_rows, index),
+            first_value(right_rows, index),
+        ) {
+            if !left.same_kind(right) {
+                return Err(ExecutorError::cause(&format!(
+                    "{} types {} and {} cannot be matched",
+                    operator,
+                    left.type_name().unwrap_or_default(),
+                    right.type_name().unwrap_or_default()
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn compare_sort_keys(order_by: &[OrderByExpression], left: &[Value], right: &[Value]) -> Ordering {
+    for ((order_by, left), right) in order_by.iter().zip(left).zip(right) {
+        let ordering = match (left, right) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) if order_by.nulls_first() => Ordering::Less,
+            (Value::Null, _) => Ordering::Greater,
+            (_, Value::Null) if order_by.nulls_first() => Ordering::Greater,
+            (_, Value::Null) => Ordering::Less,
+            (left, right) if order_by.ascending => left.total_cmp(right),
+            (left, right) => right.total_cmp(left),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{BinaryOperator, DataType, Expression, ForeignKeyReference, TableConstraintKind},
+        parser::parse_script,
+        value::{Decimal, Value},
+    };
+
+    use super::{ExecutorError, QueryExecutor, QueryResult};
+
+    fn run(executor: &mut QueryExecutor, script: &str) -> QueryResult {
+        let mut result = QueryResult::Done;
+        for parsed in parse_script(script).unwrap() {
+            result = executor.execute(&parsed.statement).unwrap();
+        }
+        result
+    }
+
+    fn rows(result: QueryResult) -> Vec<Vec<Value>> {
+        match result {
+            QueryResult::Rows { rows, .. } => rows,
+            result => panic!("expected rows, found {:?}", result),
+        }
+    }
+
+    fn text(value: &str) -> Value {
+        Value::Text(value.to_string())
+    }
+
+    fn users() -> QueryExecutor {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE users (id, name, city);
+            INSERT INTO users (id, name, city) VALUES ('1', 'kim', 'Seoul');
+            INSERT INTO users (id, name, city) VALUES ('2', 'lee', 'Busan');
+            INSERT INTO users (id, name) VALUES ('3', 'park');
+            INSERT INTO users (id, name, city) VALUES ('4', 'choi', 'Seoul');",
+        );
+        executor
+    }
+
+    #[test]
+    fn test_select() {
+        let mut executor = users();
+        assert_eq!(
+            run(&mut executor, "SELECT * FROM users WHERE city = 'Busan';"),
+            QueryResult::Rows {
+                columns: vec!["id".to_string(), "name".to_string(), "city".to_string()],
+                rows: vec![vec![text("2"), text("lee"), text("Busan")]],
+            }
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT name FROM users WHERE city IS NULL OR name LIKE 'c%';"
+            )),
+            vec![vec![text("park")], vec![text("choi")]]
+        );
+    }
+
+    #[test]
+    fn test_order_by_and_limit() {
+        let mut executor = users();
+        let test_cases = vec![
+            (
+                "SELECT id FROM users ORDER BY city, name DESC;",
+                vec!["2", "1", "4", "3"],
+            ),
+            (
+                "SELECT id FROM users ORDER BY city DESC;",
+                vec!["3", "1", "4", "2"],
+            ),
+            (
+                "SELECT id FROM users ORDER BY city ASC NULLS FIRST, id DESC;",
+                vec!["3", "2", "4", "1"],
+            ),
+            (
+                "SELECT id FROM users ORDER BY city DESC NULLS LAST, id;",
+                vec!["1", "4", "2", "3"],
+            ),
+            (
+                "SELECT id FROM users ORDER BY name LIMIT 2;",
+                vec!["4", "1"],
+            ),
+            (
+                "SELECT id FROM users ORDER BY name LIMIT 2 OFFSET 1;",
+                vec!["1", "2"],
+            ),
+            (
+                "SELECT id FROM users ORDER BY name OFFSET 3 ROWS;",
+                vec!["3"],
+            ),
+            (
+                "SELECT id FROM users ORDER BY name FETCH FIRST 1 ROWS ONLY;",
+                vec!["4"],
+            ),
+            (
+                "SELECT id FROM users WHERE city = 'Seoul' OFFSET 1 FETCH NEXT 5 ROW ONLY;",
+                vec!["4"],
+            ),
+            ("SELECT id FROM users LIMIT 0;", vec![]),
+        ];
+        for (sql, expected) in test_cases {
+            let expected: Vec<Vec<Value>> = expected.into_iter().map(|id| vec![text(id)]).collect();
+            assert_eq!(rows(run(&mut executor, sql)), expected, "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_select_items() {
+        let mut executor = users();
+        assert_eq!(
+            run(
+                &mut executor,
+                "SELECT name AS n, city c, COUNT(*) AS count FROM users WHERE id = '1' \
+                 GROUP BY name, city;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["n".to_string(), "c".to_string(), "count".to_string()],
+                rows: vec![vec![text("kim"), text("Seoul"), Value::Int64(1)]],
+            }
+        );
+        let test_cases = vec![
+            (
+                "SELECT DISTINCT city FROM users ORDER BY city;",
+                vec![vec![text("Busan")], vec![text("Seoul")], vec![Value::Null]],
+            ),
+            (
+                "SELECT DISTINCT city FROM users LIMIT 2;",
+                vec![vec![text("Seoul")], vec![text("Busan")]],
+            ),
+            (
+                "SELECT DISTINCT ON (city) city, name FROM users ORDER BY city, id DESC;",
+                vec![
+                    vec![text("Busan"), text("lee")],
+                    vec![text("Seoul"), text("choi")],
+                    vec![Value::Null, text("park")],
+                ],
+            ),
+            (
+                "SELECT name, id FROM users ORDER BY 2 DESC;",
+                vec![
+                    vec![text("choi"), text("4")],
+                    vec![text("park"), text("3")],
+                    vec![text("lee"), text("2")],
+                    vec![text("kim"), text("1")],
+                ],
+            ),
+            (
+                "SELECT name AS city FROM users ORDER BY city;",
+                vec![
+                    vec![text("choi")],
+                    vec![text("kim")],
+                    vec![text("lee")],
+                    vec![text("park")],
+                ],
+            ),
+            (
+                "SELECT city, COUNT(*) AS total FROM users GROUP BY city ORDER BY total DESC, 1;",
+                vec![
+                    vec![text("Seoul"), Value::Int64(2)],
+                    vec![text("Busan"), Value::Int64(1)],
+                    vec![Value::Null, Value::Int64(1)],
+                ],
+            ),
+            (
+                "SELECT name FROM users UNION SELECT city FROM users ORDER BY 1 LIMIT 2;",
+                vec![vec![text("Busan")], vec![text("Seoul")]],
+            ),
+            (
+                "SELECT t.total * 2 FROM (SELECT COUNT(*) AS total FROM users) t;",
+                vec![vec![Value::Int64(8)]],
+            ),
+            (
+                "SELECT name FROM users u WHERE EXISTS (SELECT u.id AS city FROM users \
+                 WHERE city = 'Busan' ORDER BY city) ORDER BY id LIMIT 1;",
+                vec![vec![text("kim")]],
+            ),
+        ];
+        for (sql, expected) in test_cases {
+            assert_eq!(rows(run(&mut executor, sql)), expected, "{}", sql);
+        }
+
+        for sql in [
+            "SELECT DISTINCT city FROM users ORDER BY name;",
+            "SELECT DISTINCT ON (city) name FROM users ORDER BY id;",
+            "SELECT name FROM users ORDER BY 2;",
+            "SELECT name, city AS name FROM users ORDER BY name;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_group_by() {
+        let mut executor = users();
+        assert_eq!(
+            run(
+                &mut executor,
+                "SELECT city, COUNT(*), MAX(name) FROM users GROUP BY city ORDER BY city;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["city".to_string(), "count".to_string(), "max".to_string()],
+                rows: vec![
+                    vec![text("Busan"), Value::Int64(1), text("lee")],
+                    vec![text("Seoul"), Value::Int64(2), text("kim")],
+                    vec![Value::Null, Value::Int64(1), text("park")],
+                ],
+            }
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT city FROM users GROUP BY city HAVING COUNT(name) > 1;"
+            )),
+            vec![vec![text("Seoul")]]
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT COUNT(city), COUNT(DISTINCT city), MIN(id) FROM users;"
+            )),
+            vec![vec![Value::Int64(3), Value::Int64(2), text("1")]]
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT COUNT(*), SUM(1), AVG(2) FROM users WHERE id = 'none';"
+            )),
+            vec![vec![Value::Int64(0), Value::Null, Value::Null]]
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT SUM(1) * 10, AVG(2) FROM users GROUP BY city ORDER BY COUNT(*) DESC, city LIMIT 2;"
+            )),
+            vec![
+                vec![Value::Int64(20), Value::Decimal(Decimal::from(2))],
+                vec![Value::Int64(10), Value::Decimal(Decimal::from(2))],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_joins() {
+        let mut executor = users();
+        run(
+            &mut executor,
+            "CREATE TABLE orders (id, user_id, item);
+            INSERT INTO orders (id, user_id, item) VALUES ('10', '1', 'book');
+            INSERT INTO orders (id, user_id, item) VALUES ('11', '1', 'pen');
+            INSERT INTO orders (id, user_id, item) VALUES ('12', '2', 'cup');
+            INSERT INTO orders (id, user_id, item) VALUES ('13', '9', 'bag');",
+        );
+        let test_cases = vec![
+            (
+                "SELECT u.name, o.item FROM users u JOIN orders o ON u.id = o.user_id ORDER BY o.id;",
+                vec![
+                    vec![text("kim"), text("book")],
+                    vec![text("kim"), text("pen")],
+                    vec![text("lee"), text("cup")],
+                ],
+            ),
+            (
+                "SELECT name, item FROM users LEFT JOIN orders ON users.id = user_id \
+                 WHERE city IS NULL OR city = 'Busan' ORDER BY name;",
+                vec![
+                    vec![text("lee"), text("cup")],
+                    vec![text("park"), Value::Null],
+                ],
+            ),
+            (
+                "SELECT u.id, item FROM users AS u RIGHT OUTER JOIN orders ON u.id = user_id \
+                 WHERE u.id IS NULL;",
+                vec![vec![Value::Null, text("bag")]],
+            ),
+            (
+                "SELECT COUNT(*), COUNT(u.id), COUNT(o.id) FROM users u FULL JOIN orders o \
+                 ON u.id = o.user_id;",
+                vec![vec![
+                    Value::Int64(6),
+                    Value::Int64(5),
+                    Value::Int64(4),
+                ]],
+            ),
+            (
+                "SELECT COUNT(*) FROM users, orders WHERE users.id = orders.user_id;",
+                vec![vec![Value::Int64(3)]],
+            ),
+            (
+                "SELECT COUNT(*) FROM users CROSS JOIN orders;",
+                vec![vec![Value::Int64(16)]],
+            ),
+            (
+                "SELECT a.name, b.name FROM users a JOIN users b ON a.city = b.city \
+                 WHERE a.id < b.id;",
+                vec![vec![text("kim"), text("choi")]],
+            ),
+        ];
+        for (sql, expected) in test_cases {
+            assert_eq!(rows(run(&mut executor, sql)), expected, "{}", sql);
+        }
+
+        run(
+            &mut executor,
+            "CREATE TABLE cities (city, country);
+            INSERT INTO cities (city, country) VALUES ('Seoul', 'KR');
+            INSERT INTO cities (city, country) VALUES ('Tokyo', 'JP');",
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "SELECT * FROM users FULL JOIN cities USING (city) WHERE users.id IS NULL OR users.id = '1';"
+            ),
+            QueryResult::Rows {
+                columns: vec![
+                    "city".to_string(),
+                    "id".to_string(),
+                    "name".to_string(),
+                    "country".to_string()
+                ],
+                rows: vec![
+                    vec![text("Seoul"), text("1"), text("kim"), text("KR")],
+                    vec![text("Tokyo"), Value::Null, Value::Null, text("JP")],
+                ],
+            }
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT users.city, cities.* FROM users JOIN cities USING (city) WHERE id = '4';"
+            )),
+            vec![vec![text("Seoul"), text("Seoul"), text("KR")]]
+        );
+
+        for sql in [
+            "SELECT id FROM users JOIN orders ON users.id = orders.user_id;",
+            "SELECT x.id FROM users;",
+            "SELECT users.age FROM users;",
+            "SELECT * FROM users JOIN users ON 1 = 1;",
+            "SELECT * FROM users u JOIN orders o USING (age);",
+            "SELECT o.* FROM users;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_subqueries() {
+        let mut executor = users();
+        run(
+            &mut executor,
+            "CREATE TABLE orders (id, user_id, item);
+            INSERT INTO orders (id, user_id, item) VALUES ('10', '1', 'book');
+            INSERT INTO orders (id, user_id, item) VALUES ('11', '1', 'pen');
+            INSERT INTO orders (id, user_id, item) VALUES ('12', '2', 'cup');
+            INSERT INTO orders (id, user_id, item) VALUES ('13', '9', 'bag');",
+        );
+        let test_cases = vec![
+            (
+                "SELECT name FROM users WHERE id IN (SELECT user_id FROM orders) ORDER BY id;",
+                vec![vec![text("kim")], vec![text("lee")]],
+            ),
+            (
+                "SELECT name FROM users WHERE id NOT IN (SELECT user_id FROM orders) ORDER BY id;",
+                vec![vec![text("park")], vec![text("choi")]],
+            ),
+            (
+                "SELECT name FROM users WHERE name NOT IN (SELECT city FROM users);",
+                vec![],
+            ),
+            (
+                "SELECT name FROM users WHERE id = (SELECT user_id FROM orders WHERE item = 'cup');",
+                vec![vec![text("lee")]],
+            ),
+            (
+                "SELECT name FROM users WHERE id = (SELECT user_id FROM orders WHERE item = 'car');",
+                vec![],
+            ),
+            (
+                "SELECT name, (SELECT COUNT(*) FROM orders o WHERE o.user_id = u.id) FROM users u \
+                 ORDER BY id;",
+                vec![
+                    vec![text("kim"), Value::Int64(2)],
+                    vec![text("lee"), Value::Int64(1)],
+                    vec![text("park"), Value::Int64(0)],
+                    vec![text("choi"), Value::Int64(0)],
+                ],
+            ),
+            (
+                "SELECT name FROM users u WHERE EXISTS (SELECT * FROM orders WHERE user_id = u.id) \
+                 ORDER BY id;",
+                vec![vec![text("kim")], vec![text("lee")]],
+            ),
+            (
+                "SELECT name FROM users WHERE NOT EXISTS \
+                 (SELECT * FROM orders WHERE user_id = users.id AND item <> 'cup') ORDER BY id;",
+                vec![vec![text("lee")], vec![text("park")], vec![text("choi")]],
+            ),
+            (
+                "SELECT name FROM users u WHERE EXISTS (SELECT * FROM orders WHERE user_id < u.id) \
+                 ORDER BY id;",
+                vec![vec![text("lee")], vec![text("park")], vec![text("choi")]],
+            ),
+            (
+                "SELECT d.user_id, d.count FROM \
+                 (SELECT user_id, COUNT(*) FROM orders GROUP BY user_id) AS d WHERE d.count > 1;",
+                vec![vec![text("1"), Value::Int64(2)]],
+            ),
+            (
+                "SELECT name, c.item FROM users JOIN (SELECT user_id, item FROM orders) c \
+                 ON id = c.user_id WHERE c.item <> 'book';",
+                vec![
+                    vec![text("kim"), text("pen")],
+                    vec![text("lee"), text("cup")],
+                ],
+            ),
+        ];
+        for (sql, expected) in test_cases {
+            assert_eq!(rows(run(&mut executor, sql)), expected, "{}", sql);
+        }
+
+        assert_eq!(
+            run(
+                &mut executor,
+                "UPDATE users SET city = (SELECT item FROM orders WHERE user_id = users.id \
+                 AND id > '10') WHERE id IN (SELECT user_id FROM orders);"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "DELETE FROM users WHERE NOT EXISTS (SELECT * FROM orders WHERE user_id = users.id);"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT name, city FROM users ORDER BY id;"
+            )),
+            vec![
+                vec![text("kim"), text("pen")],
+                vec![text("lee"), text("cup")],
+            ]
+        );
+
+        for sql in [
+            "SELECT (SELECT id FROM orders) FROM users;",
+            "SELECT id FROM users WHERE id = (SELECT id, item FROM orders WHERE id = '10');",
+            "SELECT id FROM users WHERE id IN (SELECT id, item FROM orders);",
+            "SELECT id FROM (SELECT age FROM users) t;",
+            "SELECT id FROM users WHERE EXISTS (SELECT * FROM orders o WHERE o.user_id = x.id);",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_common_table_expressions() {
+        let mut executor = users();
+        run(
+            &mut executor,
+            "CREATE TABLE employees (id, name, manager_id);
+            INSERT INTO employees (id, name) VALUES ('1', 'ceo');
+            INSERT INTO employees (id, name, manager_id) VALUES ('2', 'cto', '1');
+            INSERT INTO employees (id, name, manager_id) VALUES ('3', 'dev', '2');
+            INSERT INTO employees (id, name, manager_id) VALUES ('4', 'ops', '2');
+            INSERT INTO employees (id, name, manager_id) VALUES ('5', 'cfo', '1');",
+        );
+        let number = |n: i64| Value::Int64(n);
+        let test_cases = vec![
+            (
+                "WITH seoul AS (SELECT id, name FROM users WHERE city = 'Seoul'), \
+                 others AS (SELECT name FROM seoul WHERE id <> '1') SELECT * FROM others;",
+                vec![vec![text("choi")]],
+            ),
+            (
+                "WITH users AS (SELECT name FROM users WHERE id = '2') SELECT * FROM users;",
+                vec![vec![text("lee")]],
+            ),
+            (
+                "WITH RECURSIVE chain AS (SELECT * FROM employees WHERE id = '3' \
+                 UNION ALL SELECT e.* FROM employees e JOIN chain c ON e.id = c.manager_id) \
+                 SELECT name FROM chain;",
+                vec![vec![text("dev")], vec![text("cto")], vec![text("ceo")]],
+            ),
+            (
+                "WITH RECURSIVE tree (id, depth) AS (SELECT id, 0 FROM employees \
+                 WHERE manager_id IS NULL UNION ALL SELECT e.id, t.depth + 1 FROM employees e \
+                 JOIN tree t ON e.manager_id = t.id) SELECT id, depth FROM tree ORDER BY id;",
+                vec![
+                    vec![text("1"), number(0)],
+                    vec![text("2"), number(1)],
+                    vec![text("3"), number(2)],
+                    vec![text("4"), number(2)],
+                    vec![text("5"), number(1)],
+                ],
+            ),
+            (
+                "WITH RECURSIVE t (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM t WHERE n < 5) \
+                 SELECT SUM(n), COUNT(*) FROM t;",
+                vec![vec![number(15), number(5)]],
+            ),
+            (
+                "WITH RECURSIVE t (n) AS (SELECT 1 UNION SELECT 3 - n FROM t) \
+                 SELECT n FROM t ORDER BY n;",
+                vec![vec![number(1)], vec![number(2)]],
+            ),
+            (
+                "SELECT name FROM users u WHERE (WITH same AS \
+                 (SELECT id FROM users WHERE city = u.city) \
+                 SELECT COUNT(*) FROM users WHERE id IN (SELECT id FROM same)) > 1;",
+                vec![vec![text("kim")], vec![text("choi")]],
+            ),
+        ];
+        for (sql, expected) in test_cases {
+            assert_eq!(rows(run(&mut executor, sql)), expected, "{}", sql);
+        }
+
+        assert_eq!(
+            run(
+                &mut executor,
+                "WITH seoul AS (SELECT id FROM users WHERE city = 'Seoul') \
+                 UPDATE users SET city = 'Incheon' WHERE id IN (SELECT id FROM seoul);"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "WITH incheon AS (SELECT id FROM users WHERE city = 'Incheon') \
+                 DELETE FROM users WHERE id NOT IN (SELECT id FROM incheon);"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+
+        for sql in [
+            "WITH t (a, b) AS (SELECT id FROM users) SELECT * FROM t;",
+            "WITH a AS (SELECT * FROM b), b AS (SELECT 1) SELECT * FROM a;",
+            "WITH t AS (SELECT id FROM users UNION SELECT id, name FROM users) SELECT * FROM t;",
+            "WITH RECURSIVE t (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM t) SELECT * FROM t;",
+            "SELECT * FROM (WITH t AS (SELECT 1) SELECT * FROM t) AS v, t;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let mut executor = users();
+        assert_eq!(
+            run(
+                &mut executor,
+                "SELECT city FROM users UNION SELECT 'Tokyo' ORDER BY city;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["city".to_string()],
+                rows: vec![
+                    vec![text("Busan")],
+                    vec![text("Seoul")],
+                    vec![text("Tokyo")],
+                    vec![Value::Null],
+                ],
+            }
+        );
+        let test_cases = vec![
+            (
+                "SELECT COUNT(*) FROM (SELECT city FROM users UNION ALL SELECT city FROM users) t;",
+                vec![vec![Value::Int64(8)]],
+            ),
+            (
+                "SELECT city FROM users WHERE id < '3' INTERSECT SELECT city FROM users \
+                 WHERE id > '1' ORDER BY city;",
+                vec![vec![text("Busan")], vec![text("Seoul")]],
+            ),
+            (
+                "SELECT city FROM users INTERSECT ALL SELECT 'Seoul';",
+                vec![vec![text("Seoul")]],
+            ),
+            (
+                "SELECT city FROM users EXCEPT SELECT 'Seoul';",
+                vec![vec![text("Busan")], vec![Value::Null]],
+            ),
+            (
+                "SELECT city FROM users EXCEPT ALL SELECT 'Seoul';",
+                vec![vec![text("Busan")], vec![Value::Null], vec![text("Seoul")]],
+            ),
+            (
+                "SELECT NULL UNION SELECT NULL UNION SELECT 1;",
+                vec![vec![Value::Null], vec![Value::Int64(1)]],
+            ),
+            (
+                "SELECT 'a' UNION SELECT 'b' INTERSECT SELECT 'c';",
+                vec![vec![text("a")]],
+            ),
+            (
+                "SELECT name FROM users UNION ALL SELECT name FROM users \
+                 ORDER BY name DESC LIMIT 3 OFFSET 1;",
+                vec![vec![text("park")], vec![text("lee")], vec![text("lee")]],
+            ),
+            (
+                "SELECT name FROM users WHERE city IN (SELECT 'Busan' UNION SELECT 'Seoul') \
+                 ORDER BY id;",
+                vec![vec![text("kim")], vec![text("lee")], vec![text("choi")]],
+            ),
+            (
+                "SELECT name FROM users u WHERE EXISTS (SELECT 1 WHERE u.city = 'Busan' \
+                 UNION SELECT 1 WHERE u.id = '3') ORDER BY id;",
+                vec![vec![text("lee")], vec![text("park")]],
+            ),
+        ];
+        for (sql, expected) in test_cases {
+            assert_eq!(rows(run(&mut executor, sql)), expected, "{}", sql);
+        }
+
+        for sql in [
+            "SELECT id, name FROM users UNION SELECT id FROM users;",
+            "SELECT id FROM users EXCEPT SELECT 1;",
+            "SELECT id FROM users UNION SELECT id FROM users ORDER BY name;",
+            "SELECT id FROM users UNION SELECT id FROM users LIMIT 'a';",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let mut executor = users();
+        assert_eq!(
+            run(
+                &mut executor,
+                "UPDATE users SET city = name WHERE city = 'Seoul';"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            run(&mut executor, "DELETE FROM users WHERE city <> 'Busan';"),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT id FROM users ORDER BY id;")),
+            vec![vec![text("2")], vec![text("3")]]
+        );
+        assert_eq!(
+            run(&mut executor, "DELETE FROM users;"),
+            QueryResult::RowsAffected(2)
+        );
+    }
+
+    #[test]
+    fn test_update_from() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE accounts (id BIGINT, owner TEXT, balance NUMERIC(8, 2), audited BOOLEAN);
+            CREATE TABLE transfers (account_id BIGINT, amount NUMERIC(8, 2));
+            INSERT INTO accounts VALUES (1, 'kim', 100, FALSE), (2, 'lee', 50, FALSE), (3, 'park', 0, FALSE);
+            INSERT INTO transfers VALUES (1, 30), (2, 70), (2, 5);",
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "UPDATE accounts SET balance = balance - 10, (owner, audited) = (tag, TRUE) \
+                 FROM (SELECT 'X' AS tag) u WHERE id = 3 OR balance > 80 RETURNING id, owner, balance;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["id".to_string(), "owner".to_string(), "balance".to_string()],
+                rows: vec![
+                    vec![Value::Int64(1), text("X"), Value::Decimal("90.00".parse().unwrap())],
+                    vec![Value::Int64(3), text("X"), Value::Decimal("-10.00".parse().unwrap())],
+                ],
+            }
+        );
+        // each account is updated once, by the first transfer it joins
+        assert_eq!(
+            run(
+                &mut executor,
+                "UPDATE accounts SET balance = balance + t.amount FROM transfers AS t \
+                 WHERE accounts.id = t.account_id RETURNING accounts.id, t.amount;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["id".to_string(), "amount".to_string()],
+                rows: vec![
+                    vec![Value::Int64(1), Value::Decimal("30.00".parse().unwrap())],
+                    vec![Value::Int64(2), Value::Decimal("70.00".parse().unwrap())],
+                ],
+            }
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT balance, audited FROM accounts ORDER BY id;"
+            )),
+            vec![
+                vec![
+                    Value::Decimal("120.00".parse().unwrap()),
+                    Value::Boolean(true)
+                ],
+                vec![
+                    Value::Decimal("120.00".parse().unwrap()),
+                    Value::Boolean(false)
+                ],
+                vec![
+                    Value::Decimal("-10.00".parse().unwrap()),
+                    Value::Boolean(true)
+                ],
+            ]
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "UPDATE accounts SET audited = FALSE FROM transfers WHERE amount > 100;"
+            ),
+            QueryResult::RowsAffected(0)
+        );
+
+        // an alias on the target qualifies its columns instead of its name
+        assert_eq!(
+            run(
+                &mut executor,
+                "UPDATE accounts AS a SET owner = 'choi' FROM transfers t \
+                 WHERE a.id = t.account_id AND t.amount = 30;"
+            ),
+            QueryResult::RowsAffected(1)
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT owner FROM accounts WHERE id = 1;"
+            )),
+            vec![vec![text("choi")]]
+        );
+
+        let prepared = executor
+            .prepare("UPDATE accounts SET owner = ? FROM transfers t WHERE t.amount = ?")
+            .unwrap();
+        assert_eq!(
+            prepared.parameter_types(),
+            [
+                Some(DataType::Text),
+                Some(DataType::Decimal {
+                    precision: Some(8),
+                    scale: Some(2)
+                })
+            ]
+        );
+        for sql in [
+            "UPDATE accounts SET balance = amount FROM transfers, transfers;",
+            "UPDATE accounts SET balance = 1 FROM missing;",
+            "UPDATE accounts SET missing = 1 FROM transfers;",
+            "UPDATE accounts a SET owner = 'x' WHERE accounts.id = 1;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_now() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE events (id BIGINT, created_at TIMESTAMP DEFAULT now(), updated_at TIMESTAMP);
+            INSERT INTO events (id) VALUES (1), (2);
+            UPDATE events SET updated_at = now();",
+        );
+        let rows = rows(run(
+            &mut executor,
+            "SELECT created_at, updated_at, created_at <= updated_at, updated_at <= CURRENT_TIMESTAMP \
+             FROM events;",
+        ));
+        assert_eq!(rows.len(), 2);
+        // one statement sees a single time, a later one a later time
+        assert_eq!(rows[0][1], rows[1][1]);
+        assert!(matches!(rows[0][0], Value::Timestamp(_)));
+        assert_eq!(rows[0][2..], [Value::Boolean(true), Value::Boolean(true)]);
+
+        let prepared = executor
+            .prepare("SELECT id FROM events WHERE ? < now()")
+            .unwrap();
+        assert_eq!(prepared.parameter_types(), [Some(DataType::Timestamp)]);
+        for sql in ["SELECT now(1);", "SELECT today();"] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_delete_using() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE sessions (id BIGINT, user_id BIGINT);
+            CREATE TABLE users (id BIGINT, active BOOLEAN);
+            INSERT INTO sessions VALUES (1, 1), (2, 2), (3, 2), (4, 3), (5, NULL);
+            INSERT INTO users VALUES (1, TRUE), (2, FALSE), (2, FALSE), (3, FALSE);",
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "DELETE FROM sessions USING users AS u \
+                 WHERE sessions.user_id = u.id AND NOT u.active AND sessions.id < 4 \
+                 RETURNING sessions.id, u.active;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["id".to_string(), "active".to_string()],
+                rows: vec![
+                    vec![Value::Int64(2), Value::Boolean(false)],
+                    vec![Value::Int64(3), Value::Boolean(false)],
+                ],
+            }
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "DELETE FROM sessions USING (SELECT MAX(id) AS id FROM users) AS newest \
+                 WHERE user_id = newest.id OR user_id IS NULL;"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT id FROM sessions;")),
+            vec![vec![Value::Int64(1)]]
+        );
+        // without matching rows to join nothing is deleted
+        run(&mut executor, "DELETE FROM users;");
+        assert_eq!(
+            run(&mut executor, "DELETE FROM sessions USING users;"),
+            QueryResult::RowsAffected(0)
+        );
+
+        let prepared = executor
+            .prepare("DELETE FROM sessions USING users WHERE users.active = ? AND sessions.id = ?")
+            .unwrap();
+        assert_eq!(
+            prepared.parameter_types(),
+            [Some(DataType::Boolean), Some(DataType::BigInt)]
+        );
+        run(&mut executor, "INSERT INTO users VALUES (1, TRUE);");
+        for sql in [
+            "DELETE FROM sessions USING users WHERE id = 1;",
+            "DELETE FROM sessions USING missing;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_create_and_drop() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE users (id BIGINT PRIMARY KEY, name TEXT);
+            CREATE TABLE orders (id BIGINT, user_id BIGINT REFERENCES users (id));
+            CREATE TABLE notes (order_id BIGINT, CONSTRAINT note_order FOREIGN KEY (order_id) REFERENCES orders (id));
+            INSERT INTO users VALUES (1, 'kim');",
+        );
+        // creating an existing table does nothing, leaving its rows alone
+        assert_eq!(
+            run(
+                &mut executor,
+                "CREATE TABLE IF NOT EXISTS users (other TEXT);"
+            ),
+            QueryResult::Done
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT * FROM users;")),
+            vec![vec![Value::Int64(1), text("kim")]]
+        );
+        assert_eq!(
+            executor
+                .access_manager
+                .table::<ExecutorError>("orders")
+                .unwrap()
+                .constraint("orders_user_id_fkey")
+                .map(|constraint| &constraint.kind),
+            Some(&TableConstraintKind::ForeignKey {
+                columns: vec!["user_id".to_string()],
+                references: ForeignKeyReference {
+                    table: "users".to_string(),
+                    columns: vec!["id".to_string()],
+                },
+            })
+        );
+
+        for sql in [
+            "CREATE TABLE users (id);",
+            "CREATE TABLE t (a UNIQUE, CONSTRAINT t_a_key CHECK (a > 0));",
+            "CREATE TABLE t (x BIGINT REFERENCES missing);",
+            "CREATE TABLE t (x BIGINT REFERENCES users (missing));",
+            "CREATE TABLE t (x BIGINT REFERENCES orders);",
+            "CREATE TABLE t (x BIGINT, y BIGINT, FOREIGN KEY (x, y) REFERENCES users);",
+            "CREATE TABLE t (x BIGINT CHECK (y > 0));",
+            "CREATE TABLE t (x BIGINT, CHECK (users.x > 0));",
+            "CREATE TABLE t (x INTEGER DEFAULT 'abc');",
+            "CREATE TABLE t (x INTEGER DEFAULT 1 + 'abc');",
+            "DROP TABLE users;",
+            "DROP TABLE users RESTRICT;",
+            "DROP TABLE IF EXISTS orders, missing;",
+            "DROP TABLE users, missing CASCADE;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        assert_eq!(executor.access_manager.tables().count(), 3);
+
+        // dropping every table that depends on another needs no CASCADE
+        run(&mut executor, "DROP TABLE notes, orders, notes;");
+        run(
+            &mut executor,
+            "CREATE TABLE orders (id BIGINT, user_id BIGINT REFERENCES users (id));
+            DROP TABLE IF EXISTS missing, users CASCADE;
+            DROP TABLE IF EXISTS users;",
+        );
+        let orders = executor
+            .access_manager
+            .table::<ExecutorError>("orders")
+            .unwrap();
+        assert!(orders.constraints.is_empty());
+        assert!(executor
+            .access_manager
+            .table::<ExecutorError>("users")
+            .is_err());
+    }
+
+    #[test]
+    fn test_alter_table() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE users (id BIGINT PRIMARY KEY, name TEXT);
+            CREATE TABLE orders (id BIGINT, user_id BIGINT REFERENCES users);
+            INSERT INTO users VALUES (1, 'kim'), (2, 'lee');
+            ALTER TABLE users ADD COLUMN points BIGINT NOT NULL DEFAULT 10;
+            INSERT INTO users (id, name) VALUES (3, 'park');",
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT id, points FROM users;")),
+            vec![
+                vec![Value::Int64(1), Value::Int64(10)],
+                vec![Value::Int64(2), Value::Int64(10)],
+                vec![Value::Int64(3), Value::Int64(10)],
+            ]
+        );
+        // the rows stored before the column was added are not rewritten
+        let users = executor
+            .access_manager
+            .table::<ExecutorError>("users")
+            .unwrap();
+        assert_eq!(users.rows[0].len(), 2);
+        assert_eq!(users.rows[2].len(), 3);
+
+        run(
+            &mut executor,
+            "ALTER TABLE users ALTER COLUMN points SET DEFAULT 0;
+            UPDATE users SET points = points + 5 WHERE id = 2;
+            INSERT INTO users (id, name) VALUES (4, 'choi');
+            ALTER TABLE users ADD COLUMN note TEXT;
+            ALTER TABLE users DROP COLUMN name;
+            ALTER TABLE users RENAME COLUMN points TO score;",
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT * FROM users;")),
+            vec![
+                vec![Value::Int64(1), Value::Int64(10), Value::Null],
+                vec![Value::Int64(2), Value::Int64(15), Value::Null],
+                vec![Value::Int64(3), Value::Int64(10), Value::Null],
+                vec![Value::Int64(4), Value::Int64(0), Value::Null],
+            ]
+        );
+
+        // NOT NULL is checked by INSERT and UPDATE, and against the stored rows
+        for sql in [
+            "INSERT INTO users (id, score) VALUES (5, NULL);",
+            "UPDATE users SET score = NULL;",
+            "INSERT INTO users (score) VALUES (1);",
+            "ALTER TABLE users ALTER note SET NOT NULL;",
+            "ALTER TABLE users ADD COLUMN code TEXT NOT NULL;",
+            "ALTER TABLE users ALTER id DROP NOT NULL;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        run(
+            &mut executor,
+            "ALTER TABLE users ALTER score DROP NOT NULL;
+            UPDATE users SET score = NULL WHERE id = 4;
+            UPDATE users SET note = 'x';
+            ALTER TABLE users ALTER note SET NOT NULL;",
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT score FROM users WHERE id = 4;")),
+            vec![vec![Value::Null]]
+        );
+
+        for sql in [
+            "ALTER TABLE users ADD COLUMN id BIGINT;",
+            "ALTER TABLE users DROP COLUMN missing;",
+            "ALTER TABLE users RENAME COLUMN score TO note;",
+            "ALTER TABLE users RENAME TO orders;",
+            "ALTER TABLE missing RENAME TO other;",
+            "ALTER TABLE users ADD PRIMARY KEY (score);",
+            "ALTER TABLE users ADD UNIQUE (missing);",
+            "ALTER TABLE users ADD CONSTRAINT users_pkey UNIQUE (score);",
+            "ALTER TABLE users DROP CONSTRAINT missing;",
+            "ALTER TABLE users DROP CONSTRAINT users_pkey;",
+            "ALTER TABLE users DROP COLUMN id;",
+            "ALTER TABLE users ALTER score SET DEFAULT 'abc';",
+            "ALTER TABLE users ADD CHECK (missing > 0);",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+
+        // renaming the table and the key carries over to the foreign key
+        run(
+            &mut executor,
+            "ALTER TABLE users RENAME TO members;
+            ALTER TABLE members RENAME id TO member_id;
+            ALTER TABLE members ADD CONSTRAINT positive CHECK (score > 0);
+            ALTER TABLE members RENAME score TO points;",
+        );
+        let members = executor
+            .access_manager
+            .table::<ExecutorError>("members")
+            .unwrap();
+        assert_eq!(members.primary_key(), Some(&["member_id".to_string()][..]));
+        assert_eq!(
+            members
+                .constraint("positive")
+                .map(|constraint| &constraint.kind),
+            Some(&TableConstraintKind::Check(Expression::BinaryOperation {
+                left: Box::new(Expression::Column("points".to_string())),
+                operator: BinaryOperator::GreaterThan,
+                right: Box::new(Expression::Literal(Value::Int64(0))),
+            }))
+        );
+        assert_eq!(
+            executor
+                .access_manager
+                .table::<ExecutorError>("orders")
+                .unwrap()
+                .constraint("orders_user_id_fkey")
+                .map(|constraint| &constraint.kind),
+            Some(&TableConstraintKind::ForeignKey {
+                columns: vec!["user_id".to_string()],
+                references: ForeignKeyReference {
+                    table: "members".to_string(),
+                    columns: Vec::new(),
+                },
+            })
+        );
+
+        // constraints are checked against the stored rows before being added
+        run(
+            &mut executor,
+            "CREATE TABLE items (id BIGINT, code TEXT, price BIGINT);
+            INSERT INTO items VALUES (1, 'a', 10), (1, 'b', 20), (2, 'b', NULL);",
+        );
+        for sql in [
+            "ALTER TABLE items ADD CONSTRAINT cheap CHECK (price < 15);",
+            "ALTER TABLE items ADD PRIMARY KEY (id);",
+            "ALTER TABLE items ADD UNIQUE (code);",
+            "ALTER TABLE items ADD FOREIGN KEY (price) REFERENCES members;",
+            "ALTER TABLE items ADD FOREIGN KEY (id) REFERENCES orders;",
+            "ALTER TABLE items ADD FOREIGN KEY (id) REFERENCES missing (id);",
+            "ALTER TABLE items ADD COLUMN sku BIGINT DEFAULT 1 UNIQUE;",
+            "ALTER TABLE items ADD COLUMN stock BIGINT DEFAULT 0 CHECK (stock > 0);",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        let items = executor
+            .access_manager
+            .table::<ExecutorError>("items")
+            .unwrap();
+        assert!(items.constraints.is_empty());
+        assert_eq!(items.columns.len(), 3);
+        assert_eq!(items.not_null, vec![false; 3]);
+        run(
+            &mut executor,
+            "ALTER TABLE items ADD CONSTRAINT positive CHECK (price > 0);
+            ALTER TABLE items ADD UNIQUE (id, code);
+            ALTER TABLE items ADD COLUMN stock BIGINT DEFAULT 1 CHECK (stock > 0);",
+        );
+        assert_eq!(
+            executor
+                .access_manager
+                .table::<ExecutorError>("items")
+                .unwrap()
+                .constraints
+                .len(),
+            3
+        );
+
+        // CASCADE drops the foreign key along with the key column
+        run(
+            &mut executor,
+            "ALTER TABLE members DROP COLUMN points;
+            ALTER TABLE members DROP COLUMN member_id CASCADE;",
+        );
+        let members = executor
+            .access_manager
+            .table::<ExecutorError>("members")
+            .unwrap();
+        assert_eq!(members.columns, vec!["note".to_string()]);
+        assert!(members.constraints.is_empty());
+        assert_eq!(members.rows.len(), 4);
+        assert!(executor
+            .access_manager
+            .table::<ExecutorError>("orders")
+            .unwrap()
+            .constraints
+            .is_empty());
+    }
+
+    #[test]
+    fn test_constraints() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE users (id BIGINT PRIMARY KEY, email TEXT UNIQUE, age BIGINT CHECK (age >= 0));
+            CREATE TABLE orders (id BIGINT, user_id BIGINT REFERENCES users (id));
+            CREATE TABLE staff (id BIGINT PRIMARY KEY, manager_id BIGINT REFERENCES staff);
+            INSERT INTO users VALUES (1, 'a@x', 20), (2, NULL, NULL), (3, NULL, 30);
+            INSERT INTO orders VALUES (1, 1), (2, NULL);
+            INSERT INTO staff VALUES (2, 1), (1, NULL);",
+        );
+        for sql in [
+            "INSERT INTO users VALUES (1, 'b@x', 1);",
+            "INSERT INTO users VALUES (4, 'c@x', 1), (4, 'd@x', 1);",
+            "INSERT INTO users VALUES (4, 'a@x', 1);",
+            "INSERT INTO users VALUES (4, 'b@x', -1);",
+            "INSERT INTO orders VALUES (3, 9);",
+            "INSERT INTO staff VALUES (3, 4);",
+            "UPDATE users SET id = 2 WHERE id = 3;",
+            "UPDATE users SET age = age - 25;",
+            "UPDATE users SET id = id + 10 WHERE id = 1;",
+            "UPDATE orders SET user_id = user_id + 10;",
+            "DELETE FROM users WHERE id = 1;",
+            "DELETE FROM staff WHERE id = 1;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        assert_eq!(
+            rows(run(&mut executor, "SELECT COUNT(*) FROM users;")),
+            vec![vec![Value::Int64(3)]]
+        );
+
+        // keys may move within one statement, and NULLs neither conflict
+        // nor reference anything
+        run(
+            &mut executor,
+            "UPDATE users SET id = 4 - id WHERE id <> 2;
+            INSERT INTO users VALUES (4, NULL, 5);
+            DELETE FROM staff;
+            DELETE FROM orders WHERE user_id = 3;
+            DELETE FROM users WHERE id = 3;",
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT id, age FROM users ORDER BY id;")),
+            vec![
+                vec![Value::Int64(1), Value::Int64(30)],
+                vec![Value::Int64(2), Value::Null],
+                vec![Value::Int64(4), Value::Int64(5)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE items (id BIGINT, name TEXT DEFAULT 'none', price NUMERIC(6, 2) DEFAULT 1 + 1, note);",
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "INSERT INTO items VALUES (1, 'pen', 1.5, TRUE), (2, DEFAULT, DEFAULT, NULL);"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+        run(&mut executor, "INSERT INTO items VALUES (1 + 2, 'cup');");
+        assert_eq!(
+            run(
+                &mut executor,
+                "INSERT INTO items (price, id) VALUES (-3, (SELECT MAX(id) + 1 FROM items));"
+            ),
+            QueryResult::RowsAffected(1)
+        );
+        let price = |value: &str| Value::Decimal(value.parse().unwrap());
+        assert_eq!(
+            rows(run(&mut executor, "SELECT * FROM items ORDER BY id;")),
+            vec![
+                vec![
+                    Value::Int64(1),
+                    text("pen"),
+                    price("1.50"),
+                    Value::Boolean(true)
+                ],
+                vec![Value::Int64(2), text("none"), price("2.00"), Value::Null],
+                vec![Value::Int64(3), text("cup"), price("2.00"), Value::Null],
+                vec![Value::Int64(4), text("none"), price("-3.00"), Value::Null],
+            ]
+        );
+
+        assert!(executor
+            .prepare("INSERT INTO items (name, id) VALUES (?, ?), ($1, ? * 2)")
+            .is_err());
+        let insert = executor
+            .prepare("INSERT INTO items (name, id) VALUES ($1, $2), ($1, $2 * 2)")
+            .unwrap();
+        assert_eq!(
+            insert.parameter_types(),
+            [Some(DataType::Text), Some(DataType::BigInt)]
+        );
+        assert_eq!(
+            executor
+                .execute_prepared(&insert, &[Value::Int64(7), text("5")])
+                .unwrap(),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT name, id FROM items WHERE id > 4;"
+            )),
+            vec![
+                vec![text("7"), Value::Int64(5)],
+                vec![text("7"), Value::Int64(10)]
+            ]
+        );
+
+        // a failing row inserts nothing
+        for sql in [
+            "INSERT INTO items VALUES (1, 'a', 1, 1, 1);",
+            "INSERT INTO items (id, size) VALUES (1, 2);",
+            "INSERT INTO items VALUES (1), ('x');",
+            "INSERT INTO items (id) VALUES ((SELECT id FROM items));",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        assert_eq!(rows(run(&mut executor, "SELECT id FROM items;")).len(), 6);
+    }
+
+    #[test]
+    fn test_insert_select_and_returning() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE events (id BIGINT, kind TEXT);
+            CREATE TABLE archive (id BIGINT, kind TEXT, note TEXT DEFAULT 'moved');
+            INSERT INTO events VALUES (1, 'click'), (2, 'view'), (3, 'click');",
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "INSERT INTO archive SELECT * FROM events WHERE kind = 'click';"
+            ),
+            QueryResult::RowsAffected(2)
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "INSERT INTO archive (kind, id) (SELECT kind, id * 10 FROM events WHERE id = 2) \
+                 RETURNING id, note, archive.id > 10 AS big;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["id".to_string(), "note".to_string(), "big".to_string()],
+                rows: vec![vec![Value::Int64(20), text("moved"), Value::Boolean(true)]],
+            }
+        );
+        // the query reads the table before any row is inserted
+        assert_eq!(
+            run(&mut executor, "INSERT INTO archive SELECT * FROM archive;"),
+            QueryResult::RowsAffected(3)
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "INSERT INTO events VALUES (4, 'view') RETURNING *;"
+            )),
+            vec![vec![Value::Int64(4), text("view")]]
+        );
+
+        assert_eq!(
+            run(
+                &mut executor,
+                "UPDATE events SET kind = 'tap' WHERE kind = 'click' \
+                 RETURNING id, kind, (SELECT COUNT(*) FROM archive WHERE archive.id = events.id) AS archived;"
+            ),
+            QueryResult::Rows {
+                columns: vec!["id".to_string(), "kind".to_string(), "archived".to_string()],
+                rows: vec![
+                    vec![Value::Int64(1), text("tap"), Value::Int64(2)],
+                    vec![Value::Int64(3), text("tap"), Value::Int64(2)],
+                ],
+            }
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "DELETE FROM events WHERE id > 2 RETURNING kind;"
+            )),
+            vec![vec![text("tap")], vec![text("view")]]
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "DELETE FROM events WHERE id > 2 RETURNING kind;"
+            )),
+            Vec::<Vec<Value>>::new()
+        );
+        assert_eq!(
+            rows(run(&mut executor, "SELECT * FROM events;")),
+            vec![
+                vec![Value::Int64(1), text("tap")],
+                vec![Value::Int64(2), text("view")]
+            ]
+        );
+
+        let prepared = executor
+            .prepare(
+                "INSERT INTO events (kind, id) SELECT ?, MAX(id) + ? FROM events RETURNING id = ?",
+            )
+            .unwrap();
+        assert_eq!(
+            prepared.parameter_types(),
+            [
+                Some(DataType::Text),
+                Some(DataType::BigInt),
+                Some(DataType::BigInt)
+            ]
+        );
+        assert_eq!(
+            rows(
+                executor
+                    .execute_prepared(&prepared, &[text("key"), text("5"), Value::Int64(7)])
+                    .unwrap()
+            ),
+            vec![vec![Value::Boolean(true)]]
+        );
+
+        for sql in [
+            "INSERT INTO events SELECT id, kind, kind FROM events;",
+            "INSERT INTO events (id, kind) SELECT id FROM events;",
+            "INSERT INTO events SELECT kind FROM events;",
+            "UPDATE events SET kind = 'x' RETURNING size;",
+            "DELETE FROM events RETURNING other.*;",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        assert_eq!(
+            rows(run(&mut executor, "SELECT kind FROM events;")),
+            vec![vec![text("tap")], vec![text("view")], vec![text("key")]]
+        );
+    }
+
+    #[test]
+    fn test_quoted_identifiers() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE \"Orders\" (\"order\", \"User Id\", Amount);
+            INSERT INTO \"Orders\" (\"order\", \"User Id\", amount) VALUES ('1', 'kim', '10');",
+        );
+        assert_eq!(
+            run(
+                &mut executor,
+                "SELECT \"User Id\", AMOUNT, `order` AS \"Order\" FROM \"Orders\" \"select\" \
+                 WHERE \"select\".\"order\" = '1';"
+            ),
+            QueryResult::Rows {
+                columns: vec![
+                    "User Id".to_string(),
+                    "amount".to_string(),
+                    "Order".to_string()
+                ],
+                rows: vec![vec![text("kim"), text("10"), text("1")]],
+            }
+        );
+        for sql in [
+            "SELECT * FROM orders;",
+            "SELECT \"Amount\" FROM \"Orders\";",
+            "SELECT user_id FROM \"Orders\";",
+        ] {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        assert!(parse_script("SELECT order FROM \"Orders\";").is_err());
+    }
+
+    #[test]
+    fn test_prepared_statements() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE items (id BIGINT, name TEXT, price NUMERIC(6, 2));
+            INSERT INTO items (id, name, price) VALUES ('1', 'pen', '1.50');
+            INSERT INTO items (id, name, price) VALUES ('2', 'ink', '12.00');
+            INSERT INTO items (id, name, price) VALUES ('3', 'pad', '3.25');",
+        );
+        let select = executor
+            .prepare(
+                "SELECT name FROM items WHERE id >= ? AND name LIKE ? AND price * ? > 1 \
+                 ORDER BY id LIMIT ?",
+            )
+            .unwrap();
+        let decimal = DataType::Decimal {
+            precision: Some(6),
+            scale: Some(2),
+        };
+        assert_eq!(select.parameter_count(), 4);
+        assert_eq!(
+            select.parameter_types(),
+            [
+                Some(DataType::BigInt),
+                Some(DataType::Text),
+                Some(decimal),
+                Some(DataType::BigInt)
+            ]
+        );
+        let mut names =
+            |parameters: &[Value]| rows(executor.execute_prepared(&select, parameters).unwrap());
+        assert_eq!(
+            names(&[
+                Value::Int64(2),
+                text("p%"),
+                Value::Int64(1),
+                Value::Int64(5)
+            ]),
+            vec![vec![text("pad")]]
+        );
+        // values are converted to the inferred types
+        assert_eq!(
+            names(&[text("1"), text("%"), text("0.8"), Value::Null]),
+            vec![vec![text("pen")], vec![text("ink")], vec![text("pad")]]
+        );
+        assert!(executor
+            .execute_prepared(&select, &[Value::Int64(1)])
+            .is_err());
+        assert!(executor
+            .execute_prepared(&select, &[text("x"), text("%"), text("1"), Value::Null])
+            .is_err());
+
+        let update = executor
+            .prepare("UPDATE items SET price = :price WHERE name = :name OR id = :id")
+            .unwrap();
+        assert_eq!(
+            update.parameter_types(),
+            [Some(decimal), Some(DataType::Text), Some(DataType::BigInt)]
+        );
+        assert_eq!(update.parameter_index("id"), Some(2));
+        assert_eq!(
+            executor
+                .execute_prepared(&update, &[text("2"), text("pen"), Value::Int64(3)])
+                .unwrap(),
+            QueryResult::RowsAffected(2)
+        );
+        let delete = executor
+            .prepare("DELETE FROM items WHERE price = $1")
+            .unwrap();
+        assert_eq!(
+            executor
+                .execute_prepared(&delete, &[Value::Int64(2)])
+                .unwrap(),
+            QueryResult::RowsAffected(2)
+        );
+
+        // a statement with parameters only runs prepared
+        let statement = parse_script("SELECT ?;").unwrap().remove(0).statement;
+        assert!(executor.execute(&statement).is_err());
+        let untyped = executor.prepare("SELECT ?, $1 FROM items;");
+        assert!(untyped.is_err());
+        let untyped = executor.prepare("SELECT $1 FROM items;").unwrap();
+        assert_eq!(untyped.parameter_types(), [None]);
+        assert_eq!(
+            rows(
+                executor
+                    .execute_prepared(&untyped, &[Value::Boolean(true)])
+                    .unwrap()
+            ),
+            vec![vec![Value::Boolean(true)]]
+        );
+    }
+
+    #[test]
+    fn test_column_types() {
+        let mut executor = QueryExecutor::new();
+        run(
+            &mut executor,
+            "CREATE TABLE events (id BIGINT, price NUMERIC(5, 2), day DATE, done BOOLEAN);
+            INSERT INTO events (id, price, day, done) VALUES ('9007199254740993', '1.005', '2024-02-29', 'yes');
+            INSERT INTO events (id, price, day) VALUES ('2', '20', '2024-03-01');",
+        );
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "SELECT id + 1, price * 2, day = '2024-03-01', done FROM events \
+                 WHERE day > '2024-02-01' ORDER BY id;"
+            )),
+            vec![
+                vec![
+                    Value::Int64(3),
+                    Value::Decimal(Decimal::from(40)),
+                    Value::Boolean(true),
+                    Value::Null
+                ],
+                vec![
+                    Value::Int64(9007199254740994),
+                    Value::Decimal("2.02".parse().unwrap()),
+                    Value::Boolean(false),
+                    Value::Boolean(true)
+                ],
+            ]
+        );
+        assert_eq!(
+            run(&mut executor, "UPDATE events SET price = price * 10;"),
+            QueryResult::RowsAffected(2)
+        );
+        let test_cases = vec![
+            "INSERT INTO events (id) VALUES ('one');",
+            "INSERT INTO events (day) VALUES ('2023-02-29');",
+            "UPDATE events SET price = price * 100;",
+            "SELECT * FROM events WHERE id = day;",
+            "SELECT day + 9223372036854775807 FROM events;",
+        ];
+        for sql in test_cases {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        // digits far past the scale of the column round away
+        assert_eq!(
+            rows(run(
+                &mut executor,
+                "INSERT INTO events (id, price) VALUES (3, 1e-50) RETURNING price;"
+            )),
+            vec![vec![Value::Decimal(Decimal::from(0))]]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut executor = users();
+        let test_cases = vec![
+            "SELECT age FROM users;",
+            "SELECT id FROM orders;",
+            "SELECT id FROM users LIMIT 'a';",
+            "SELECT SUM(name) FROM users;",
+            "UPDATE users SET id = -name;",
+            "DELETE FROM users WHERE id = 1;",
+            "CREATE TABLE users (id);",
+        ];
+        for sql in test_cases {
+            let statement = parse_script(sql).unwrap().remove(0).statement;
+            assert!(executor.execute(&statement).is_err(), "{}", sql);
+        }
+        // failed statements leave the table unchanged
+        assert_eq!(rows(run(&mut executor, "SELECT id FROM users;")).len(), 4);
+    }
+}
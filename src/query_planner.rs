@@ -1 +1,980 @@
+use std::cell::RefCell;
 
+use crate::{
+    access_manager::{AccessManager, Table},
+    ast::{
+        BinaryOperator, CommonTableExpression, DataType, Expression, InsertSource, InsertValue,
+        JoinConstraint, Query, SQLStatement, SelectItem, SelectStatement, TableReference,
+        UnaryOperator, WhereClause, With,
+    },
+    relation::{Relation, RelationColumn},
+    types::DBError,
+    value::Value,
+};
+
+/// How a subquery is evaluated for the rows of its enclosing query.
+#[derive(Debug, PartialEq)]
+pub enum SubqueryPlan {
+    /// References no column of an enclosing query, so a single execution
+    /// serves every outer row.
+    Uncorrelated,
+    /// Outer columns only appear in `inner = outer` conjuncts of WHERE.
+    /// `statement` is the subquery without them, executed once and split by
+    /// the values of `inner`, and an outer row takes the part matching its
+    /// values of `outer`.
+    Keyed {
+        statement: Box<SelectStatement>,
+        inner: Vec<Expression>,
+        outer: Vec<Expression>,
+    },
+    /// Executed again for every outer row.
+    Correlated,
+}
+
+/// Derives what is known about a query from the catalog, without reading rows.
+pub struct QueryPlanner<'a> {
+    access_manager: &'a AccessManager,
+    /// Names and columns of the WITH queries in scope, innermost last.
+    common_tables: RefCell<Vec<(String, Vec<String>)>>,
+}
+
+impl<'a> QueryPlanner<'a> {
+    pub fn new(
+        access_manager: &'a AccessManager,
+        common_tables: Vec<(String, Vec<String>)>,
+    ) -> Self {
+        QueryPlanner {
+            access_manager,
+            common_tables: RefCell::new(common_tables),
+        }
+    }
+
+    /// Columns of the relation the FROM clause produces.
+    pub fn source_columns<ERR: DBError>(
+        &self,
+        from: &[TableReference],
+    ) -> Result<Vec<RelationColumn>, ERR> {
+        let mut columns: Option<Vec<RelationColumn>> = None;
+        for reference in from {
+            let right = self.reference_columns(reference)?;
+            columns = Some(match columns {
+                Some(left) => Relation::join_columns(&left, &right, &JoinConstraint::None)?,
+                None => right,
+            });
+        }
+        Ok(columns.unwrap_or_default())
+    }
+
+    /// Names of the columns `query` returns, those of its first SELECT for
+    /// a set operation.
+    pub fn output_columns<ERR: DBError>(&self, query: &Query) -> Result<Vec<String>, ERR> {
+        self.with_common_tables(query.with(), || {
+            for common_table in query.with().iter().flat_map(|with| &with.queries) {
+                self.bind(common_table)?;
+            }
+            match query {
+                Query::Select(statement) => {
+                    let columns = self.source_columns(&statement.from)?;
+                    Ok(projection(&statement.columns, &columns)?.0)
+                }
+                Query::SetOperation(operation) => self.output_columns(&operation.left),
+            }
+        })
+    }
+
+    /// Set operations are only ever uncorrelated or correlated, while a
+    /// SELECT may also be keyed.
+    pub fn plan_subquery<ERR: DBError>(&self, subquery: &Query) -> Result<SubqueryPlan, ERR> {
+        if !self.references_outer(subquery, &[])? {
+            return Ok(SubqueryPlan::Uncorrelated);
+        }
+        let Query::Select(subquery) = subquery else {
+            return Ok(SubqueryPlan::Correlated);
+        };
+        self.with_common_tables(subquery.with.as_ref(), || {
+            for common_table in subquery.with.iter().flat_map(|with| &with.queries) {
+                self.bind(common_table)?;
+            }
+            self.plan_keyed(subquery)
+        })
+    }
+
+    fn plan_keyed<ERR: DBError>(&self, subquery: &SelectStatement) -> Result<SubqueryPlan, ERR> {
+        let Some(where_clause) = &subquery.where_clause else {
+            return Ok(SubqueryPlan::Correlated);
+        };
+        let columns = self.source_columns(&subquery.from)?;
+        let (mut inner, mut outer, mut remaining) = (Vec::new(), Vec::new(), Vec::new());
+        for conjunct in conjuncts(&where_clause.condition) {
+            if !self.expression_references_outer(conjunct, &columns, &[])? {
+                remaining.push(conjunct.clone());
+                continue;
+            }
+            let Expression::BinaryOperation {
+                left,
+                operator: BinaryOperator::Equal,
+                right,
+            } = conjunct
+            else {
+                return Ok(SubqueryPlan::Correlated);
+            };
+            if !self.expression_references_outer(left, &columns, &[])?
+                && outer_only(right, &columns)?
+            {
+                inner.push(left.as_ref().clone());
+                outer.push(right.as_ref().clone());
+            } else if !self.expression_references_outer(right, &columns, &[])?
+                && outer_only(left, &columns)?
+            {
+                inner.push(right.as_ref().clone());
+                outer.push(left.as_ref().clone());
+            } else {
+                return Ok(SubqueryPlan::Correlated);
+            }
+        }
+        let mut statement = Box::new(subquery.clone());
+        statement.where_clause = remaining
+            .into_iter()
+            .reduce(|left, right| Expression::binary(left, BinaryOperator::And, right))
+            .map(WhereClause::new);
+        if self.select_references_outer(&statement, &[])? {
+            return Ok(SubqueryPlan::Correlated);
+        }
+        Ok(SubqueryPlan::Keyed {
+            statement,
+            inner,
+            outer,
+        })
+    }
+
+    /// Runs `f`, then forgets the WITH queries it made visible.
+    fn with_common_tables<T, ERR: DBError>(
+        &self,
+        with: Option<&With>,
+        f: impl FnOnce() -> Result<T, ERR>,
+    ) -> Result<T, ERR> {
+        if with.is_none() {
+            return f();
+        }
+        let depth = self.common_tables.borrow().len();
+        let result = f();
+        self.common_tables.borrow_mut().truncate(depth);
+        result
+    }
+
+    /// Makes a WITH query visible to the rest of its statement.
+    fn bind<ERR: DBError>(&self, query: &CommonTableExpression) -> Result<(), ERR> {
+        let columns = match query.columns.is_empty() {
+            true => self.output_columns(&query.query)?,
+            false => query.columns.clone(),
+        };
+        self.common_tables
+            .borrow_mut()
+            .push((query.name.clone(), columns));
+        Ok(())
+    }
+
+    fn reference_columns<ERR: DBError>(
+        &self,
+        reference: &TableReference,
+    ) -> Result<Vec<RelationColumn>, ERR> {
+        match reference {
+            TableReference::Table { name, alias } => {
+                let qualifier = alias.as_deref().unwrap_or(name);
+                let common_tables = self.common_tables.borrow();
+                if let Some((_, columns)) = common_tables.iter().rev().find(|(n, _)| n == name) {
+                    return Ok(columns
+                        .iter()
+                        .map(|column| RelationColumn::new(qualifier, column))
+                        .collect());
+                }
+                let table = self.access_manager.table(name)?;
+                Ok(RelationColumn::of_table(table, qualifier))
+            }
+            TableReference::Derived { subquery, alias } => Ok(self
+                .output_columns(subquery)?
+                .iter()
+                .map(|name| RelationColumn::new(alias, name))
+                .collect()),
+            TableReference::Join {
+                left,
+                right,
+                constraint,
+                ..
+            } => Relation::join_columns(
+                &self.reference_columns(left)?,
+                &self.reference_columns(right)?,
+                constraint,
+            ),
+        }
+    }
+
+    /// Whether `query` references a column of a query enclosing it, where
+    /// `scopes` are the columns of the enclosing queries that are not outer to
+    /// the subquery being planned.
+    fn references_outer<ERR: DBError>(
+        &self,
+        query: &Query,
+        scopes: &[&[RelationColumn]],
+    ) -> Result<bool, ERR> {
+        let operation = match query {
+            Query::Select(statement) => return self.select_references_outer(statement, scopes),
+            Query::SetOperation(operation) => operation,
+        };
+        self.common_tables_reference_outer(operation.with.as_ref(), scopes, || {
+            if self.references_outer(&operation.left, scopes)?
+                || self.references_outer(&operation.right, scopes)?
+            {
+                return Ok(true);
+            }
+            let columns: Vec<RelationColumn> = self
+                .output_columns(&operation.left)?
+                .iter()
+                .map(|name| RelationColumn::unqualified(name))
+                .collect();
+            for expression in operation.expressions() {
+                if self.expression_references_outer(expression, &columns, scopes)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        })
+    }
+
+    fn select_references_outer<ERR: DBError>(
+        &self,
+        statement: &SelectStatement,
+        scopes: &[&[RelationColumn]],
+    ) -> Result<bool, ERR> {
+        self.common_tables_reference_outer(statement.with.as_ref(), scopes, || {
+            for reference in &statement.from {
+                if self.reference_references_outer(reference, scopes)? {
+                    return Ok(true);
+                }
+            }
+            let columns = self.source_columns(&statement.from)?;
+            for expression in statement.source_expressions() {
+                if self.expression_references_outer(expression, &columns, scopes)? {
+                    return Ok(true);
+                }
+            }
+            let (names, projection) = projection(&statement.columns, &columns)?;
+            for expression in statement.sort_expressions() {
+                if let SortKey::Expression(expression) = sort_key(expression, &names, &projection)?
+                {
+                    if self.expression_references_outer(expression, &columns, scopes)? {
+                        return Ok(true);
+                    }
+                }
+            }
+            Ok(false)
+        })
+    }
+
+    /// Whether the queries of `with` reference outer columns, or else `f`
+    /// does, which runs with the queries visible.
+    fn common_tables_reference_outer<ERR: DBError>(
+        &self,
+        with: Option<&With>,
+        scopes: &[&[RelationColumn]],
+        f: impl FnOnce() -> Result<bool, ERR>,
+    ) -> Result<bool, ERR> {
+        self.with_common_tables(with, || {
+            for query in with.iter().flat_map(|with| &with.queries) {
+                // a recursive query reads its own columns
+                let recursive = with.is_some_and(|with| query.is_recursive(with));
+                if recursive {
+                    self.bind(query)?;
+                }
+                if self.references_outer(&query.query, scopes)? {
+                    return Ok(true);
+                }
+                if !recursive {
+                    self.bind(query)?;
+                }
+            }
+            f()
+        })
+    }
+
+    fn reference_references_outer<ERR: DBError>(
+        &self,
+        reference: &TableReference,
+        scopes: &[&[RelationColumn]],
+    ) -> Result<bool, ERR> {
+        match reference {
+            TableReference::Table { .. } => Ok(false),
+            // a derived table cannot see the other entries of its FROM clause
+            TableReference::Derived { subquery, .. } => self.references_outer(subquery, scopes),
+            TableReference::Join {
+                left,
+                right,
+                constraint,
+                ..
+            } => {
+                if self.reference_references_outer(left, scopes)?
+                    || self.reference_references_outer(right, scopes)?
+                {
+                    return Ok(true);
+                }
+                let JoinConstraint::On(condition) = constraint else {
+                    return Ok(false);
+                };
+                let mut columns = self.reference_columns(left)?;
+                columns.extend(self.reference_columns(right)?);
+                self.expression_references_outer(condition, &columns, scopes)
+            }
+        }
+    }
+
+    fn expression_references_outer<ERR: DBError>(
+        &self,
+        expression: &Expression,
+        columns: &[RelationColumn],
+        scopes: &[&[RelationColumn]],
+    ) -> Result<bool, ERR> {
+        let nested = |subquery: &Query| {
+            let mut scopes = scopes.to_vec();
+            scopes.push(columns);
+            self.references_outer(subquery, &scopes)
+        };
+        let (table, name) = match expression {
+            Expression::Column(name) => (None, name),
+            Expression::QualifiedColumn { table, column } => (Some(table.as_str()), column),
+            Expression::Subquery(subquery) | Expression::Exists(subquery) => {
+                return nested(subquery)
+            }
+            Expression::InSubquery {
+                expression,
+                subquery,
+                ..
+            } => {
+                return Ok(
+                    self.expression_references_outer(expression, columns, scopes)?
+                        || nested(subquery)?,
+                )
+            }
+            _ => {
+                for child in expression.children() {
+                    if self.expression_references_outer(child, columns, scopes)? {
+                        return Ok(true);
+                    }
+                }
+                return Ok(false);
+            }
+        };
+        for columns in std::iter::once(columns).chain(scopes.iter().rev().copied()) {
+            if RelationColumn::find::<ERR>(columns, table, name)?.is_some() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Types of the `count` parameters of `statement`, taken from the columns
+    /// and values they are compared with or assigned to, `None` where nothing
+    /// tells the type.
+    pub fn parameter_types<ERR: DBError>(
+        &self,
+        statement: &SQLStatement,
+        count: usize,
+    ) -> Result<Vec<Option<DataType>>, ERR> {
+        let mut types = vec![None; count];
+        match statement {
+            SQLStatement::Select(query) => self.query_parameter_types(query, &[], &mut types)?,
+            SQLStatement::Update(statement) => {
+                let table = self.access_manager.table(&statement.table_name)?;
+                let mut columns = typed_columns(table, statement.qualifier());
+                for reference in &statement.from {
+                    columns.extend(self.reference_parameter_types(reference, &[], &mut types)?);
+                }
+                for set_clause in &statement.set_clauses {
+                    if let Expression::Parameter(index) = set_clause.value {
+                        let column = table.column_index(&set_clause.field);
+                        infer(
+                            &mut types,
+                            index,
+                            column.and_then(|index| table.types[index]),
+                        );
+                    }
+                }
+                let expressions = statement
+                    .set_clauses
+                    .iter()
+                    .map(|set_clause| &set_clause.value)
+                    .chain(
+                        statement
+                            .where_clause
+                            .iter()
+                            .map(|clause| &clause.condition),
+                    )
+                    .chain(item_expressions(&statement.returning));
+                for expression in expressions {
+                    self.expression_parameter_types(expression, &[&columns], &mut types)?;
+                }
+            }
+            SQLStatement::Delete(statement) => {
+                let table = self.access_manager.table(&statement.table_name)?;
+                let mut columns = typed_columns(table, &statement.table_name);
+                for reference in &statement.using {
+                    columns.extend(self.reference_parameter_types(reference, &[], &mut types)?);
+                }
+                let expressions = statement
+                    .where_clause
+                    .iter()
+                    .map(|clause| &clause.condition)
+                    .chain(item_expressions(&statement.returning));
+                for expression in expressions {
+                    self.expression_parameter_types(expression, &[&columns], &mut types)?;
+                }
+            }
+            SQLStatement::Insert(statement) => {
+                let table = self.access_manager.table(&statement.table_name)?;
+                let targets: Vec<Option<usize>> = if statement.columns.is_empty() {
+                    (0..table.columns.len()).map(Some).collect()
+                } else {
+                    statement
+                        .columns
+                        .iter()
+                        .map(|column| table.column_index(column))
+                        .collect()
+                };
+                let target_type =
+                    |target: &Option<usize>| target.and_then(|index| table.types[index]);
+                match &statement.source {
+                    InsertSource::Values(values) => {
+                        for tuple in values {
+                            for (target, value) in targets.iter().zip(tuple) {
+                                let InsertValue::Expression(expression) = value else {
+                                    continue;
+                                };
+                                if let Expression::Parameter(index) = expression {
+                                    infer(&mut types, *index, target_type(target));
+                                }
+                                self.expression_parameter_types(expression, &[], &mut types)?;
+                            }
+                        }
+                    }
+                    InsertSource::Query(query) => {
+                        // `INSERT ... SELECT ?` takes the type of the target column
+                        if let Query::Select(select) = query.as_ref() {
+                            for (target, item) in targets.iter().zip(&select.columns) {
+                                if let SelectItem::Expression {
+                                    expression: Expression::Parameter(index),
+                                    ..
+                                } = item
+                                {
+                                    infer(&mut types, *index, target_type(target));
+                                }
+                            }
+                        }
+                        self.query_parameter_types(query, &[], &mut types)?;
+                    }
+                }
+                let columns = typed_columns(table, &statement.table_name);
+                for expression in item_expressions(&statement.returning) {
+                    self.expression_parameter_types(expression, &[&columns], &mut types)?;
+                }
+            }
+            SQLStatement::CreateTable(_)
+            | SQLStatement::AlterTable(_)
+            | SQLStatement::DropTable(_) => {}
+        }
+        Ok(types)
+    }
+
+    fn query_parameter_types<ERR: DBError>(
+        &self,
+        query: &Query,
+        scopes: &[&[TypedColumn]],
+        types: &mut [Option<DataType>],
+    ) -> Result<(), ERR> {
+        self.with_common_tables(query.with(), || {
+            for common_table in query.with().iter().flat_map(|with| &with.queries) {
+                self.query_parameter_types(&common_table.query, scopes, types)?;
+                self.bind(common_table)?;
+            }
+            let (columns, expressions) = match query {
+                Query::Select(statement) => {
+                    let mut columns = Vec::new();
+                    for reference in &statement.from {
+                        columns.extend(self.reference_parameter_types(reference, scopes, types)?);
+                    }
+                    (columns, statement.expressions())
+                }
+                Query::SetOperation(operation) => {
+                    self.query_parameter_types(&operation.left, scopes, types)?;
+                    self.query_parameter_types(&operation.right, scopes, types)?;
+                    (Vec::new(), operation.expressions())
+                }
+            };
+            let scopes: Vec<&[TypedColumn]> =
+                scopes.iter().copied().chain([columns.as_slice()]).collect();
+            for expression in expressions {
+                self.expression_parameter_types(expression, &scopes, types)?;
+            }
+            let (limit, offset) = match query {
+                Query::Select(statement) => (&statement.limit, &statement.offset),
+                Query::SetOperation(operation) => (&operation.limit, &operation.offset),
+            };
+            for expression in limit.iter().chain(offset) {
+                if let Expression::Parameter(index) = expression {
+                    infer(types, *index, Some(DataType::BigInt));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Typed columns of a FROM item, inferring the parameter types of its
+    /// join conditions and derived tables on the way.
+    fn reference_parameter_types<ERR: DBError>(
+        &self,
+        reference: &TableReference,
+        scopes: &[&[TypedColumn]],
+        types: &mut [Option<DataType>],
+    ) -> Result<Vec<TypedColumn>, ERR> {
+        match reference {
+            TableReference::Table { name, alias } => {
+                let qualifier = alias.as_deref().unwrap_or(name);
+                let common_tables = self.common_tables.borrow();
+                if common_tables
+                    .iter()
+                    .any(|(common_table, _)| common_table == name)
+                {
+                    drop(common_tables);
+                    let columns = self.reference_columns(reference)?;
+                    return Ok(columns.into_iter().map(|column| (column, None)).collect());
+                }
+                Ok(typed_columns(self.access_manager.table(name)?, qualifier))
+            }
+            TableReference::Derived { subquery, .. } => {
+                self.query_parameter_types(subquery, scopes, types)?;
+                let columns = self.reference_columns(reference)?;
+                Ok(columns.into_iter().map(|column| (column, None)).collect())
+            }
+            TableReference::Join {
+                left,
+                right,
+                constraint,
+                ..
+            } => {
+                let mut columns = self.reference_parameter_types(left, scopes, types)?;
+                columns.extend(self.reference_parameter_types(right, scopes, types)?);
+                if let JoinConstraint::On(condition) = constraint {
+                    let scopes: Vec<&[TypedColumn]> =
+                        scopes.iter().copied().chain([columns.as_slice()]).collect();
+                    self.expression_parameter_types(condition, &scopes, types)?;
+                }
+                Ok(columns)
+            }
+        }
+    }
+
+    /// Infers the types of the parameters `expression` compares with, or
+    /// combines with, an operand of known type.
+    fn expression_parameter_types<ERR: DBError>(
+        &self,
+        expression: &Expression,
+        scopes: &[&[TypedColumn]],
+        types: &mut [Option<DataType>],
+    ) -> Result<(), ERR> {
+        let type_of = |expression: &Expression, types: &[Option<DataType>]| {
+            expression_type(expression, scopes, types)
+        };
+        let mut operands: Vec<&Expression> = Vec::new();
+        let mut known = None;
+        match expression {
+            Expression::BinaryOperation {
+                operator: BinaryOperator::And | BinaryOperator::Or,
+                left,
+                right,
+            } => {
+                operands.extend([left.as_ref(), right.as_ref()]);
+                known = Some(DataType::Boolean);
+            }
+            Expression::UnaryOperation {
+                operator: UnaryOperator::Not,
+                operand,
+            } => {
+                operands.push(operand);
+                known = Some(DataType::Boolean);
+            }
+            Expression::BinaryOperation { left, right, .. } => {
+                operands.extend([left.as_ref(), right.as_ref()])
+            }
+            Expression::InList {
+                expression, list, ..
+            } => {
+                operands.push(expression);
+                operands.extend(list);
+            }
+            Expression::Between {
+                expression,
+                low,
+                high,
+                ..
+            } => operands.extend([expression.as_ref(), low, high]),
+            Expression::Like {
+                expression,
+                pattern,
+                ..
+            } => {
+                operands.extend([expression.as_ref(), pattern]);
+                known = Some(DataType::Text);
+            }
+            _ => {}
+        }
+        if known.is_none() {
+            known = operands.iter().find_map(|operand| type_of(operand, types));
+        }
+        for operand in operands {
+            if let Expression::Parameter(index) = operand {
+                infer(types, *index, known);
+            }
+        }
+        for child in expression.children() {
+            self.expression_parameter_types(child, scopes, types)?;
+        }
+        for subquery in expression.subqueries() {
+            self.query_parameter_types(subquery, scopes, types)?;
+        }
+        Ok(())
+    }
+}
+
+/// A column of a relation with its declared type, if any.
+type TypedColumn = (RelationColumn, Option<DataType>);
+
+/// The expressions of a select list, leaving out wildcards.
+fn item_expressions(items: &[SelectItem]) -> impl Iterator<Item = &Expression> {
+    items.iter().filter_map(|item| match item {
+        SelectItem::Expression { expression, .. } => Some(expression),
+        _ => None,
+    })
+}
+
+fn typed_columns(table: &Table, qualifier: &str) -> Vec<TypedColumn> {
+    RelationColumn::of_table(table, qualifier)
+        .into_iter()
+        .zip(table.types.iter().copied())
+        .collect()
+}
+
+/// Sets the type of the parameter at `index` unless it is known already.
+fn infer(types: &mut [Option<DataType>], index: usize, data_type: Option<DataType>) {
+    if let Some(slot) = types.get_mut(index) {
+        *slot = slot.or(data_type);
+    }
+}
+
+/// Type of the values of `expression`, as far as it can be told without
+/// evaluating it.
+fn expression_type(
+    expression: &Expression,
+    scopes: &[&[TypedColumn]],
+    types: &[Option<DataType>],
+) -> Option<DataType> {
+    let column_type = |table: Option<&str>, name: &str| {
+        scopes.iter().rev().find_map(|columns| {
+            columns
+                .iter()
+                .find(|(column, _)| {
+                    column.name == name
+                        && table.is_none_or(|table| column.table.as_deref() == Some(table))
+                })
+                .map(|(_, data_type)| *data_type)
+        })?
+    };
+    match expression {
+        Expression::Column(name) => column_type(None, name),
+        Expression::QualifiedColumn { table, column } => column_type(Some(table), column),
+        Expression::Literal(value) => value.data_type(),
+        Expression::Parameter(index) => types.get(*index).copied().flatten(),
+        Expression::UnaryOperation {
+            operator: UnaryOperator::Not,
+            ..
+        } => Some(DataType::Boolean),
+        Expression::UnaryOperation { operand, .. } => expression_type(operand, scopes, types),
+        Expression::BinaryOperation {
+            left,
+            operator:
+                BinaryOperator::Plus
+                | BinaryOperator::Minus
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulo,
+            right,
+        } => expression_type(left, scopes, types).or(expression_type(right, scopes, types)),
+        Expression::Function { name, .. } if name.eq_ignore_ascii_case("COUNT") => {
+            Some(DataType::BigInt)
+        }
+        Expression::Function {
+            name, arguments, ..
+        } if name.eq_ignore_ascii_case("MIN") || name.eq_ignore_ascii_case("MAX") => {
+            expression_type(arguments.first()?, scopes, types)
+        }
+        Expression::Function {
+            name, arguments, ..
+        } if arguments.is_empty()
+            && (name.eq_ignore_ascii_case("NOW")
+                || name.eq_ignore_ascii_case("CURRENT_TIMESTAMP")) =>
+        {
+            Some(DataType::Timestamp)
+        }
+        Expression::Function { .. } | Expression::Subquery(_) => None,
+        _ => Some(DataType::Boolean),
+    }
+}
+
+/// Output column names and expressions of the select items, with `*` and
+/// `table.*` expanded against the columns of the FROM clause.
+pub fn projection<ERR: DBError>(
+    items: &[SelectItem],
+    columns: &[RelationColumn],
+) -> Result<(Vec<String>, Vec<Expression>), ERR> {
+    let mut names = Vec::new();
+    let mut expressions = Vec::new();
+    for item in items {
+        match item {
+            SelectItem::Expression { expression, alias } => {
+                names.push(match alias {
+                    Some(alias) => alias.clone(),
+                    None => column_name(expression),
+                });
+                expressions.push(expression.clone());
+            }
+            SelectItem::Wildcard => {
+                for column in columns.iter().filter(|column| !column.hidden) {
+                    names.push(column.name.clone());
+                    expressions.push(match &column.table {
+                        Some(table) => Expression::QualifiedColumn {
+                            table: table.clone(),
+                            column: column.name.clone(),
+                        },
+                        None => Expression::Column(column.name.clone()),
+                    });
+                }
+            }
+            SelectItem::QualifiedWildcard(table) => {
+                let count = names.len();
+                for column in columns {
+                    if column.table.as_ref() == Some(table) {
+                        names.push(column.name.clone());
+                        expressions.push(Expression::QualifiedColumn {
+                            table: table.clone(),
+                            column: column.name.clone(),
+                        });
+                    }
+                }
+                if names.len() == count {
+                    return Err(ERR::cause(&format!(
+                        "table {} is not in the FROM clause",
+                        table
+                    )));
+                }
+            }
+        }
+    }
+    Ok((names, expressions))
+}
+
+/// What an ORDER BY or DISTINCT ON expression is evaluated from.
+#[derive(Debug, PartialEq)]
+pub enum SortKey<'a> {
+    /// The output column at this index.
+    Output(usize),
+    /// An expression over the rows of the FROM clause.
+    Expression(&'a Expression),
+}
+
+/// Resolves an ORDER BY or DISTINCT ON expression against the output of a
+/// SELECT: a number is the position of an output column, a bare name the
+/// output column of that name, and an expression that is also projected
+/// reads the projected value.
+pub fn sort_key<'a, ERR: DBError>(
+    expression: &'a Expression,
+    names: &[String],
+    projection: &[Expression],
+) -> Result<SortKey<'a>, ERR> {
+    match expression {
+        Expression::Literal(Value::Int64(position)) => {
+            if *position < 1 || *position > names.len() as i64 {
+                return Err(ERR::cause(&format!(
+                    "position {} is not in select list",
+                    position
+                )));
+            }
+            return Ok(SortKey::Output(*position as usize - 1));
+        }
+        Expression::Column(name) => {
+            let mut indexes = (0..names.len()).filter(|&index| &names[index] == name);
+            if let Some(index) = indexes.next() {
+                if indexes.any(|other| projection[other] != projection[index]) {
+                    return Err(ERR::cause(&format!("column {} is ambiguous", name)));
+                }
+                return Ok(SortKey::Output(index));
+            }
+        }
+        _ => {}
+    }
+    Ok(
+        match projection.iter().position(|other| other == expression) {
+            Some(index) => SortKey::Output(index),
+            None => SortKey::Expression(expression),
+        },
+    )
+}
+
+/// Output column name of a projected expression.
+fn column_name(expression: &Expression) -> String {
+    match expression {
+        Expression::Column(name) | Expression::QualifiedColumn { column: name, .. } => name.clone(),
+        Expression::Function { name, .. } => name.to_lowercase(),
+        Expression::Exists(_) => "exists".to_string(),
+        _ => "?column?".to_string(),
+    }
+}
+
+/// The operands of the top level ANDs of `condition`.
+fn conjuncts(condition: &Expression) -> Vec<&Expression> {
+    match condition {
+        Expression::BinaryOperation {
+            left,
+            operator: BinaryOperator::And,
+            right,
+        } => {
+            let mut result = conjuncts(left);
+            result.extend(conjuncts(right));
+            result
+        }
+        condition => vec![condition],
+    }
+}
+
+/// Whether every column `expression` references is outside of `columns`, so
+/// it can be evaluated against the outer row alone.
+fn outer_only<ERR: DBError>(
+    expression: &Expression,
+    columns: &[RelationColumn],
+) -> Result<bool, ERR> {
+    match expression {
+        Expression::Column(name) => Ok(RelationColumn::find::<ERR>(columns, None, name)?.is_none()),
+        Expression::QualifiedColumn { table, column } => {
+            Ok(RelationColumn::find::<ERR>(columns, Some(table), column)?.is_none())
+        }
+        Expression::Subquery(_) | Expression::InSubquery { .. } | Expression::Exists(_) => {
+            Ok(false)
+        }
+        expression if expression.is_aggregate() => Ok(false),
+        expression => {
+            for child in expression.children() {
+                if !outer_only(child, columns)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        access_manager::{AccessManager, Table},
+        ast::{Expression, Query, SQLStatement},
+        parser::{parse_sql, ParserError},
+    };
+
+    use super::{QueryPlanner, SubqueryPlan};
+
+    fn catalog() -> AccessManager {
+        let mut access_manager = AccessManager::new();
+        for (name, columns) in [("users", ["id", "name"]), ("orders", ["id", "user_id"])] {
+            let columns = columns.iter().map(|column| column.to_string()).collect();
+            access_manager
+                .create_table::<ParserError>(Table::new(name.to_string(), columns))
+                .unwrap();
+        }
+        access_manager
+    }
+
+    /// Plan of the first subquery in the WHERE clause of `sql`.
+    fn plan(access_manager: &AccessManager, sql: &str) -> SubqueryPlan {
+        let Ok(SQLStatement::Select(Query::Select(statement))) = parse_sql(sql) else {
+            panic!("expected a select statement");
+        };
+        let condition = statement.where_clause.unwrap().condition;
+        let subquery: &Query = match &condition {
+            Expression::Exists(subquery) | Expression::Subquery(subquery) => subquery,
+            Expression::InSubquery { subquery, .. } => subquery,
+            Expression::BinaryOperation { right, .. } => match right.as_ref() {
+                Expression::Subquery(subquery) => subquery,
+                _ => panic!("expected a subquery"),
+            },
+            _ => panic!("expected a subquery"),
+        };
+        QueryPlanner::new(access_manager, Vec::new())
+            .plan_subquery::<ParserError>(subquery)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_plan_subquery() {
+        let access_manager = catalog();
+        let uncorrelated = [
+            "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders)",
+            "SELECT id FROM users u WHERE EXISTS (SELECT o.id FROM orders o WHERE o.user_id = o.id)",
+            "SELECT id FROM users WHERE EXISTS (SELECT id FROM orders WHERE id IN \
+             (SELECT user_id FROM orders o WHERE o.id = orders.id))",
+        ];
+        for sql in uncorrelated {
+            assert_eq!(
+                plan(&access_manager, sql),
+                SubqueryPlan::Uncorrelated,
+                "{}",
+                sql
+            );
+        }
+
+        let SubqueryPlan::Keyed {
+            statement,
+            inner,
+            outer,
+        } = plan(
+            &access_manager,
+            "SELECT id FROM users u WHERE 0 < (SELECT COUNT(*) FROM orders \
+             WHERE user_id = u.id AND id > 10)",
+        )
+        else {
+            panic!("expected a keyed plan");
+        };
+        assert_eq!(inner, vec![Expression::Column("user_id".to_string())]);
+        assert_eq!(
+            outer,
+            vec![Expression::QualifiedColumn {
+                table: "u".to_string(),
+                column: "id".to_string(),
+            }]
+        );
+        assert_eq!(
+            statement.where_clause.unwrap().condition,
+            crate::parser::parse_expression("id > 10").unwrap()
+        );
+
+        let correlated = [
+            "SELECT id FROM users u WHERE EXISTS (SELECT id FROM orders WHERE user_id > u.id)",
+            "SELECT id FROM users u WHERE EXISTS (SELECT id FROM orders WHERE user_id = u.id OR id = 1)",
+            "SELECT id FROM users u WHERE EXISTS (SELECT u.name FROM orders WHERE user_id = u.id)",
+            "SELECT id FROM users WHERE EXISTS (SELECT id FROM orders o WHERE o.user_id = users.id + o.id)",
+        ];
+        for sql in correlated {
+            assert_eq!(
+                plan(&access_manager, sql),
+                SubqueryPlan::Correlated,
+                "{}",
+                sql
+            );
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    access_manager::Table,
+    ast::{Expression, JoinConstraint, JoinOperator, SetOperator},
+    types::DBError,
+    value::Value,
+};
+
+/// A column of a relation, qualified by the name or alias of its table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelationColumn {
+    /// `None` for the columns a USING join merged from both sides.
+    pub table: Option<String>,
+    pub name: String,
+    /// Hidden columns are only found by qualified references, like the
+    /// original columns of a USING join.
+    pub hidden: bool,
+}
+
+impl RelationColumn {
+    pub fn new(table: &str, name: &str) -> Self {
+        RelationColumn {
+            table: Some(table.to_string()),
+            name: name.to_string(),
+            hidden: false,
+        }
+    }
+
+    /// A column without a table, like those a set operation returns.
+    pub fn unqualified(name: &str) -> Self {
+        RelationColumn {
+            table: None,
+            name: name.to_string(),
+            hidden: false,
+        }
+    }
+
+    pub fn of_table(table: &Table, qualifier: &str) -> Vec<RelationColumn> {
+        table
+            .columns
+            .iter()
+            .map(|name| RelationColumn::new(qualifier, name))
+            .collect()
+    }
+
+    /// Index of the column a possibly qualified column reference refers to,
+    /// `None` if there is no such column.
+    pub fn find<ERR: DBError>(
+        columns: &[RelationColumn],
+        table: Option<&str>,
+        name: &str,
+    ) -> Result<Option<usize>, ERR> {
+        let mut found = None;
+        for (index, column) in columns.iter().enumerate() {
+            let matches = column.name == name
+                && match table {
+                    Some(table) => column.table.as_deref() == Some(table),
+                    None => !column.hidden,
+                };
+            if matches && found.replace(index).is_some() {
+                return Err(ERR::cause(&format!(
+                    "column reference {} is ambiguous",
+                    name
+                )));
+            }
+        }
+        Ok(found)
+    }
+
+    /// Like `find`, but failing if there is no such column.
+    pub fn resolve<ERR: DBError>(
+        columns: &[RelationColumn],
+        table: Option<&str>,
+        name: &str,
+    ) -> Result<usize, ERR> {
+        Self::find(columns, table, name)?.ok_or_else(|| Self::not_found(columns, table, name))
+    }
+
+    pub fn not_found<ERR: DBError>(
+        columns: &[RelationColumn],
+        table: Option<&str>,
+        name: &str,
+    ) -> ERR {
+        match table {
+            Some(table) if !columns.iter().any(|c| c.table.as_deref() == Some(table)) => {
+                ERR::cause(&format!("table {} is not in the FROM clause", table))
+            }
+            Some(table) => ERR::cause(&format!("column {}.{} does not exist", table, name)),
+            None => ERR::cause(&format!("column {} does not exist", name)),
+        }
+    }
+}
+
+/// Rows with qualified columns, as read from a table or produced by a join.
+pub struct Relation {
+    pub columns: Vec<RelationColumn>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Relation {
+    pub fn scan(table: &Table, qualifier: &str) -> Self {
+        Relation {
+            columns: RelationColumn::of_table(table, qualifier),
+            rows: table.scan().map(Cow::into_owned).collect(),
+        }
+    }
+
+    /// Columns of the join of relations with columns `left` and `right`. A
+    /// USING join puts the merged columns first and hides the originals from
+    /// unqualified references.
+    pub fn join_columns<ERR: DBError>(
+        left: &[RelationColumn],
+        right: &[RelationColumn],
+        constraint: &JoinConstraint,
+    ) -> Result<Vec<RelationColumn>, ERR> {
+        let using = Self::using_columns(left, right, constraint)?;
+        let mut columns = Vec::with_capacity(using.len() + left.len() + right.len());
+        for (left_index, _) in &using {
+            columns.push(RelationColumn {
+                table: None,
+                name: left[*left_index].name.clone(),
+                hidden: false,
+            });
+        }
+        columns.extend(left.iter().cloned());
+        columns.extend(right.iter().cloned());
+        for (left_index, right_index) in using.iter().copied() {
+            let offset = using.len();
+            columns[offset + left_index].hidden = true;
+            columns[offset + left.len() + right_index].hidden = true;
+        }
+        Ok(columns)
+    }
+
+    /// Joins two relations with a nested loop, padding the unmatched rows of
+    /// the outer sides with NULLs. `on` decides whether a pair of rows joins
+    /// under an ON condition.
+    pub fn join<ERR, F>(
+        left: Relation,
+        operator: JoinOperator,
+        right: Relation,
+        constraint: &JoinConstraint,
+        mut on: F,
+    ) -> Result<Relation, ERR>
+    where
+        ERR: DBError,
+        F: FnMut(&Expression, &[RelationColumn], &[Value]) -> Result<bool, ERR>,
+    {
+        let using = Self::using_columns(&left.columns, &right.columns, constraint)?;
+        let columns = Self::join_columns(&left.columns, &right.columns, constraint)?;
+        let mut pair_columns = left.columns.clone();
+        pair_columns.extend(right.columns.iter().cloned());
+
+        let mut pairs = Vec::new();
+        let mut right_matched = vec![false; right.rows.len()];
+        for left_values in &left.rows {
+            let mut matched = false;
+            for (index, right_values) in right.rows.iter().enumerate() {
+                let values: Vec<Value> = left_values.iter().chain(right_values).cloned().collect();
+                let joined = match constraint {
+                    JoinConstraint::On(condition) => on(condition, &pair_columns, &values)?,
+                    JoinConstraint::Using(_) => {
+                        let mut equal = true;
+                        for (left_index, right_index) in &using {
+                            let ordering = left_values[*left_index]
+                                .compare::<ERR>(&right_values[*right_index])?;
+                            equal &= ordering == Some(Ordering::Equal);
+                        }
+                        equal
+                    }
+                    JoinConstraint::None => true,
+                };
+                if joined {
+                    matched = true;
+                    right_matched[index] = true;
+                    pairs.push(values);
+                }
+            }
+            if !matched && matches!(operator, JoinOperator::Left | JoinOperator::Full) {
+                let mut values = left_values.clone();
+                values.resize(pair_columns.len(), Value::Null);
+                pairs.push(values);
+            }
+        }
+        if matches!(operator, JoinOperator::Right | JoinOperator::Full) {
+            for (right_values, _) in right
+                .rows
+                .iter()
+                .zip(right_matched)
+                .filter(|(_, matched)| !matched)
+            {
+                let mut values = vec![Value::Null; left.columns.len()];
+                values.extend(right_values.iter().cloned());
+                pairs.push(values);
+            }
+        }
+
+        let width = left.columns.len();
+        let rows = pairs
+            .into_iter()
+            .map(|values| {
+                let mut row = Vec::with_capacity(columns.len());
+                for (left_index, right_index) in &using {
+                    // the side a row is missing from is NULL, so this takes the other
+                    row.push(match &values[*left_index] {
+                        Value::Null => values[width + right_index].clone(),
+                        value => value.clone(),
+                    });
+                }
+                row.extend(values);
+                row
+            })
+            .collect();
+        Ok(Relation { columns, rows })
+    }
+
+    /// Index pairs of the columns a USING join compares, after making sure
+    /// no table name is used on both sides.
+    fn using_columns<ERR: DBError>(
+        left: &[RelationColumn],
+        right: &[RelationColumn],
+        constraint: &JoinConstraint,
+    ) -> Result<Vec<(usize, usize)>, ERR> {
+        for column in right {
+            if let Some(table) = &column.table {
+                if left.iter().any(|c| c.table.as_ref() == Some(table)) {
+                    return Err(ERR::cause(&format!(
+                        "table name {} specified more than once",
+                        table
+                    )));
+                }
+            }
+        }
+        let mut using = Vec::new();
+        if let JoinConstraint::Using(names) = constraint {
+            for name in names {
+                using.push((
+                    RelationColumn::resolve::<ERR>(left, None, name)?,
+                    RelationColumn::resolve::<ERR>(right, None, name)?,
+                ));
+            }
+        }
+        Ok(using)
+    }
+}
+
+/// Values of the GROUP BY expressions of a row, under which NULLs are equal.
+pub struct GroupKey(pub Vec<Value>);
+impl Hash for GroupKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+impl Ord for GroupKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (left, right) in self.0.iter().zip(&other.0) {
+            let ordering = left.total_cmp(right);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        self.0.len().cmp(&other.0.len())
+    }
+}
+impl PartialOrd for GroupKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl PartialEq for GroupKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for GroupKey {}
+
+/// The rows not yet in `seen`, without duplicates, adding them to `seen`.
+pub fn distinct_rows(rows: Vec<Vec<Value>>, seen: &mut HashSet<GroupKey>) -> Vec<Vec<Value>> {
+    rows.into_iter()
+        .filter(|row| seen.insert(GroupKey(row.clone())))
+        .collect()
+}
+
+/// Combines the rows of the operands of a set operation. Without ALL the
+/// result has no duplicates, with ALL a row appearing m times on the left and
+/// n times on the right appears m + n times in a UNION, min(m, n) times in an
+/// INTERSECT and m - n times in an EXCEPT.
+pub fn combine(
+    operator: SetOperator,
+    all: bool,
+    left: Vec<Vec<Value>>,
+    right: Vec<Vec<Value>>,
+) -> Vec<Vec<Value>> {
+    if operator == SetOperator::Union {
+        let mut rows = left;
+        rows.extend(right);
+        return match all {
+            true => rows,
+            false => distinct_rows(rows, &mut HashSet::new()),
+        };
+    }
+    let mut counts: HashMap<GroupKey, usize> = HashMap::new();
+    for row in right {
+        *counts.entry(GroupKey(row)).or_default() += 1;
+    }
+    let left = match all {
+        true => left,
+        false => distinct_rows(left, &mut HashSet::new()),
+    };
+    left.into_iter()
+        .filter(|row| {
+            let in_right = match counts.get_mut(&GroupKey(row.clone())) {
+                Some(count) if *count > 0 => {
+                    // with ALL every row on the right cancels one on the left
+                    if all {
+                        *count -= 1;
+                    }
+                    true
+                }
+                _ => false,
+            };
+            in_right == (operator == SetOperator::Intersect)
+        })
+        .collect()
+}
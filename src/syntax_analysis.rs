@@ -1 +1,557 @@
+use crate::{
+    ast::{
+        AlterColumnAction, AlterOperation, AlterStatement, ColumnDefinition, ColumnOption,
+        CreateStatement, Expression, ForeignKeyReference, InsertSource, InsertStatement,
+        InsertValue, JoinConstraint, Query, SQLStatement, SelectItem, SelectStatement, SetOperator,
+        TableConstraint, TableConstraintKind, TableReference, With,
+    },
+    types::DBError,
+    value::Value,
+};
 
+/// Checks the rules of a parsed statement the grammar alone cannot express,
+/// like where aggregate functions may appear and which columns a grouped
+/// query may reference.
+pub struct SyntaxAnalyzer;
+impl SyntaxAnalyzer {
+    pub fn analyze<ERR: DBError>(statement: &SQLStatement) -> Result<(), ERR> {
+        match statement {
+            SQLStatement::CreateTable(statement) => Self::analyze_create(statement),
+            SQLStatement::AlterTable(statement) => Self::analyze_alter(statement),
+            SQLStatement::Select(query) => Self::analyze_query(query),
+            SQLStatement::Insert(statement) => Self::analyze_insert(statement),
+            SQLStatement::Update(statement) => {
+                Self::analyze_with(statement.with.as_ref())?;
+                for (index, set_clause) in statement.set_clauses.iter().enumerate() {
+                    if statement.set_clauses[..index]
+                        .iter()
+                        .any(|other| other.field == set_clause.field)
+                    {
+                        return Err(ERR::cause(&format!(
+                            "multiple assignments to same column {}",
+                            set_clause.field
+                        )));
+                    }
+                    Self::analyze_scalar(&set_clause.value, "UPDATE")?;
+                }
+                for reference in &statement.from {
+                    Self::analyze_table_reference(reference)?;
+                }
+                if let Some(where_clause) = &statement.where_clause {
+                    Self::analyze_scalar(&where_clause.condition, "WHERE")?;
+                }
+                Self::analyze_returning(&statement.returning)
+            }
+            SQLStatement::Delete(statement) => {
+                Self::analyze_with(statement.with.as_ref())?;
+                for reference in &statement.using {
+                    Self::analyze_table_reference(reference)?;
+                }
+                if let Some(where_clause) = &statement.where_clause {
+                    Self::analyze_scalar(&where_clause.condition, "WHERE")?;
+                }
+                Self::analyze_returning(&statement.returning)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn analyze_insert<ERR: DBError>(statement: &InsertStatement) -> Result<(), ERR> {
+        Self::analyze_with(statement.with.as_ref())?;
+        for (index, column) in statement.columns.iter().enumerate() {
+            if statement.columns[..index].contains(column) {
+                return Err(ERR::cause(&format!(
+                    "column {} specified more than once",
+                    column
+                )));
+            }
+        }
+        let values = match &statement.source {
+            InsertSource::Values(values) => values,
+            InsertSource::Query(query) => {
+                Self::analyze_query(query)?;
+                return Self::analyze_returning(&statement.returning);
+            }
+        };
+        let width = values.first().map_or(0, Vec::len);
+        for tuple in values {
+            if tuple.len() != width {
+                return Err(ERR::cause("VALUES lists must all be the same length"));
+            }
+            for value in tuple {
+                if let InsertValue::Expression(expression) = value {
+                    Self::analyze_scalar(expression, "VALUES")?;
+                    if Self::references_column(expression) {
+                        return Err(ERR::cause("cannot use column reference in VALUES"));
+                    }
+                }
+            }
+        }
+        // without a column list the executor checks the width against the table
+        if !statement.columns.is_empty() && width != statement.columns.len() {
+            return Err(ERR::cause(&format!(
+                "INSERT has {} target columns but {} expressions",
+                statement.columns.len(),
+                width
+            )));
+        }
+        Self::analyze_returning(&statement.returning)
+    }
+
+    fn analyze_returning<ERR: DBError>(items: &[SelectItem]) -> Result<(), ERR> {
+        for item in items {
+            if let SelectItem::Expression { expression, .. } = item {
+                Self::analyze_scalar(expression, "RETURNING")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn analyze_create<ERR: DBError>(statement: &CreateStatement) -> Result<(), ERR> {
+        let mut primary_keys = 0;
+        for (index, column) in statement.columns.iter().enumerate() {
+            if statement.columns[..index]
+                .iter()
+                .any(|other| other.name == column.name)
+            {
+                return Err(ERR::cause(&format!(
+                    "column {} specified more than once",
+                    column.name
+                )));
+            }
+            Self::analyze_column(column)?;
+            primary_keys += column
+                .constraints
+                .iter()
+                .filter(|constraint| constraint.option == ColumnOption::PrimaryKey)
+                .count();
+        }
+        for constraint in &statement.constraints {
+            Self::analyze_table_constraint(constraint)?;
+            let columns = match &constraint.kind {
+                TableConstraintKind::PrimaryKey(columns) => {
+                    primary_keys += 1;
+                    columns
+                }
+                TableConstraintKind::Unique(columns)
+                | TableConstraintKind::ForeignKey { columns, .. } => columns,
+                TableConstraintKind::Check(_) => continue,
+            };
+            for name in columns {
+                if !statement.columns.iter().any(|column| &column.name == name) {
+                    return Err(ERR::cause(&format!(
+                        "column {} named in key does not exist",
+                        name
+                    )));
+                }
+            }
+        }
+        if primary_keys > 1 {
+            return Err(ERR::cause(&format!(
+                "multiple primary keys for table {} are not allowed",
+                statement.table_name
+            )));
+        }
+        Ok(())
+    }
+
+    fn analyze_alter<ERR: DBError>(statement: &AlterStatement) -> Result<(), ERR> {
+        match &statement.operation {
+            AlterOperation::AddColumn(column) => Self::analyze_column(column),
+            AlterOperation::AddConstraint(constraint) => Self::analyze_table_constraint(constraint),
+            AlterOperation::AlterColumn {
+                action: AlterColumnAction::SetDefault(expression),
+                ..
+            } => Self::analyze_default(expression),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the constraints of a column definition.
+    fn analyze_column<ERR: DBError>(column: &ColumnDefinition) -> Result<(), ERR> {
+        let (mut null, mut not_null) = (false, false);
+        for constraint in &column.constraints {
+            match &constraint.option {
+                ColumnOption::Null => null = true,
+                ColumnOption::NotNull => not_null = true,
+                ColumnOption::Default(expression) => Self::analyze_default(expression)?,
+                ColumnOption::Check(expression) => {
+                    Self::analyze_constraint_expression(expression, "check constraints")?
+                }
+                ColumnOption::References(references) => Self::analyze_foreign_key(1, references)?,
+                ColumnOption::PrimaryKey | ColumnOption::Unique => {}
+            }
+        }
+        if null && not_null {
+            return Err(ERR::cause(&format!(
+                "conflicting NULL/NOT NULL declarations for column {}",
+                column.name
+            )));
+        }
+        Ok(())
+    }
+
+    fn analyze_default<ERR: DBError>(expression: &Expression) -> Result<(), ERR> {
+        Self::analyze_constraint_expression(expression, "DEFAULT expressions")?;
+        if Self::references_column(expression) {
+            return Err(ERR::cause(
+                "cannot use column reference in DEFAULT expression",
+            ));
+        }
+        Ok(())
+    }
+
+    fn analyze_table_constraint<ERR: DBError>(constraint: &TableConstraint) -> Result<(), ERR> {
+        match &constraint.kind {
+            TableConstraintKind::ForeignKey {
+                columns,
+                references,
+            } => Self::analyze_foreign_key(columns.len(), references),
+            TableConstraintKind::Check(expression) => {
+                Self::analyze_constraint_expression(expression, "check constraints")
+            }
+            TableConstraintKind::PrimaryKey(_) | TableConstraintKind::Unique(_) => Ok(()),
+        }
+    }
+
+    fn analyze_foreign_key<ERR: DBError>(
+        columns: usize,
+        references: &ForeignKeyReference,
+    ) -> Result<(), ERR> {
+        if !references.columns.is_empty() && references.columns.len() != columns {
+            return Err(ERR::cause(
+                "number of referencing and referenced columns for foreign key disagree",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks an expression of DEFAULT or CHECK, which is evaluated for a
+    /// single row without running queries.
+    fn analyze_constraint_expression<ERR: DBError>(
+        expression: &Expression,
+        clause: &str,
+    ) -> Result<(), ERR> {
+        if !expression.subqueries().is_empty() {
+            return Err(ERR::cause(&format!(
+                "subqueries are not allowed in {}",
+                clause
+            )));
+        }
+        if Self::references_parameter(expression) {
+            return Err(ERR::cause(&format!(
+                "parameters are not allowed in {}",
+                clause
+            )));
+        }
+        Self::analyze_scalar(expression, clause)
+    }
+
+    fn references_parameter(expression: &Expression) -> bool {
+        matches!(expression, Expression::Parameter(_))
+            || expression
+                .children()
+                .into_iter()
+                .any(Self::references_parameter)
+    }
+
+    fn references_column(expression: &Expression) -> bool {
+        matches!(
+            expression,
+            Expression::Column(_) | Expression::QualifiedColumn { .. }
+        ) || expression
+            .children()
+            .into_iter()
+            .any(Self::references_column)
+    }
+
+    fn analyze_query<ERR: DBError>(query: &Query) -> Result<(), ERR> {
+        match query {
+            Query::Select(statement) => Self::analyze_select(statement),
+            Query::SetOperation(operation) => {
+                Self::analyze_with(operation.with.as_ref())?;
+                Self::analyze_query(&operation.left)?;
+                Self::analyze_query(&operation.right)?;
+                for order_by in &operation.order_by {
+                    Self::analyze_scalar(&order_by.expression, "ORDER BY")?;
+                }
+                if let Some(limit) = &operation.limit {
+                    Self::analyze_scalar(limit, "LIMIT")?;
+                }
+                if let Some(offset) = &operation.offset {
+                    Self::analyze_scalar(offset, "OFFSET")?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn analyze_select<ERR: DBError>(statement: &SelectStatement) -> Result<(), ERR> {
+        Self::analyze_with(statement.with.as_ref())?;
+        for reference in &statement.from {
+            Self::analyze_table_reference(reference)?;
+        }
+        if let Some(where_clause) = &statement.where_clause {
+            Self::analyze_scalar(&where_clause.condition, "WHERE")?;
+        }
+        for expression in &statement.group_by {
+            Self::analyze_scalar(expression, "GROUP BY")?;
+        }
+        if let Some(limit) = &statement.limit {
+            Self::analyze_scalar(limit, "LIMIT")?;
+        }
+        if let Some(offset) = &statement.offset {
+            Self::analyze_scalar(offset, "OFFSET")?;
+        }
+
+        let mut expressions = Vec::new();
+        for item in &statement.columns {
+            if let SelectItem::Expression { expression, .. } = item {
+                expressions.push(expression);
+            }
+        }
+        expressions.extend(&statement.having);
+        // a position or alias is checked as the select item it names
+        expressions.extend(
+            statement
+                .sort_expressions()
+                .into_iter()
+                .filter(|expression| !Self::names_select_item(expression, &statement.columns)),
+        );
+        for expression in &expressions {
+            Self::analyze_function_calls(expression)?;
+        }
+
+        let grouped = !statement.group_by.is_empty()
+            || statement.having.is_some()
+            || expressions
+                .iter()
+                .any(|expression| expression.contains_aggregate());
+        if !grouped {
+            return Ok(());
+        }
+        for item in &statement.columns {
+            if !matches!(item, SelectItem::Expression { .. }) {
+                return Err(ERR::cause("`*` cannot be used in a grouped query"));
+            }
+        }
+        for expression in expressions {
+            Self::analyze_grouped(expression, &statement.group_by)?;
+        }
+        Ok(())
+    }
+
+    fn names_select_item(expression: &Expression, items: &[SelectItem]) -> bool {
+        match expression {
+            Expression::Literal(Value::Int64(_)) => true,
+            Expression::Column(name) => items.iter().any(|item| {
+                matches!(item, SelectItem::Expression { alias: Some(alias), .. } if alias == name)
+            }),
+            _ => false,
+        }
+    }
+
+    /// Checks the queries of a WITH clause, of which only the second half of
+    /// `query UNION [ALL] query` may read the query itself.
+    fn analyze_with<ERR: DBError>(with: Option<&With>) -> Result<(), ERR> {
+        let Some(with) = with else {
+            return Ok(());
+        };
+        for (index, query) in with.queries.iter().enumerate() {
+            if with.queries[..index]
+                .iter()
+                .any(|other| other.name == query.name)
+            {
+                return Err(ERR::cause(&format!(
+                    "WITH query name {} specified more than once",
+                    query.name
+                )));
+            }
+            Self::analyze_query(&query.query)?;
+            if !query.is_recursive(with) {
+                continue;
+            }
+            let Query::SetOperation(operation) = &query.query else {
+                return Err(Self::not_recursive_form(&query.name));
+            };
+            if operation.operator != SetOperator::Union {
+                return Err(Self::not_recursive_form(&query.name));
+            }
+            if operation.left.references_table(&query.name) {
+                return Err(ERR::cause(&format!(
+                    "recursive reference to query {} must not appear within its non-recursive term",
+                    query.name
+                )));
+            }
+            if !operation.order_by.is_empty()
+                || operation.limit.is_some()
+                || operation.offset.is_some()
+            {
+                return Err(ERR::cause(&format!(
+                    "ORDER BY, LIMIT and OFFSET are not allowed in recursive query {}",
+                    query.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn not_recursive_form<ERR: DBError>(name: &str) -> ERR {
+        ERR::cause(&format!(
+            "recursive query {} does not have the form non-recursive-term UNION [ALL] recursive-term",
+            name
+        ))
+    }
+
+    fn analyze_table_reference<ERR: DBError>(reference: &TableReference) -> Result<(), ERR> {
+        match reference {
+            TableReference::Table { .. } => Ok(()),
+            TableReference::Derived { subquery, .. } => Self::analyze_query(subquery),
+            TableReference::Join {
+                left,
+                right,
+                constraint,
+                ..
+            } => {
+                Self::analyze_table_reference(left)?;
+                Self::analyze_table_reference(right)?;
+                match constraint {
+                    JoinConstraint::On(condition) => {
+                        Self::analyze_scalar(condition, "JOIN conditions")
+                    }
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Checks an expression evaluated once per row, where no aggregate may appear.
+    fn analyze_scalar<ERR: DBError>(expression: &Expression, clause: &str) -> Result<(), ERR> {
+        if expression.contains_aggregate() {
+            return Err(ERR::cause(&format!(
+                "aggregate functions are not allowed in {}",
+                clause
+            )));
+        }
+        Self::analyze_function_calls(expression)
+    }
+
+    /// Checks the arguments of every aggregate call in `expression` and the
+    /// subqueries it contains.
+    fn analyze_function_calls<ERR: DBError>(expression: &Expression) -> Result<(), ERR> {
+        match expression {
+            Expression::Subquery(subquery)
+            | Expression::Exists(subquery)
+            | Expression::InSubquery { subquery, .. } => Self::analyze_query(subquery)?,
+            _ => {}
+        }
+        if let Expression::Function {
+            name,
+            arguments,
+            distinct,
+            wildcard,
+        } = expression
+        {
+            if expression.is_aggregate() {
+                if arguments.iter().any(Expression::contains_aggregate) {
+                    return Err(ERR::cause("aggregate function calls cannot be nested"));
+                }
+                if *wildcard && !name.eq_ignore_ascii_case("COUNT") {
+                    return Err(ERR::cause(&format!("{}(*) is not allowed", name)));
+                }
+                if !*wildcard && arguments.len() != 1 {
+                    return Err(ERR::cause(&format!("{} takes exactly one argument", name)));
+                }
+            } else if *distinct || *wildcard {
+                return Err(ERR::cause(&format!(
+                    "{} is not an aggregate function",
+                    name
+                )));
+            }
+        }
+        for child in expression.children() {
+            Self::analyze_function_calls(child)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every column `expression` references outside an aggregate
+    /// is part of one of the `group_by` expressions.
+    fn analyze_grouped<ERR: DBError>(
+        expression: &Expression,
+        group_by: &[Expression],
+    ) -> Result<(), ERR> {
+        if expression.is_aggregate() || group_by.contains(expression) {
+            return Ok(());
+        }
+        let column = match expression {
+            Expression::Column(name) => Some(name.clone()),
+            Expression::QualifiedColumn { table, column } => Some(format!("{}.{}", table, column)),
+            _ => None,
+        };
+        if let Some(column) = column {
+            return Err(ERR::cause(&format!(
+                "column {} must appear in the GROUP BY clause or be used in an aggregate function",
+                column
+            )));
+        }
+        for child in expression.children() {
+            Self::analyze_grouped(child, group_by)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse_sql;
+
+    #[test]
+    fn test_analyze() {
+        let valid = vec![
+            "SELECT COUNT(*) FROM t",
+            "SELECT city, COUNT(DISTINCT name), MAX(age) + 1 FROM t GROUP BY city",
+            "SELECT age / 10, SUM(age) FROM t GROUP BY age / 10",
+            "SELECT city FROM t GROUP BY city HAVING AVG(age) > 20 ORDER BY MIN(age)",
+            "SELECT city FROM t GROUP BY city, name",
+            "SELECT city, (SELECT MAX(age) FROM u) FROM t GROUP BY city",
+            "SELECT a FROM t WHERE EXISTS (SELECT COUNT(*) FROM u HAVING COUNT(*) > 1)",
+            "WITH a AS (SELECT 1), b AS (SELECT * FROM a) SELECT * FROM b",
+            "WITH RECURSIVE a (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM a) SELECT n FROM a",
+            "WITH a AS (SELECT * FROM a) UPDATE t SET x = (SELECT COUNT(*) FROM a)",
+            "SELECT city, COUNT(*) AS n FROM t GROUP BY city ORDER BY n DESC, 1",
+            "SELECT DISTINCT ON (city) city, MAX(age) FROM t GROUP BY city",
+        ];
+        for sql in valid {
+            assert!(parse_sql(sql).is_ok(), "{}", sql);
+        }
+        let invalid = vec![
+            "SELECT name, COUNT(*) FROM t",
+            "SELECT name FROM t GROUP BY city",
+            "SELECT age + 1 FROM t GROUP BY age / 10",
+            "SELECT city FROM t GROUP BY city ORDER BY name",
+            "SELECT * FROM t GROUP BY city",
+            "SELECT city FROM t WHERE COUNT(*) > 1 GROUP BY city",
+            "SELECT city FROM t GROUP BY COUNT(*)",
+            "SELECT SUM(MAX(age)) FROM t",
+            "SELECT SUM(*) FROM t",
+            "SELECT AVG(age, name) FROM t",
+            "DELETE FROM t WHERE MAX(age) > 1",
+            "SELECT a FROM t WHERE a IN (SELECT name FROM u GROUP BY city)",
+            "SELECT a FROM (SELECT name, COUNT(*) FROM u) AS v",
+            "SELECT a FROM t JOIN u ON COUNT(*) > 1",
+            "WITH a AS (SELECT 1), a AS (SELECT 2) SELECT * FROM a",
+            "WITH RECURSIVE a AS (SELECT n FROM a UNION SELECT 1) SELECT * FROM a",
+            "WITH a AS (SELECT COUNT(*) FROM t GROUP BY COUNT(*)) DELETE FROM t",
+            "WITH RECURSIVE a (n) AS (SELECT 1 INTERSECT SELECT n FROM a) SELECT n FROM a",
+            "WITH RECURSIVE a (n) AS (SELECT n FROM a) SELECT n FROM a",
+            "WITH RECURSIVE a (n) AS (SELECT 1 UNION SELECT n FROM a ORDER BY n) SELECT n FROM a",
+            "SELECT a FROM t UNION SELECT b FROM u ORDER BY COUNT(*)",
+            "SELECT DISTINCT ON (name) city FROM t GROUP BY city",
+            "SELECT city AS c FROM t GROUP BY city ORDER BY c, name",
+        ];
+        for sql in invalid {
+            assert!(parse_sql(sql).is_err(), "{}", sql);
+        }
+    }
+}
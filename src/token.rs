@@ -1,9 +1,14 @@
+use std::fmt;
+
+use crate::value::Decimal;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     // ddl
     Table,
     Create,
     Drop,
+    Alter,
 
     // dml
     Insert,
@@ -15,17 +20,91 @@ pub enum Token {
     Where,
     Set,
     Values,
+    Group,
+    Having,
+    Distinct,
+    Order,
+    By,
+    Asc,
+    Desc,
+    Limit,
+    Offset,
+    Fetch,
+    As,
+    Join,
+    Inner,
+    Left,
+    Right,
+    Full,
+    Outer,
+    Cross,
+    On,
+    Using,
+    With,
+    Union,
+    All,
+    Intersect,
+    Except,
+    Returning,
 
-    Equal,      // =
-    Semicolon,  // ;
-    Comma,      // ,
-    LeftParen,  // (
-    RightParen, // )
-    Asterisk,   // *
+    // logical
+    And,
+    Or,
+    Not,
+    In,
+    Between,
+    Like,
+    ILike,
+    Escape,
+    Is,
+    Null,
+    True,
+    False,
+    Exists,
 
+    Equal,              // =
+    NotEqual,           // <> or !=
+    LessThan,           // <
+    LessThanOrEqual,    // <=
+    GreaterThan,        // >
+    GreaterThanOrEqual, // >=
+    Plus,               // +
+    Minus,              // -
+    Slash,              // /
+    Percent,            // %
+    Semicolon,          // ;
+    Comma,              // ,
+    LeftParen,          // (
+    RightParen,         // )
+    Asterisk,           // *
+    Dot,                // .
+
+    /// An unquoted name, folded to lower case, or a non-reserved keyword.
     Identifier(String),
+    /// A name delimited by `"` or backticks, kept as written.
+    QuotedIdentifier(String),
     StringLiteral(String),
-    NumericLiteral(f64),
+    /// An integer literal that fits a bigint.
+    IntegerLiteral(i64),
+    /// A literal with a fraction or an exponent, or an integer too large for a bigint.
+    DecimalLiteral(Decimal),
+    /// A literal with too many digits or too large an exponent for a decimal.
+    FloatLiteral(f64),
+    /// `X'...'`, bytes given in hexadecimal.
+    BlobLiteral(Vec<u8>),
+
+    // parameter placeholders
+    /// `?`, numbered by its position among the other `?` of the statement.
+    QuestionMark,
+    /// `$n`, 1-based.
+    PositionalParameter(usize),
+    /// `:name`
+    NamedParameter(String),
+
+    // trivia, only kept when asked for
+    /// A `-- line` or `/* block */` comment, including its delimiters.
+    Comment(String),
+    Whitespace(String),
 
     //etc
     EOF,
@@ -36,4 +115,130 @@ impl Token {
     pub fn is_eof(&self) -> bool {
         matches!(self, Token::EOF)
     }
+
+    /// Whether the token is a comment or whitespace, which the parser never sees.
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Token::Comment(_) | Token::Whitespace(_))
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Table => write!(f, "TABLE"),
+            Token::Create => write!(f, "CREATE"),
+            Token::Drop => write!(f, "DROP"),
+            Token::Alter => write!(f, "ALTER"),
+            Token::Insert => write!(f, "INSERT"),
+            Token::Select => write!(f, "SELECT"),
+            Token::Update => write!(f, "UPDATE"),
+            Token::Delete => write!(f, "DELETE"),
+            Token::Into => write!(f, "INTO"),
+            Token::From => write!(f, "FROM"),
+            Token::Where => write!(f, "WHERE"),
+            Token::Set => write!(f, "SET"),
+            Token::Values => write!(f, "VALUES"),
+            Token::Group => write!(f, "GROUP"),
+            Token::Having => write!(f, "HAVING"),
+            Token::Distinct => write!(f, "DISTINCT"),
+            Token::Order => write!(f, "ORDER"),
+            Token::By => write!(f, "BY"),
+            Token::Asc => write!(f, "ASC"),
+            Token::Desc => write!(f, "DESC"),
+            Token::Limit => write!(f, "LIMIT"),
+            Token::Offset => write!(f, "OFFSET"),
+            Token::Fetch => write!(f, "FETCH"),
+            Token::As => write!(f, "AS"),
+            Token::Join => write!(f, "JOIN"),
+            Token::Inner => write!(f, "INNER"),
+            Token::Left => write!(f, "LEFT"),
+            Token::Right => write!(f, "RIGHT"),
+            Token::Full => write!(f, "FULL"),
+            Token::Outer => write!(f, "OUTER"),
+            Token::Cross => write!(f, "CROSS"),
+            Token::On => write!(f, "ON"),
+            Token::Using => write!(f, "USING"),
+            Token::With => write!(f, "WITH"),
+            Token::Union => write!(f, "UNION"),
+            Token::All => write!(f, "ALL"),
+            Token::Intersect => write!(f, "INTERSECT"),
+            Token::Except => write!(f, "EXCEPT"),
+            Token::Returning => write!(f, "RETURNING"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::In => write!(f, "IN"),
+            Token::Between => write!(f, "BETWEEN"),
+            Token::Like => write!(f, "LIKE"),
+            Token::ILike => write!(f, "ILIKE"),
+            Token::Escape => write!(f, "ESCAPE"),
+            Token::Is => write!(f, "IS"),
+            Token::Null => write!(f, "NULL"),
+            Token::True => write!(f, "TRUE"),
+            Token::False => write!(f, "FALSE"),
+            Token::Exists => write!(f, "EXISTS"),
+            Token::Equal => write!(f, "="),
+            Token::NotEqual => write!(f, "<>"),
+            Token::LessThan => write!(f, "<"),
+            Token::LessThanOrEqual => write!(f, "<="),
+            Token::GreaterThan => write!(f, ">"),
+            Token::GreaterThanOrEqual => write!(f, ">="),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Semicolon => write!(f, ";"),
+            Token::Comma => write!(f, ","),
+            Token::LeftParen => write!(f, "("),
+            Token::RightParen => write!(f, ")"),
+            Token::Asterisk => write!(f, "*"),
+            Token::Dot => write!(f, "."),
+            Token::Identifier(name) => write!(f, "{}", name),
+            Token::QuotedIdentifier(name) => write!(f, "\"{}\"", name.replace('"', "\"\"")),
+            Token::StringLiteral(value) => write!(f, "'{}'", value.replace('\'', "''")),
+            Token::IntegerLiteral(value) => write!(f, "{}", value),
+            Token::DecimalLiteral(value) => write!(f, "{}", value),
+            Token::FloatLiteral(value) => write!(f, "{:?}", value),
+            Token::BlobLiteral(bytes) => {
+                write!(f, "X'")?;
+                for byte in bytes {
+                    write!(f, "{:02X}", byte)?;
+                }
+                write!(f, "'")
+            }
+            Token::QuestionMark => write!(f, "?"),
+            Token::PositionalParameter(position) => write!(f, "${}", position),
+            Token::NamedParameter(name) => write!(f, ":{}", name),
+            Token::Comment(text) | Token::Whitespace(text) => write!(f, "{}", text),
+            Token::EOF => write!(f, "end of input"),
+            Token::Unknown(char) => write!(f, "{}", char),
+        }
+    }
+}
+
+/// Location of a token in the source text. `start` and `end` are byte
+/// offsets, `line` and `column` are 1-based and point at the first character,
+/// `end_line` and `end_column` at the character right behind the last one.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// Empty span right behind this one, used to report a missing token.
+    pub fn after(&self) -> Span {
+        Span {
+            start: self.end,
+            end: self.end,
+            line: self.end_line,
+            column: self.end_column,
+            end_line: self.end_line,
+            end_column: self.end_column,
+        }
+    }
 }
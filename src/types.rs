@@ -1,6 +1,9 @@
-pub struct TableValue<T>(String, T);
-pub struct TableName(String);
+use crate::token::Span;
+
+pub struct TableValue<T>(pub String, pub T);
+pub struct TableName(pub String);
 pub trait DBError {
     fn cause(msg: &str) -> Self;
     fn and_cause(self, msg: &str) -> Self;
+    fn at(self, span: Span) -> Self;
 }
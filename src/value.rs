@@ -0,0 +1,1042 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use crate::{ast::DataType, types::DBError};
+
+/// A value of a column, a literal or an evaluated expression.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Null,
+    Boolean(bool),
+    Int64(i64),
+    Float64(f64),
+    Decimal(Decimal),
+    Text(String),
+    Bytes(Vec<u8>),
+    /// Days since 1970-01-01.
+    Date(i32),
+    /// Microseconds since 1970-01-01 00:00:00.
+    Timestamp(i64),
+}
+
+impl Value {
+    /// The column type values like this one are stored as, `None` for NULL.
+    pub fn data_type(&self) -> Option<DataType> {
+        match self {
+            Value::Null => None,
+            Value::Boolean(_) => Some(DataType::Boolean),
+            Value::Int64(_) => Some(DataType::BigInt),
+            Value::Float64(_) => Some(DataType::Real),
+            Value::Decimal(_) => Some(DataType::Decimal {
+                precision: None,
+                scale: None,
+            }),
+            Value::Text(_) => Some(DataType::Text),
+            Value::Bytes(_) => Some(DataType::Blob),
+            Value::Date(_) => Some(DataType::Date),
+            Value::Timestamp(_) => Some(DataType::Timestamp),
+        }
+    }
+
+    /// Name of the type of this value, `None` for NULL, which has every type.
+    pub fn type_name(&self) -> Option<&'static str> {
+        match self {
+            Value::Null => None,
+            Value::Boolean(_) => Some("boolean"),
+            Value::Int64(_) => Some("bigint"),
+            Value::Float64(_) => Some("double precision"),
+            Value::Decimal(_) => Some("numeric"),
+            Value::Text(_) => Some("text"),
+            Value::Bytes(_) => Some("bytea"),
+            Value::Date(_) => Some("date"),
+            Value::Timestamp(_) => Some("timestamp"),
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Value::Int64(_) | Value::Float64(_) | Value::Decimal(_)
+        )
+    }
+
+    /// Whether values of the types of `self` and `other` can be compared,
+    /// as numbers of any type and dates with timestamps can.
+    pub fn same_kind(&self, other: &Value) -> bool {
+        self.kind() == other.kind()
+    }
+
+    /// Compares under SQL semantics, `None` if either value is NULL. Text
+    /// compared with a date or timestamp is read as one.
+    pub fn compare<ERR: DBError>(&self, other: &Value) -> Result<Option<Ordering>, ERR> {
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(None),
+            (Value::Date(_) | Value::Timestamp(_), Value::Text(text)) => {
+                let data_type = self.data_type().unwrap_or(DataType::Timestamp);
+                let other = Value::Text(text.clone()).cast(&data_type)?;
+                Ok(Some(self.total_cmp(&other)))
+            }
+            (Value::Text(text), Value::Date(_) | Value::Timestamp(_)) => {
+                let data_type = other.data_type().unwrap_or(DataType::Timestamp);
+                let value = Value::Text(text.clone()).cast(&data_type)?;
+                Ok(Some(value.total_cmp(other)))
+            }
+            _ if self.same_kind(other) => Ok(Some(self.total_cmp(other))),
+            _ => Err(ERR::cause(&format!(
+                "cannot compare {} with {}",
+                self.type_name().unwrap_or_default(),
+                other.type_name().unwrap_or_default()
+            ))),
+        }
+    }
+
+    /// Total order used for sort and group keys: values of different kinds
+    /// are ordered boolean, number, text, bytes, date, and NULL sorts after
+    /// every other value and equals NULL.
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Boolean(left), Value::Boolean(right)) => left.cmp(right),
+            (Value::Text(left), Value::Text(right)) => left.cmp(right),
+            (Value::Bytes(left), Value::Bytes(right)) => left.cmp(right),
+            (Value::Int64(left), Value::Int64(right)) => left.cmp(right),
+            (Value::Decimal(left), Value::Decimal(right)) => left.cmp(right),
+            (Value::Int64(left), Value::Decimal(right)) => Decimal::from(*left).cmp(right),
+            (Value::Decimal(left), Value::Int64(right)) => left.cmp(&Decimal::from(*right)),
+            // 0.0 and -0.0 are equal, as they are to the integer 0
+            (Value::Float64(left), Value::Float64(right)) if *left == 0.0 && *right == 0.0 => {
+                Ordering::Equal
+            }
+            (Value::Float64(left), Value::Float64(right)) => left.total_cmp(right),
+            (Value::Int64(left), Value::Float64(right)) => Decimal::from(*left).cmp_f64(*right),
+            (Value::Float64(left), Value::Int64(right)) => {
+                Decimal::from(*right).cmp_f64(*left).reverse()
+            }
+            (Value::Decimal(left), Value::Float64(right)) => left.cmp_f64(*right),
+            (Value::Float64(left), Value::Decimal(right)) => right.cmp_f64(*left).reverse(),
+            (Value::Date(_) | Value::Timestamp(_), Value::Date(_) | Value::Timestamp(_)) => {
+                self.timestamp().cmp(&other.timestamp())
+            }
+            _ => self.kind().cmp(&other.kind()),
+        }
+    }
+
+    /// Converts the value for a column of type `data_type`, following the
+    /// implicit conversions of an assignment.
+    pub fn cast<ERR: DBError>(self, data_type: &DataType) -> Result<Value, ERR> {
+        let invalid = |value: &Value| {
+            ERR::cause(&format!(
+                "invalid input syntax for type {}: \"{}\"",
+                data_type, value
+            ))
+        };
+        let value = match (data_type, self) {
+            (_, Value::Null) => Value::Null,
+            (DataType::Integer | DataType::BigInt, value) => {
+                let integer = match &value {
+                    Value::Int64(value) => Some(*value),
+                    Value::Float64(float) => {
+                        let rounded = float.round();
+                        if !(i64::MIN as f64..i64::MAX as f64).contains(&rounded) {
+                            return Err(ERR::cause(&format!("{} out of range", data_type)));
+                        }
+                        Some(rounded as i64)
+                    }
+                    Value::Decimal(decimal) => match i64::try_from(decimal.round(0).mantissa) {
+                        Ok(integer) => Some(integer),
+                        Err(_) => return Err(ERR::cause(&format!("{} out of range", data_type))),
+                    },
+                    Value::Text(text) => text.trim().parse().ok(),
+                    _ => return Err(Self::cannot_cast(&value, data_type)),
+                };
+                let integer = integer.ok_or_else(|| invalid(&value))?;
+                if *data_type == DataType::Integer && i32::try_from(integer).is_err() {
+                    return Err(ERR::cause("integer out of range"));
+                }
+                Value::Int64(integer)
+            }
+            (DataType::Real, value) => match &value {
+                Value::Text(text) => {
+                    Value::Float64(text.trim().parse().map_err(|_| invalid(&value))?)
+                }
+                value if value.is_numeric() => Value::Float64(value.to_f64()),
+                _ => return Err(Self::cannot_cast(&value, data_type)),
+            },
+            (DataType::Decimal { precision, scale }, value) => {
+                let decimal = match &value {
+                    Value::Int64(value) => Decimal::from(*value),
+                    Value::Decimal(value) => *value,
+                    Value::Float64(float) => {
+                        Decimal::from_f64(*float).ok_or_else(|| invalid(&value))?
+                    }
+                    Value::Text(text) => text.trim().parse().map_err(|_| invalid(&value))?,
+                    _ => return Err(Self::cannot_cast(&value, data_type)),
+                };
+                // DECIMAL(p) has no digits after the decimal point
+                let scale = scale.or(precision.map(|_| 0));
+                let decimal = match scale {
+                    Some(scale) => decimal.round(scale),
+                    None => decimal,
+                };
+                if let Some(precision) = precision {
+                    let digits = decimal.integer_digits() + scale.unwrap_or(0);
+                    if digits > *precision {
+                        return Err(ERR::cause("numeric field overflow"));
+                    }
+                }
+                Value::Decimal(decimal)
+            }
+            (DataType::Text, Value::Bytes(bytes)) | (DataType::Varchar(_), Value::Bytes(bytes)) => {
+                return Err(Self::cannot_cast(&Value::Bytes(bytes), data_type))
+            }
+            (DataType::Text, value) => Value::Text(value.to_string()),
+            (DataType::Varchar(length), value) => {
+                let text = value.to_string();
+                if let Some(length) = length {
+                    if text.chars().count() > *length as usize {
+                        return Err(ERR::cause(&format!(
+                            "value too long for type character varying({})",
+                            length
+                        )));
+                    }
+                }
+                Value::Text(text)
+            }
+            (DataType::Boolean, Value::Boolean(value)) => Value::Boolean(value),
+            (DataType::Boolean, Value::Text(text)) => match text.trim().to_lowercase().as_str() {
+                "true" | "t" | "yes" | "y" | "on" | "1" => Value::Boolean(true),
+                "false" | "f" | "no" | "n" | "off" | "0" => Value::Boolean(false),
+                _ => return Err(invalid(&Value::Text(text))),
+            },
+            (DataType::Blob, Value::Bytes(bytes)) => Value::Bytes(bytes),
+            (DataType::Blob, Value::Text(text)) => match text.strip_prefix("\\x") {
+                Some(hex) => {
+                    Value::Bytes(parse_hex(hex).ok_or_else(|| invalid(&Value::Text(text.clone())))?)
+                }
+                None => Value::Bytes(text.into_bytes()),
+            },
+            (DataType::Date, Value::Date(days)) => Value::Date(days),
+            (DataType::Date, Value::Timestamp(micros)) => {
+                Value::Date(micros.div_euclid(MICROS_PER_DAY) as i32)
+            }
+            (DataType::Date, Value::Text(text)) => Value::Date(
+                parse_date(text.trim()).ok_or_else(|| invalid(&Value::Text(text.clone())))?,
+            ),
+            (DataType::Timestamp, Value::Timestamp(micros)) => Value::Timestamp(micros),
+            (DataType::Timestamp, Value::Date(days)) => {
+                Value::Timestamp(days as i64 * MICROS_PER_DAY)
+            }
+            (DataType::Timestamp, Value::Text(text)) => Value::Timestamp(
+                parse_timestamp(text.trim()).ok_or_else(|| invalid(&Value::Text(text.clone())))?,
+            ),
+            (_, value) => return Err(Self::cannot_cast(&value, data_type)),
+        };
+        Ok(value)
+    }
+
+    fn cannot_cast<ERR: DBError>(value: &Value, data_type: &DataType) -> ERR {
+        ERR::cause(&format!(
+            "cannot convert {} to {}",
+            value.type_name().unwrap_or_default(),
+            data_type
+        ))
+    }
+
+    /// The value as a float, NaN for a value that is not a number.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Value::Int64(value) => *value as f64,
+            Value::Float64(value) => *value,
+            Value::Decimal(value) => value.to_f64(),
+            _ => f64::NAN,
+        }
+    }
+
+    /// Groups the types whose values compare with each other.
+    fn kind(&self) -> u8 {
+        match self {
+            Value::Boolean(_) => 0,
+            Value::Int64(_) | Value::Float64(_) | Value::Decimal(_) => 1,
+            Value::Text(_) => 2,
+            Value::Bytes(_) => 3,
+            Value::Date(_) | Value::Timestamp(_) => 4,
+            Value::Null => 5,
+        }
+    }
+
+    /// A date or timestamp as microseconds since the epoch.
+    fn timestamp(&self) -> i64 {
+        match self {
+            Value::Date(days) => *days as i64 * MICROS_PER_DAY,
+            Value::Timestamp(micros) => *micros,
+            _ => 0,
+        }
+    }
+}
+
+/// Consistent with `total_cmp`: numbers that compare equal hash the same
+/// whatever their type, and so do dates and timestamps.
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind().hash(state);
+        match self {
+            Value::Null => {}
+            Value::Boolean(value) => value.hash(state),
+            Value::Int64(_) | Value::Float64(_) | Value::Decimal(_) => {
+                let value = self.to_f64();
+                // 0.0 and -0.0 are equal, as are all NaNs
+                let value = if value == 0.0 { 0.0 } else { value };
+                let bits = if value.is_nan() {
+                    f64::NAN.to_bits()
+                } else {
+                    value.to_bits()
+                };
+                bits.hash(state)
+            }
+            Value::Text(value) => value.hash(state),
+            Value::Bytes(value) => value.hash(state),
+            Value::Date(_) | Value::Timestamp(_) => self.timestamp().hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Int64(value) => write!(f, "{}", value),
+            Value::Float64(value) => write!(f, "{}", value),
+            Value::Decimal(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", value),
+            Value::Bytes(bytes) => {
+                write!(f, "\\x")?;
+                for byte in bytes {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            Value::Date(days) => {
+                let (year, month, day) = civil_from_days(*days as i64);
+                write!(f, "{:04}-{:02}-{:02}", year, month, day)
+            }
+            Value::Timestamp(micros) => {
+                let (year, month, day) = civil_from_days(micros.div_euclid(MICROS_PER_DAY));
+                let time = micros.rem_euclid(MICROS_PER_DAY);
+                let seconds = time / 1_000_000;
+                write!(
+                    f,
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                    year,
+                    month,
+                    day,
+                    seconds / 3600,
+                    seconds / 60 % 60,
+                    seconds % 60
+                )?;
+                match time % 1_000_000 {
+                    0 => Ok(()),
+                    fraction => {
+                        let fraction = format!("{:06}", fraction);
+                        write!(f, ".{}", fraction.trim_end_matches('0'))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Significant digits a DECIMAL division keeps, at least as many after the
+/// decimal point.
+const DIVISION_SCALE: u32 = 16;
+
+/// Most digits a decimal has after the decimal point, as many as fit the
+/// mantissa.
+const MAX_SCALE: u32 = 38;
+
+/// An exact number `mantissa * 10^-scale`, kept without trailing zeros in
+/// the fraction so that equal numbers have equal representations.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mut mantissa: i128, mut scale: u32) -> Self {
+        if mantissa == 0 {
+            scale = 0;
+        }
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Decimal { mantissa, scale }
+    }
+
+    /// The shortest decimal that reads back as `value`, rounded to
+    /// `MAX_SCALE` digits after the decimal point. `None` for NaN and
+    /// infinities.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        value
+            .to_string()
+            .parse()
+            .or_else(|_| format!("{:.*}", MAX_SCALE as usize, value).parse())
+            .ok()
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    /// Number of digits before the decimal point.
+    pub fn integer_digits(&self) -> u32 {
+        // a divisor past i128 leaves no integer part
+        let integer = match 10i128.checked_pow(self.scale) {
+            Some(divisor) => (self.mantissa / divisor).unsigned_abs(),
+            None => 0,
+        };
+        match integer {
+            0 => 0,
+            integer => integer.ilog10() + 1,
+        }
+    }
+
+    /// Rounds half away from zero to `scale` digits after the decimal point.
+    pub fn round(&self, scale: u32) -> Self {
+        if scale >= self.scale {
+            return *self;
+        }
+        // a divisor past i128 is more than twice any mantissa
+        let Some(divisor) = 10i128.checked_pow(self.scale - scale) else {
+            return Decimal::new(0, scale);
+        };
+        let mut mantissa = self.mantissa / divisor;
+        if (self.mantissa % divisor).abs() * 2 >= divisor {
+            mantissa += self.mantissa.signum();
+        }
+        Decimal::new(mantissa, scale)
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Decimal::new(self.mantissa.checked_neg()?, self.scale))
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Option<Self> {
+        let (left, right, scale) = self.aligned(other)?;
+        Some(Decimal::new(left.checked_add(right)?, scale))
+    }
+
+    pub fn checked_sub(&self, other: &Decimal) -> Option<Self> {
+        let (left, right, scale) = self.aligned(other)?;
+        Some(Decimal::new(left.checked_sub(right)?, scale))
+    }
+
+    pub fn checked_mul(&self, other: &Decimal) -> Option<Self> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        let product = Decimal::new(mantissa, self.scale.checked_add(other.scale)?);
+        Some(product.round(MAX_SCALE))
+    }
+
+    /// Divides keeping `DIVISION_SCALE` significant digits of a quotient
+    /// below 1 and as many digits of the fraction of a larger one, or more
+    /// if an operand has more, up to `MAX_SCALE`. `None` on overflow or
+    /// division by zero.
+    pub fn checked_div(&self, other: &Decimal) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        // the quotient's leading digit is about this power of ten
+        let exponent = self.exponent() - other.exponent();
+        let scale = (DIVISION_SCALE as i64 - exponent.min(0)).min(MAX_SCALE as i64) as u32;
+        let scale = scale.max(self.scale).max(other.scale);
+        let numerator = self
+            .mantissa
+            .checked_mul(10i128.checked_pow(scale + other.scale - self.scale)?)?;
+        let mut quotient = numerator / other.mantissa;
+        if (numerator % other.mantissa).abs() * 2 >= other.mantissa.abs() {
+            quotient += numerator.signum() * other.mantissa.signum();
+        }
+        Some(Decimal::new(quotient, scale))
+    }
+
+    pub fn checked_rem(&self, other: &Decimal) -> Option<Self> {
+        let (left, right, scale) = self.aligned(other)?;
+        Some(Decimal::new(left.checked_rem(right)?, scale))
+    }
+
+    /// Power of ten of the leading digit, 0 for zero.
+    fn exponent(&self) -> i64 {
+        match self.mantissa.unsigned_abs() {
+            0 => 0,
+            mantissa => mantissa.ilog10() as i64 - self.scale as i64,
+        }
+    }
+
+    /// Compares with the exact value of `value`, a NaN sorting past the
+    /// infinities like in `f64::total_cmp`.
+    pub fn cmp_f64(&self, value: f64) -> Ordering {
+        if value.is_nan() {
+            return match value.is_sign_negative() {
+                true => Ordering::Greater,
+                false => Ordering::Less,
+            };
+        }
+        // any decimal is within i128, as is the integer part of a smaller
+        // float
+        let integer = value.trunc();
+        if integer >= 2f64.powi(127) {
+            return Ordering::Less;
+        }
+        if integer < -(2f64.powi(127)) {
+            return Ordering::Greater;
+        }
+        let (digits, exact) = fraction_digits(value.fract().abs());
+        let digits = if value < 0.0 { -digits } else { digits };
+        let (self_integer, fraction) = self.split();
+        match self_integer.cmp(&(integer as i128)) {
+            Ordering::Equal if exact => fraction.cmp(&digits),
+            // the float has more digits, so it is past `digits` away from 0
+            Ordering::Equal => match fraction.cmp(&digits) {
+                Ordering::Equal if value < 0.0 => Ordering::Greater,
+                Ordering::Equal => Ordering::Less,
+                ordering => ordering,
+            },
+            ordering => ordering,
+        }
+    }
+
+    /// The integer part of the number, and the fraction left as a multiple
+    /// of `10^-MAX_SCALE` with the same sign, which order decimals in turn.
+    fn split(&self) -> (i128, i128) {
+        let decimal = self.round(MAX_SCALE);
+        let divisor = 10i128.pow(decimal.scale);
+        (
+            decimal.mantissa / divisor,
+            decimal.mantissa % divisor * 10i128.pow(MAX_SCALE - decimal.scale),
+        )
+    }
+
+    /// Mantissas of both numbers at the larger of their scales.
+    fn aligned(&self, other: &Decimal) -> Option<(i128, i128, u32)> {
+        let scale = self.scale.max(other.scale);
+        let rescale = |decimal: &Decimal| {
+            decimal
+                .mantissa
+                .checked_mul(10i128.checked_pow(scale - decimal.scale)?)
+        };
+        Some((rescale(self)?, rescale(other)?, scale))
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Decimal::new(value as i128, 0)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.split().cmp(&other.split())
+    }
+}
+
+/// `fraction`, from 0 up to 1, as a multiple of `10^-MAX_SCALE` rounded
+/// down, and whether that is its exact value.
+fn fraction_digits(fraction: f64) -> (i128, bool) {
+    if fraction == 0.0 {
+        return (0, true);
+    }
+    // fraction = mantissa * 2^-shift, with an odd mantissa below 2^shift
+    let bits = fraction.to_bits();
+    let exponent = (bits >> 52) as i32 & 0x7ff;
+    let (mut mantissa, mut shift) = match exponent {
+        0 => (bits & ((1 << 52) - 1), 1074),
+        _ => ((bits & ((1 << 52) - 1)) | 1 << 52, 1075 - exponent as u32),
+    };
+    let zeros = mantissa.trailing_zeros();
+    mantissa >>= zeros;
+    shift -= zeros;
+    let mantissa = mantissa as u128;
+    if shift <= MAX_SCALE {
+        // mantissa * 10^MAX_SCALE / 2^shift is an integer below 10^MAX_SCALE
+        let digits = mantissa * 5u128.pow(shift) * 10u128.pow(MAX_SCALE - shift);
+        return (digits as i128, true);
+    }
+    // the last digit of the fraction is past MAX_SCALE, so dividing
+    // mantissa * 5^MAX_SCALE, which takes up to 142 bits, by
+    // 2^(shift - MAX_SCALE) leaves a remainder
+    let shift = shift - MAX_SCALE;
+    let factor = 5u128.pow(MAX_SCALE);
+    let low = (factor & u64::MAX as u128) * mantissa;
+    let high = (factor >> 64) * mantissa + (low >> 64);
+    let low = low & u64::MAX as u128;
+    let digits = match shift {
+        0..=63 => (high << (64 - shift)) | (low >> shift),
+        _ => high.checked_shr(shift - 64).unwrap_or(0),
+    };
+    (digits as i128, false)
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Reads `[+-]digits[.digits][e[+-]digits]`.
+impl FromStr for Decimal {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (number, exponent) = match text.find(['e', 'E']) {
+            Some(index) => (
+                &text[..index],
+                text[index + 1..].parse::<i32>().map_err(|_| ())?,
+            ),
+            None => (text, 0),
+        };
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, number.strip_prefix('+').unwrap_or(number)),
+        };
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let digits = || integer.chars().chain(fraction.chars());
+        if integer.len() + fraction.len() == 0 || !digits().all(|c| c.is_ascii_digit()) {
+            return Err(());
+        }
+        let mut mantissa: i128 = 0;
+        for digit in digits() {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|mantissa| mantissa.checked_add(digit as i128 - '0' as i128))
+                .ok_or(())?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        let scale = fraction.len() as i64 - exponent as i64;
+        if scale < 0 {
+            let factor = u32::try_from(-scale).map_err(|_| ())?;
+            let factor = 10i128.checked_pow(factor).ok_or(())?;
+            return Ok(Decimal::new(mantissa.checked_mul(factor).ok_or(())?, 0));
+        }
+        let decimal = Decimal::new(mantissa, u32::try_from(scale).map_err(|_| ())?);
+        match decimal.scale > MAX_SCALE {
+            true => Err(()),
+            false => Ok(decimal),
+        }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, integer, fraction)
+    }
+}
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Year, month and day of the date `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Reads `YYYY-MM-DD` as days since 1970-01-01.
+fn parse_date(text: &str) -> Option<i32> {
+    let mut parts = text.splitn(3, '-');
+    let year: i64 = parse_digits(parts.next()?, 4, 4)?;
+    let month: u32 = parse_digits(parts.next()?, 1, 2)?;
+    let day: u32 = parse_digits(parts.next()?, 1, 2)?;
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+    i32::try_from(days_from_civil(year, month, day)).ok()
+}
+
+/// Reads `YYYY-MM-DD[{ |T}HH:MM[:SS[.fraction]]]` as microseconds since
+/// 1970-01-01 00:00:00.
+fn parse_timestamp(text: &str) -> Option<i64> {
+    let (date, time) = match text.find([' ', 'T']) {
+        Some(index) => (&text[..index], Some(text[index + 1..].trim_start())),
+        None => (text, None),
+    };
+    let mut micros = parse_date(date)? as i64 * MICROS_PER_DAY;
+    let Some(time) = time else {
+        return Some(micros);
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut parts = time.splitn(3, ':');
+    let hour: i64 = parse_digits(parts.next()?, 1, 2)?;
+    let minute: i64 = parse_digits(parts.next()?, 2, 2)?;
+    let second: i64 = match parts.next() {
+        Some(second) => parse_digits(second, 2, 2)?,
+        None if fraction.is_empty() => 0,
+        None => return None,
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    micros += ((hour * 60 + minute) * 60 + second) * 1_000_000;
+    if !fraction.is_empty() {
+        if !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        // digits past microseconds are dropped
+        let digits = &fraction[..fraction.len().min(6)];
+        micros += digits.parse::<i64>().ok()? * 10i64.pow(6 - digits.len() as u32);
+    }
+    Some(micros)
+}
+
+/// Reads between `min` and `max` ASCII digits.
+fn parse_digits<T: FromStr>(text: &str, min: usize, max: usize) -> Option<T> {
+    if text.len() < min || text.len() > max || !text.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// Bytes of pairs of hexadecimal digits.
+pub fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering,
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use crate::{ast::DataType, parser::ParserError};
+
+    use super::{Decimal, Value};
+
+    fn decimal(text: &str) -> Decimal {
+        text.parse().unwrap()
+    }
+
+    fn hash(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_decimal() {
+        assert_eq!(decimal("12.50").to_string(), "12.5");
+        assert_eq!(decimal("-0.05").to_string(), "-0.05");
+        assert_eq!(decimal("1.5e3").to_string(), "1500");
+        assert_eq!(decimal("25e-3").to_string(), "0.025");
+        assert_eq!(decimal("+.5"), decimal("0.50"));
+        for text in ["", ".", "1.2.3", "1e", "abc", "--1"] {
+            assert!(text.parse::<Decimal>().is_err(), "{}", text);
+        }
+        assert_eq!(
+            decimal("0.1").checked_add(&decimal("0.2")),
+            Some(decimal("0.3"))
+        );
+        assert_eq!(
+            decimal("1.5").checked_mul(&decimal("-2.5")),
+            Some(decimal("-3.75"))
+        );
+        assert_eq!(
+            decimal("1").checked_div(&decimal("3")),
+            Some(decimal("0.3333333333333333"))
+        );
+        assert_eq!(
+            decimal("-2").checked_div(&decimal("3")),
+            Some(decimal("-0.6666666666666667"))
+        );
+        assert_eq!(
+            decimal("1e-30").checked_div(&decimal("3")),
+            Some(decimal("3.3333333e-31"))
+        );
+        assert_eq!(
+            decimal("2e-10").checked_div(&decimal("3")),
+            Some(decimal("6.666666666666667e-11"))
+        );
+        assert_eq!(
+            decimal("1").checked_div(&decimal("8e20")),
+            Some(decimal("1.25e-21"))
+        );
+        assert_eq!(decimal("1").checked_div(&decimal("0")), None);
+        assert_eq!(
+            decimal("7.5").checked_rem(&decimal("2")),
+            Some(decimal("1.5"))
+        );
+        assert_eq!(decimal("2.345").round(2), decimal("2.35"));
+        assert_eq!(decimal("-2.345").round(0), decimal("-2"));
+        assert_eq!(decimal("123.45").integer_digits(), 3);
+        assert_eq!(Decimal::new(1, 50).round(2), Decimal::from(0));
+        assert_eq!(Decimal::new(-9, 39).round(0), Decimal::from(0));
+        assert_eq!(Decimal::new(1, 50).integer_digits(), 0);
+        assert_eq!(decimal("1e38").round(2), decimal("1e38"));
+        assert_eq!(decimal("1e38").integer_digits(), 39);
+        assert!("1e-39".parse::<Decimal>().is_err());
+        assert!("1e-2000000000".parse::<Decimal>().is_err());
+        assert_eq!(decimal("0e-2000000000"), Decimal::from(0));
+        assert_eq!(
+            decimal("1.000e-38").to_string(),
+            format!("0.{}1", "0".repeat(37))
+        );
+        assert_eq!(
+            decimal("1e-20").checked_mul(&decimal("1e-20")),
+            Some(Decimal::from(0))
+        );
+        assert_eq!(Decimal::from_f64(1e-50), Some(Decimal::from(0)));
+        assert!(decimal("0.1") < decimal("0.12"));
+        assert_eq!(Decimal::from_f64(0.1), Some(decimal("0.1")));
+        assert_eq!(decimal("0.1").to_f64(), 0.1);
+    }
+
+    #[test]
+    fn test_compare() {
+        let compare = |left: &Value, right: &Value| left.compare::<ParserError>(right);
+        let one = Value::Int64(1);
+        assert_eq!(compare(&one, &Value::Null), Ok(None));
+        assert_eq!(compare(&Value::Null, &Value::Null), Ok(None));
+        assert_eq!(
+            compare(&one, &Value::Float64(1.0)),
+            Ok(Some(Ordering::Equal))
+        );
+        assert_eq!(
+            compare(&Value::Decimal(decimal("1.5")), &one),
+            Ok(Some(Ordering::Greater))
+        );
+        assert_eq!(
+            compare(&Value::Int64(i64::MAX), &Value::Int64(i64::MAX - 1)),
+            Ok(Some(Ordering::Greater))
+        );
+        assert_eq!(
+            compare(&Value::Date(1), &Value::Text("1970-01-02".to_string())),
+            Ok(Some(Ordering::Equal))
+        );
+        assert_eq!(
+            compare(&Value::Timestamp(1), &Value::Date(0)),
+            Ok(Some(Ordering::Greater))
+        );
+        assert!(compare(&one, &Value::Text("1".to_string())).is_err());
+        assert!(compare(&Value::Date(1), &Value::Text("yesterday".to_string())).is_err());
+
+        let mut values = vec![
+            Value::Null,
+            Value::Text("a".to_string()),
+            Value::Float64(2.5),
+            Value::Int64(2),
+            Value::Boolean(true),
+            Value::Decimal(decimal("-1")),
+        ];
+        values.sort_by(Value::total_cmp);
+        assert_eq!(
+            values,
+            vec![
+                Value::Boolean(true),
+                Value::Decimal(decimal("-1")),
+                Value::Int64(2),
+                Value::Float64(2.5),
+                Value::Text("a".to_string()),
+                Value::Null,
+            ]
+        );
+        assert_eq!(Value::Null.total_cmp(&Value::Null), Ordering::Equal);
+
+        // floats compare with integers and decimals by their exact value
+        let big = Value::Int64((1 << 53) + 1);
+        let float = Value::Float64((1u64 << 53) as f64);
+        assert_eq!(big.total_cmp(&float), Ordering::Greater);
+        assert_eq!(float.total_cmp(&big), Ordering::Less);
+        assert_eq!(float.total_cmp(&Value::Int64(1 << 53)), Ordering::Equal);
+        for (left, right, ordering) in [
+            ("0.1", 0.1, Ordering::Less),
+            ("0.5", 0.5, Ordering::Equal),
+            ("-2.75", -2.75, Ordering::Equal),
+            ("-0.1", -0.1, Ordering::Greater),
+            ("0.30000000000000004", 0.1 + 0.2, Ordering::Less),
+            ("1e-38", 1e-50, Ordering::Greater),
+            ("8.6736173798840354720e-19", 2f64.powi(-60), Ordering::Less),
+            (
+                "8.6736173798840354721e-19",
+                2f64.powi(-60),
+                Ordering::Greater,
+            ),
+            (
+                "-8.6736173798840354720e-19",
+                -(2f64.powi(-60)),
+                Ordering::Greater,
+            ),
+            ("-1.5", -1.5 - 2f64.powi(-52), Ordering::Greater),
+            ("0", -0.0, Ordering::Equal),
+            ("1e38", 1e39, Ordering::Less),
+            ("1e38", f64::NEG_INFINITY, Ordering::Greater),
+            ("1e38", f64::NAN, Ordering::Less),
+        ] {
+            let left = Value::Decimal(decimal(left));
+            let right = Value::Float64(right);
+            assert_eq!(left.total_cmp(&right), ordering, "{} {}", left, right);
+            assert_eq!(right.total_cmp(&left), ordering.reverse());
+        }
+        assert_eq!(
+            Value::Float64(0.0).total_cmp(&Value::Float64(-0.0)),
+            Ordering::Equal
+        );
+        assert_eq!(decimal("1e30").cmp(&Decimal::new(1, 38)), Ordering::Greater);
+
+        // equal keys hash the same across numeric types
+        assert_eq!(hash(&Value::Int64(3)), hash(&Value::Float64(3.0)));
+        assert_eq!(
+            hash(&Value::Int64(3)),
+            hash(&Value::Decimal(decimal("3.00")))
+        );
+        assert_eq!(hash(&Value::Float64(0.0)), hash(&Value::Float64(-0.0)));
+        assert_eq!(
+            hash(&Value::Date(1)),
+            hash(&Value::Timestamp(86_400_000_000))
+        );
+        assert_ne!(hash(&Value::Int64(3)), hash(&Value::Text("3".to_string())));
+    }
+
+    #[test]
+    fn test_cast() {
+        let cast = |value: Value, data_type: DataType| value.cast::<ParserError>(&data_type);
+        let text = |value: &str| Value::Text(value.to_string());
+        assert_eq!(cast(text(" 42 "), DataType::BigInt), Ok(Value::Int64(42)));
+        assert_eq!(
+            cast(Value::Float64(2.5), DataType::Integer),
+            Ok(Value::Int64(3))
+        );
+        assert!(cast(Value::Int64(1 << 40), DataType::Integer).is_err());
+        assert_eq!(
+            cast(
+                Value::Decimal(decimal("9007199254740993.4")),
+                DataType::BigInt
+            ),
+            Ok(Value::Int64(9007199254740993))
+        );
+        assert_eq!(
+            cast(Value::Decimal(decimal("-2.5")), DataType::BigInt),
+            Ok(Value::Int64(-3))
+        );
+        assert_eq!(
+            cast(
+                Value::Decimal(decimal("-9223372036854775808.4")),
+                DataType::BigInt
+            ),
+            Ok(Value::Int64(i64::MIN))
+        );
+        assert!(cast(
+            Value::Decimal(decimal("9223372036854775807.5")),
+            DataType::BigInt
+        )
+        .is_err());
+        assert!(cast(text("4x"), DataType::Integer).is_err());
+        assert!(cast(Value::Boolean(true), DataType::Integer).is_err());
+        assert_eq!(
+            cast(Value::Int64(2), DataType::Real),
+            Ok(Value::Float64(2.0))
+        );
+        let numeric = DataType::Decimal {
+            precision: Some(5),
+            scale: Some(2),
+        };
+        assert_eq!(
+            cast(text("123.456"), numeric),
+            Ok(Value::Decimal(decimal("123.46")))
+        );
+        assert!(cast(Value::Int64(1000), numeric).is_err());
+        assert_eq!(cast(Value::Int64(7), DataType::Text), Ok(text("7")));
+        assert!(cast(text("abcd"), DataType::Varchar(Some(3))).is_err());
+        assert_eq!(cast(text("t"), DataType::Boolean), Ok(Value::Boolean(true)));
+        assert_eq!(
+            cast(text("\\x00ff"), DataType::Blob),
+            Ok(Value::Bytes(vec![0, 255]))
+        );
+        assert_eq!(cast(Value::Null, DataType::Date), Ok(Value::Null));
+        for (input, output) in [
+            ("1970-01-01", "1970-01-01"),
+            ("2024-02-29", "2024-02-29"),
+            ("1969-12-31", "1969-12-31"),
+            ("0001-01-01", "0001-01-01"),
+        ] {
+            assert_eq!(
+                cast(text(input), DataType::Date).map(|value| value.to_string()),
+                Ok(output.to_string())
+            );
+        }
+        for input in ["2023-02-29", "2024-13-01", "2024-1-1x", "24-01-01"] {
+            assert!(cast(text(input), DataType::Date).is_err(), "{}", input);
+        }
+        for (input, output) in [
+            ("2024-03-01 12:34:56", "2024-03-01 12:34:56"),
+            ("2024-03-01T08:05", "2024-03-01 08:05:00"),
+            ("1969-12-31 23:59:59.5", "1969-12-31 23:59:59.5"),
+            ("2024-03-01", "2024-03-01 00:00:00"),
+        ] {
+            assert_eq!(
+                cast(text(input), DataType::Timestamp).map(|value| value.to_string()),
+                Ok(output.to_string())
+            );
+        }
+        assert!(cast(text("2024-03-01 24:00:00"), DataType::Timestamp).is_err());
+        assert_eq!(
+            cast(Value::Timestamp(-1), DataType::Date),
+            Ok(Value::Date(-1))
+        );
+    }
+}